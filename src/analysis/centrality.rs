@@ -0,0 +1,294 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display};
+
+use petgraph::algo::page_rank::parallel_page_rank;
+use petgraph::graph::NodeIndex;
+use rayon::prelude::*;
+
+use crate::analysis::Network;
+use crate::views::graph::VisualGraph;
+
+/// The probability of following an edge instead of jumping to a random node
+/// used for [`Metric::PageRank`].
+const DAMPING_FACTOR: f64 = 0.85;
+/// The most iterations of `PageRank`, which usually converges long before.
+const PAGE_RANK_ITERATIONS: usize = 100;
+/// The most rounds of label propagation, which usually settles long before.
+const LABEL_PROPAGATION_ROUNDS: usize = 50;
+
+/// How important a node is by the different measures.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeMetrics {
+    /// The id of the node.
+    pub id: usize,
+    pub degree: usize,
+    /// The share of shortest paths between other nodes passing through the
+    /// node.
+    pub betweenness: f64,
+    /// How close the node is to all nodes it can reach.
+    pub closeness: f64,
+    pub page_rank: f64,
+    /// The connected component of the node, the largest one is 0.
+    pub component: usize,
+    /// The community of the node, the largest one is 0.
+    pub community: usize,
+}
+
+/// A measure of [`NodeMetrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Degree,
+    Betweenness,
+    Closeness,
+    PageRank,
+    Component,
+    Community,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::Degree,
+        Metric::Betweenness,
+        Metric::Closeness,
+        Metric::PageRank,
+        Metric::Component,
+        Metric::Community,
+    ];
+
+    /// The value of the measure for a node.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn value(self, metrics: &NodeMetrics) -> f64 {
+        match self {
+            Metric::Degree => metrics.degree as f64,
+            Metric::Betweenness => metrics.betweenness,
+            Metric::Closeness => metrics.closeness,
+            Metric::PageRank => metrics.page_rank,
+            Metric::Component => metrics.component as f64,
+            Metric::Community => metrics.community as f64,
+        }
+    }
+
+    /// Whether the measure groups nodes instead of ranking them.
+    #[must_use]
+    pub fn is_group(self) -> bool {
+        matches!(self, Metric::Component | Metric::Community)
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::Degree => write!(f, "Degree"),
+            Metric::Betweenness => write!(f, "Betweenness"),
+            Metric::Closeness => write!(f, "Closeness"),
+            Metric::PageRank => write!(f, "PageRank"),
+            Metric::Component => write!(f, "Component"),
+            Metric::Community => write!(f, "Community"),
+        }
+    }
+}
+
+/// Compute all measures for every node of a graph, treating edges as
+/// undirected.
+///
+/// This takes a while for large graphs and should be run off the UI thread.
+/// The measures are computed on all cores.
+#[must_use]
+pub fn analyze(graph: &VisualGraph) -> Vec<NodeMetrics> {
+    let network = Network::new(graph, |_| true);
+    let betweenness = betweenness(&network);
+    let closeness = closeness(&network);
+    let page_rank = parallel_page_rank(&network.graph, DAMPING_FACTOR, PAGE_RANK_ITERATIONS, None);
+    let components = components(&network);
+    let communities = communities(&network);
+
+    network
+        .graph
+        .node_indices()
+        .map(|index| {
+            let i = index.index();
+            NodeMetrics {
+                id: network.id(index),
+                degree: network.graph.neighbors(index).count(),
+                betweenness: betweenness[i],
+                closeness: closeness[i],
+                page_rank: page_rank[i],
+                component: components[i],
+                community: communities[i],
+            }
+        })
+        .collect()
+}
+
+/// The shortest paths from a node to all others: the nodes in the order they
+/// were reached, their distances, how many shortest paths reach them and the
+/// nodes before them on these paths.
+struct ShortestPaths {
+    order: Vec<NodeIndex>,
+    distances: Vec<Option<usize>>,
+    counts: Vec<f64>,
+    predecessors: Vec<Vec<NodeIndex>>,
+}
+
+fn shortest_paths(network: &Network, source: NodeIndex) -> ShortestPaths {
+    let n = network.graph.node_count();
+    let mut paths = ShortestPaths {
+        order: Vec::new(),
+        distances: vec![None; n],
+        counts: vec![0.0; n],
+        predecessors: vec![Vec::new(); n],
+    };
+    paths.distances[source.index()] = Some(0);
+    paths.counts[source.index()] = 1.0;
+
+    let mut queue = VecDeque::from([source]);
+    while let Some(node) = queue.pop_front() {
+        paths.order.push(node);
+        let distance = paths.distances[node.index()].unwrap_or_default() + 1;
+        for neighbor in network.graph.neighbors(node) {
+            let i = neighbor.index();
+            if paths.distances[i].is_none() {
+                paths.distances[i] = Some(distance);
+                queue.push_back(neighbor);
+            }
+            if paths.distances[i] == Some(distance) {
+                paths.counts[i] += paths.counts[node.index()];
+                paths.predecessors[i].push(node);
+            }
+        }
+    }
+    paths
+}
+
+/// Brandes' algorithm, normalized by the number of pairs of other nodes.
+#[allow(clippy::cast_precision_loss)]
+fn betweenness(network: &Network) -> Vec<f64> {
+    let n = network.graph.node_count();
+    let indices: Vec<NodeIndex> = network.graph.node_indices().collect();
+    let mut betweenness = indices
+        .par_iter()
+        .fold(
+            || vec![0.0; n],
+            |mut betweenness, &source| {
+                let paths = shortest_paths(network, source);
+                let mut dependencies = vec![0.0; n];
+                for &node in paths.order.iter().rev() {
+                    let i = node.index();
+                    for &predecessor in &paths.predecessors[i] {
+                        let p = predecessor.index();
+                        dependencies[p] +=
+                            paths.counts[p] / paths.counts[i] * (1.0 + dependencies[i]);
+                    }
+                    if node != source {
+                        betweenness[i] += dependencies[i];
+                    }
+                }
+                betweenness
+            },
+        )
+        .reduce(
+            || vec![0.0; n],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                a
+            },
+        );
+
+    // every path was counted from both ends
+    let pairs = (n.saturating_sub(1) * n.saturating_sub(2)) as f64;
+    if pairs > 0.0 {
+        for b in &mut betweenness {
+            *b /= pairs;
+        }
+    }
+    betweenness
+}
+
+/// The closeness by Wasserman and Faust, which scales the closeness within the
+/// reachable part of the graph by its size, so nodes of small components don't
+/// look central.
+#[allow(clippy::cast_precision_loss)]
+fn closeness(network: &Network) -> Vec<f64> {
+    let n = network.graph.node_count();
+    let indices: Vec<NodeIndex> = network.graph.node_indices().collect();
+    indices
+        .par_iter()
+        .map(|&source| {
+            let paths = shortest_paths(network, source);
+            let total: usize = paths.distances.iter().flatten().sum();
+            let reached = (paths.order.len() - 1) as f64;
+            if total == 0 {
+                0.0
+            } else {
+                reached / total as f64 * reached / (n - 1) as f64
+            }
+        })
+        .collect()
+}
+
+/// Number groups of nodes by their size, the largest group first.
+fn by_size(groups: &[usize]) -> Vec<usize> {
+    let mut sizes: HashMap<usize, usize> = HashMap::new();
+    for &group in groups {
+        *sizes.entry(group).or_default() += 1;
+    }
+    let mut order: Vec<(usize, usize)> = sizes.into_iter().collect();
+    order.sort_unstable_by(|(a, a_size), (b, b_size)| b_size.cmp(a_size).then(a.cmp(b)));
+    let numbers: HashMap<usize, usize> = order
+        .into_iter()
+        .enumerate()
+        .map(|(number, (group, _))| (group, number))
+        .collect();
+    groups.iter().map(|group| numbers[group]).collect()
+}
+
+fn components(network: &Network) -> Vec<usize> {
+    let n = network.graph.node_count();
+    let mut components = vec![usize::MAX; n];
+    for start in network.graph.node_indices() {
+        if components[start.index()] != usize::MAX {
+            continue;
+        }
+        components[start.index()] = start.index();
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for neighbor in network.graph.neighbors(node) {
+                if components[neighbor.index()] == usize::MAX {
+                    components[neighbor.index()] = start.index();
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    by_size(&components)
+}
+
+/// Label propagation: every node joins the community most of its neighbors
+/// belong to until no node changes its community anymore. Ties go to the
+/// community with the lowest label, which makes the result reproducible.
+fn communities(network: &Network) -> Vec<usize> {
+    let mut labels: Vec<usize> = (0..network.graph.node_count()).collect();
+    for _ in 0..LABEL_PROPAGATION_ROUNDS {
+        let mut changed = false;
+        for node in network.graph.node_indices() {
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for neighbor in network.graph.neighbors(node) {
+                *counts.entry(labels[neighbor.index()]).or_default() += 1;
+            }
+            let best = counts
+                .into_iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)));
+            if let Some((label, _)) = best {
+                if label != labels[node.index()] {
+                    labels[node.index()] = label;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    by_size(&labels)
+}
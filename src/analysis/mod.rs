@@ -0,0 +1,50 @@
+pub mod centrality;
+pub mod paths;
+pub mod query;
+pub mod timeline;
+
+use std::collections::HashMap;
+
+use petgraph::graph::{NodeIndex, UnGraph};
+
+use crate::views::graph::{VisualEdge, VisualGraph};
+
+/// The investigation as an undirected petgraph graph, as relationships between
+/// entities are usually followed both ways when analyzing them.
+pub struct Network {
+    /// The graph, whose node weights are the ids of the nodes of the
+    /// investigation. Nodes connected by several edges are connected once.
+    pub graph: UnGraph<usize, ()>,
+    /// The index of every node in [`Network::graph`], by its id.
+    pub indices: HashMap<usize, NodeIndex>,
+}
+
+impl Network {
+    /// Build the network from the nodes of a graph and the edges to keep.
+    #[must_use]
+    pub fn new(graph: &VisualGraph, keep: impl Fn(&VisualEdge) -> bool) -> Self {
+        let mut network = UnGraph::with_capacity(graph.nodes.len(), graph.edges.len());
+        let indices: HashMap<usize, NodeIndex> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.id, network.add_node(node.id)))
+            .collect();
+        for edge in graph.edges.iter().filter(|edge| keep(edge)) {
+            if let (Some(&from), Some(&to)) = (indices.get(&edge.from), indices.get(&edge.to)) {
+                if from != to {
+                    network.update_edge(from, to, ());
+                }
+            }
+        }
+        Self {
+            graph: network,
+            indices,
+        }
+    }
+
+    /// The id of the node at an index.
+    #[must_use]
+    pub fn id(&self, index: NodeIndex) -> usize {
+        self.graph[index]
+    }
+}
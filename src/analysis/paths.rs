@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display};
+
+use petgraph::algo;
+use petgraph::graph::NodeIndex;
+
+use crate::analysis::Network;
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph};
+
+/// The most paths [`PathMode::AllSimple`] finds, as their number grows
+/// exponentially with their length in dense graphs.
+pub const MAX_PATHS: usize = 1000;
+
+/// How the selected entities are connected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathMode {
+    /// The shortest path between every pair of entities.
+    #[default]
+    Shortest,
+    /// All paths between every pair of entities that visit no node twice.
+    AllSimple,
+    /// A small subgraph connecting all entities, like a Steiner tree.
+    Connecting,
+}
+
+impl PathMode {
+    pub const ALL: [PathMode; 3] = [
+        PathMode::Shortest,
+        PathMode::AllSimple,
+        PathMode::Connecting,
+    ];
+}
+
+impl Display for PathMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathMode::Shortest => write!(f, "Shortest paths"),
+            PathMode::AllSimple => write!(f, "All paths"),
+            PathMode::Connecting => write!(f, "Connecting subgraph"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathOptions {
+    pub mode: PathMode,
+    /// The most edges a path of [`PathMode::AllSimple`] may have.
+    pub max_length: usize,
+    /// Edges of these kinds aren't followed.
+    pub ignored_kinds: Vec<EdgeKind>,
+    /// Nodes with more edges than this, like big hosting providers, aren't
+    /// passed through. Paths can still start or end at them.
+    pub max_degree: Option<usize>,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            mode: PathMode::default(),
+            max_length: 4,
+            ignored_kinds: Vec::new(),
+            max_degree: None,
+        }
+    }
+}
+
+/// The nodes and edges connecting entities.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Connection {
+    /// The ids of the nodes on the paths.
+    pub nodes: HashSet<usize>,
+    /// The pairs of connected node ids, the smaller id first.
+    pub edges: HashSet<(usize, usize)>,
+    /// How many paths were found.
+    pub paths: usize,
+}
+
+impl Connection {
+    /// Whether an edge is on one of the paths.
+    #[must_use]
+    pub fn has_edge(&self, edge: &VisualEdge) -> bool {
+        self.edges.contains(&pair(edge.from, edge.to))
+    }
+
+    fn add_path(&mut self, network: &Network, path: &[NodeIndex]) {
+        self.nodes
+            .extend(path.iter().map(|&index| network.id(index)));
+        for step in path.windows(2) {
+            self.edges
+                .insert(pair(network.id(step[0]), network.id(step[1])));
+        }
+        self.paths += 1;
+    }
+}
+
+fn pair(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Find how the nodes with the given ids are connected.
+///
+/// Edges are followed in both directions, so e.g. two domains resolving to the
+/// same IP are connected through it.
+#[must_use]
+pub fn connect(graph: &VisualGraph, ids: &[usize], options: &PathOptions) -> Connection {
+    let is_followed = |edge: &VisualEdge| !options.ignored_kinds.contains(&edge.kind);
+    let mut degrees: HashMap<usize, usize> = HashMap::new();
+    for edge in graph.edges.iter().filter(|edge| is_followed(edge)) {
+        *degrees.entry(edge.from).or_default() += 1;
+        *degrees.entry(edge.to).or_default() += 1;
+    }
+    // hubs are cut off from the graph, unless they are to be connected
+    let is_passable = |id: usize| {
+        ids.contains(&id)
+            || options
+                .max_degree
+                .is_none_or(|max| degrees.get(&id).copied().unwrap_or_default() <= max)
+    };
+    let network = Network::new(graph, |edge| {
+        is_followed(edge) && is_passable(edge.from) && is_passable(edge.to)
+    });
+    let terminals: Vec<NodeIndex> = ids
+        .iter()
+        .filter_map(|id| network.indices.get(id).copied())
+        .collect();
+
+    let mut connection = Connection::default();
+    match options.mode {
+        PathMode::Shortest => {
+            for (i, &from) in terminals.iter().enumerate() {
+                for &to in &terminals[i + 1..] {
+                    let path = algo::astar(&network.graph, from, |n| n == to, |_| 1, |_| 0);
+                    if let Some((_, path)) = path {
+                        connection.add_path(&network, &path);
+                    }
+                }
+            }
+        }
+        PathMode::AllSimple => {
+            for (i, &from) in terminals.iter().enumerate() {
+                for &to in &terminals[i + 1..] {
+                    let max_intermediate = options.max_length.saturating_sub(1);
+                    let paths = algo::all_simple_paths::<Vec<_>, _>(
+                        &network.graph,
+                        from,
+                        to,
+                        0,
+                        Some(max_intermediate),
+                    );
+                    for path in paths.take(MAX_PATHS.saturating_sub(connection.paths)) {
+                        connection.add_path(&network, &path);
+                    }
+                }
+            }
+        }
+        PathMode::Connecting => connect_all(&network, &terminals, &mut connection),
+    }
+    connection
+}
+
+/// Grow a tree from the first terminal by repeatedly adding the shortest path
+/// to the closest terminal that isn't connected yet, which approximates the
+/// smallest tree connecting all terminals.
+fn connect_all(network: &Network, terminals: &[NodeIndex], connection: &mut Connection) {
+    let Some(&first) = terminals.first() else {
+        return;
+    };
+    let mut tree = HashSet::from([first]);
+    connection.nodes.insert(network.id(first));
+    let mut remaining: HashSet<NodeIndex> = terminals[1..].iter().copied().collect();
+    remaining.remove(&first);
+
+    while !remaining.is_empty() {
+        // a breadth first search from the whole tree reaches the closest terminal first
+        let mut previous: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue: VecDeque<NodeIndex> = tree.iter().copied().collect();
+        let mut found = None;
+        while let Some(node) = queue.pop_front() {
+            if remaining.contains(&node) {
+                found = Some(node);
+                break;
+            }
+            for neighbor in network.graph.neighbors(node) {
+                if !tree.contains(&neighbor) && !previous.contains_key(&neighbor) {
+                    previous.insert(neighbor, node);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        // the other terminals can't be reached from the tree
+        let Some(terminal) = found else {
+            return;
+        };
+
+        let mut path = vec![terminal];
+        let mut node = terminal;
+        while let Some(&next) = previous.get(&node) {
+            path.push(next);
+            node = next;
+        }
+        connection.add_path(network, &path);
+        tree.extend(path);
+        remaining.remove(&terminal);
+    }
+}
@@ -0,0 +1,622 @@
+use std::collections::{HashMap, HashSet};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use regex::Regex;
+
+use crate::entity;
+use crate::error::Error;
+use crate::views::graph::{EdgeKind, VisualGraph, VisualNode};
+
+/// The most edges a relationship without an upper bound, like `-[*]-`, spans.
+pub const MAX_HOPS: usize = 10;
+
+/// A pattern of entities and the relationships between them, written like
+/// Cypher:
+///
+/// ```text
+/// (e:EmailAddress)--(Domain)--(DNSEntry {recordtype = MX})-[resolves-to]->(IP {value = "203.0.113.5"}) RETURN e
+/// ```
+///
+/// - `(Domain)` matches entities of a type, `(*)` or `()` any entity and
+///   `(d:Domain)` names the entity `d` for `RETURN`.
+/// - `{field = value}` compares a field, case insensitively. The fields are
+///   the ones shown in the info column without spaces, e.g. `recordtype`,
+///   plus `type` and `value`. Annotations are compared with `notes`, `tag`,
+///   `confidence` and the names of their properties, e.g. `{tag = suspect}`.
+///   `!=` excludes values, `~` looks for a part of the value and `=~` matches
+///   a regular expression.
+/// - `--` follows edges both ways, `-->` and `<--` only in one direction.
+///   `-[resolves-to|related]->` only follows edges of these kinds and
+///   `-[*1..3]-` spans one to three edges.
+/// - `RETURN e, d` picks the entities that are the result, by default all
+///   matched entities are.
+#[derive(Debug, Clone)]
+pub struct Query {
+    nodes: Vec<NodePattern>,
+    /// The relationships between neighboring entities of the pattern.
+    relations: Vec<RelationPattern>,
+    /// The indices of the entities that are the result.
+    returns: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct NodePattern {
+    variable: Option<String>,
+    /// The type name, `None` to match any type.
+    entity_type: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    /// The normalized name of the field.
+    field: String,
+    operator: Operator,
+    value: String,
+}
+
+#[derive(Debug, Clone)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+    Matches(Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Forward,
+    Backward,
+    Either,
+}
+
+#[derive(Debug, Clone)]
+struct RelationPattern {
+    /// The kinds of the edges, empty to follow all kinds.
+    kinds: Vec<EdgeKind>,
+    direction: Direction,
+    min_hops: usize,
+    max_hops: usize,
+}
+
+/// The entities and edges a query matched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    /// The ids of the matched entities.
+    pub nodes: HashSet<usize>,
+    /// The pairs of node ids connected by matched edges, the smaller id first.
+    /// Edges spanned by relationships over several edges aren't included.
+    pub edges: HashSet<(usize, usize)>,
+}
+
+impl QueryResult {
+    /// Whether the edge between two nodes was matched.
+    #[must_use]
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.edges.contains(&(from.min(to), from.max(to)))
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        Parser::new(query)?.query()
+    }
+}
+
+impl Query {
+    /// Find all entities and edges of a graph matching the pattern.
+    #[must_use]
+    pub fn run(&self, graph: &VisualGraph) -> QueryResult {
+        let adjacency = Adjacency::new(graph);
+
+        // the entities that can be reached from a match of the start of the pattern
+        let mut reached: Vec<HashSet<usize>> = Vec::with_capacity(self.nodes.len());
+        for (i, pattern) in self.nodes.iter().enumerate() {
+            let candidates = match i.checked_sub(1) {
+                None => graph.nodes.iter().map(|n| n.id).collect(),
+                Some(previous) => {
+                    adjacency.step(&reached[previous], &self.relations[previous], false)
+                }
+            };
+            reached.push(
+                graph
+                    .nodes
+                    .iter()
+                    .filter(|n| candidates.contains(&n.id) && pattern.matches(n))
+                    .map(|n| n.id)
+                    .collect(),
+            );
+        }
+
+        // of these only the ones from which the end can be reached are part of a match
+        for i in (0..self.relations.len()).rev() {
+            let back = adjacency.step(&reached[i + 1], &self.relations[i], true);
+            reached[i].retain(|id| back.contains(id));
+        }
+
+        let mut result = QueryResult::default();
+        for &i in &self.returns {
+            result.nodes.extend(&reached[i]);
+        }
+        for (i, relation) in self.relations.iter().enumerate() {
+            let is_returned = self.returns.contains(&i) && self.returns.contains(&(i + 1));
+            if !is_returned || relation.min_hops != 1 || relation.max_hops != 1 {
+                continue;
+            }
+            for edge in &graph.edges {
+                let forward = reached[i].contains(&edge.from) && reached[i + 1].contains(&edge.to);
+                let backward = reached[i].contains(&edge.to) && reached[i + 1].contains(&edge.from);
+                let is_matched = match relation.direction {
+                    Direction::Forward => forward,
+                    Direction::Backward => backward,
+                    Direction::Either => forward || backward,
+                };
+                if is_matched && relation.follows(&edge.kind) {
+                    result
+                        .edges
+                        .insert((edge.from.min(edge.to), edge.from.max(edge.to)));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl NodePattern {
+    fn matches(&self, node: &VisualNode) -> bool {
+        self.entity_type
+            .as_ref()
+            .is_none_or(|t| t.eq_ignore_ascii_case(entity::type_name(&node.node)))
+            && self.conditions.iter().all(|c| c.matches(node))
+    }
+}
+
+impl Condition {
+    fn matches(&self, node: &VisualNode) -> bool {
+        let values: Vec<String> = match self.field.as_str() {
+            "type" => vec![entity::type_name(&node.node).to_string()],
+            "value" => vec![entity::label(&node.node)],
+            "notes" => vec![node.annotations.notes.clone()],
+            "tag" | "tags" => node.annotations.tags.clone(),
+            "confidence" => vec![node.annotations.confidence.name().to_string()],
+            field => entity::fields(&node.node)
+                .into_iter()
+                .chain(
+                    node.annotations
+                        .properties
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.clone())),
+                )
+                .filter(|(name, _)| normalize(name) == field)
+                .map(|(_, value)| value)
+                .collect(),
+        };
+        let value = self.value.to_lowercase();
+        match &self.operator {
+            Operator::Equals => values.iter().any(|v| v.to_lowercase() == value),
+            Operator::NotEquals => values.iter().all(|v| v.to_lowercase() != value),
+            Operator::Contains => values.iter().any(|v| v.to_lowercase().contains(&value)),
+            Operator::Matches(regex) => values.iter().any(|v| regex.is_match(v)),
+        }
+    }
+}
+
+impl RelationPattern {
+    fn follows(&self, kind: &EdgeKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(kind)
+    }
+}
+
+/// The name of a field without spaces and in lowercase, e.g. `recordtype`.
+fn normalize(field: &str) -> String {
+    field
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The edges of a graph by the nodes they start and end at.
+struct Adjacency<'a> {
+    outgoing: HashMap<usize, Vec<(usize, &'a EdgeKind)>>,
+    incoming: HashMap<usize, Vec<(usize, &'a EdgeKind)>>,
+}
+
+impl<'a> Adjacency<'a> {
+    fn new(graph: &'a VisualGraph) -> Self {
+        let mut adjacency = Self {
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+        };
+        for edge in &graph.edges {
+            adjacency
+                .outgoing
+                .entry(edge.from)
+                .or_default()
+                .push((edge.to, &edge.kind));
+            adjacency
+                .incoming
+                .entry(edge.to)
+                .or_default()
+                .push((edge.from, &edge.kind));
+        }
+        adjacency
+    }
+
+    /// The nodes reachable from any of the given nodes over a relationship,
+    /// or the nodes it can be reached from if `reverse` is set.
+    fn step(
+        &self,
+        from: &HashSet<usize>,
+        relation: &RelationPattern,
+        reverse: bool,
+    ) -> HashSet<usize> {
+        let (forward, backward) = match (relation.direction, reverse) {
+            (Direction::Either, _) => (true, true),
+            (Direction::Forward, false) | (Direction::Backward, true) => (true, false),
+            (Direction::Forward, true) | (Direction::Backward, false) => (false, true),
+        };
+
+        let mut reached = HashSet::new();
+        let mut layer = from.clone();
+        for hops in 1..=relation.max_hops {
+            let mut next = HashSet::new();
+            for id in &layer {
+                let outgoing = self.outgoing.get(id).filter(|_| forward);
+                let incoming = self.incoming.get(id).filter(|_| backward);
+                for (neighbor, kind) in outgoing.into_iter().chain(incoming).flatten() {
+                    if relation.follows(kind) {
+                        next.insert(*neighbor);
+                    }
+                }
+            }
+            if hops >= relation.min_hops {
+                reached.extend(&next);
+            }
+            if next.is_empty() {
+                break;
+            }
+            layer = next;
+        }
+        reached
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    OpenBrace,
+    CloseBrace,
+    OpenBracket,
+    CloseBracket,
+    Colon,
+    Comma,
+    Pipe,
+    Star,
+    Less,
+    Greater,
+    Dash,
+    Equals,
+    NotEquals,
+    Tilde,
+    RegexMatch,
+    Word(String),
+    Text(String),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidQuery(reason.into())
+}
+
+fn is_word_start(c: char) -> bool {
+    c.is_alphanumeric() || "_.@+/".contains(c)
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '|' => Token::Pipe,
+            '*' => Token::Star,
+            '<' => Token::Less,
+            '>' => Token::Greater,
+            '-' => Token::Dash,
+            '~' => Token::Tilde,
+            '=' if chars.next_if_eq(&'~').is_some() => Token::RegexMatch,
+            '=' => Token::Equals,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::NotEquals,
+            '"' | '\'' => Token::Text(quoted(&mut chars, c)?),
+            c if is_word_start(c) => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|&c| is_word_start(c) || c == '-') {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            c => return Err(invalid(format!("unexpected '{c}'"))),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// The rest of a quoted text, in which `\` escapes the quote. Other
+/// backslashes are kept for regular expressions.
+fn quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String, Error> {
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next_if_eq(&quote).is_some() => text.push(quote),
+            c if c == quote => return Ok(text),
+            c => text.push(c),
+        }
+    }
+    Err(invalid("a quote isn't closed"))
+}
+
+impl Parser {
+    fn new(query: &str) -> Result<Self, Error> {
+        Ok(Self {
+            tokens: tokenize(query)?,
+            position: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let is_next = self.peek() == Some(token);
+        if is_next {
+            self.position += 1;
+        }
+        is_next
+    }
+
+    fn expect(&mut self, token: &Token, what: &str) -> Result<(), Error> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(invalid(format!("expected {what}")))
+        }
+    }
+
+    fn word(&mut self, what: &str) -> Result<String, Error> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            _ => Err(invalid(format!("expected {what}"))),
+        }
+    }
+
+    fn query(mut self) -> Result<Query, Error> {
+        let mut nodes = vec![self.node()?];
+        let mut relations = Vec::new();
+        while matches!(self.peek(), Some(Token::Dash | Token::Less)) {
+            relations.push(self.relation()?);
+            nodes.push(self.node()?);
+        }
+
+        let returns = match self.next() {
+            None => (0..nodes.len()).collect(),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("return") => {
+                let mut returns = Vec::new();
+                loop {
+                    let variable = self.word("a name after RETURN")?;
+                    let index = nodes
+                        .iter()
+                        .position(|n| n.variable.as_ref() == Some(&variable))
+                        .ok_or_else(|| {
+                            invalid(format!("'{variable}' isn't named in the pattern"))
+                        })?;
+                    returns.push(index);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                if self.peek().is_some() {
+                    return Err(invalid("expected the end after RETURN"));
+                }
+                returns
+            }
+            Some(_) => return Err(invalid("expected a relationship or RETURN")),
+        };
+        Ok(Query {
+            nodes,
+            relations,
+            returns,
+        })
+    }
+
+    /// `(name:Type {field = value, …})`
+    fn node(&mut self) -> Result<NodePattern, Error> {
+        self.expect(&Token::Open, "'(' before an entity")?;
+        let mut pattern = NodePattern::default();
+        let name = match self.peek() {
+            Some(Token::Word(_)) => Some(self.word("a type")?),
+            Some(Token::Star) => {
+                self.position += 1;
+                None
+            }
+            _ => None,
+        };
+        if self.eat(&Token::Colon) {
+            pattern.variable = name;
+            if !self.eat(&Token::Star) {
+                pattern.entity_type = Some(self.word("a type after ':'")?);
+            }
+        } else {
+            pattern.entity_type = name;
+        }
+
+        if self.eat(&Token::OpenBrace) {
+            loop {
+                pattern.conditions.push(self.condition()?);
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Token::CloseBrace, "'}' after the conditions")?;
+        }
+        self.expect(&Token::Close, "')' after an entity")?;
+        Ok(pattern)
+    }
+
+    /// `field = value`
+    fn condition(&mut self) -> Result<Condition, Error> {
+        let field = normalize(&self.word("a field name")?);
+        let operator = self.next();
+        let Some(Token::Word(value) | Token::Text(value)) = self.next() else {
+            return Err(invalid(format!("expected a value for '{field}'")));
+        };
+        let operator = match operator {
+            Some(Token::Equals) => Operator::Equals,
+            Some(Token::NotEquals) => Operator::NotEquals,
+            Some(Token::Tilde) => Operator::Contains,
+            Some(Token::RegexMatch) => Operator::Matches(
+                Regex::new(&value).map_err(|e| invalid(format!("bad regular expression: {e}")))?,
+            ),
+            _ => return Err(invalid(format!("expected =, !=, ~ or =~ after '{field}'"))),
+        };
+        Ok(Condition {
+            field,
+            operator,
+            value,
+        })
+    }
+
+    /// `--`, `-->`, `<--` or these with `[kind|kind*min..max]` between the
+    /// dashes.
+    fn relation(&mut self) -> Result<RelationPattern, Error> {
+        let backward = self.eat(&Token::Less);
+        self.expect(&Token::Dash, "'-' in a relationship")?;
+        let mut relation = RelationPattern {
+            kinds: Vec::new(),
+            direction: Direction::Either,
+            min_hops: 1,
+            max_hops: 1,
+        };
+
+        if self.eat(&Token::OpenBracket) {
+            self.eat(&Token::Colon);
+            if let Some(Token::Word(_)) = self.peek() {
+                loop {
+                    relation
+                        .kinds
+                        .push(EdgeKind::from_name(&self.word("an edge kind")?));
+                    if !self.eat(&Token::Pipe) {
+                        break;
+                    }
+                }
+            }
+            if self.eat(&Token::Star) {
+                (relation.min_hops, relation.max_hops) = self.hops()?;
+            }
+            self.expect(&Token::CloseBracket, "']' in a relationship")?;
+        }
+
+        self.expect(&Token::Dash, "'-' in a relationship")?;
+        let forward = self.eat(&Token::Greater);
+        relation.direction = match (backward, forward) {
+            (false, false) => Direction::Either,
+            (false, true) => Direction::Forward,
+            (true, false) => Direction::Backward,
+            (true, true) => return Err(invalid("a relationship can't point both ways")),
+        };
+        Ok(relation)
+    }
+
+    /// `min..max`, `count` or nothing after the `*` of a relationship.
+    fn hops(&mut self) -> Result<(usize, usize), Error> {
+        let Some(Token::Word(range)) = self.peek().cloned() else {
+            return Ok((1, MAX_HOPS));
+        };
+        self.position += 1;
+        let parse = |hops: &str, default: usize| {
+            if hops.is_empty() {
+                return Ok(default);
+            }
+            hops.parse::<usize>()
+                .map_err(|_| invalid(format!("'{range}' isn't a number of edges")))
+        };
+        let (min, max) = if let Some((min, max)) = range.split_once("..") {
+            (parse(min, 1)?, parse(max, MAX_HOPS)?)
+        } else {
+            let hops = parse(&range, 1)?;
+            (hops, hops)
+        };
+        if min == 0 || min > max || max > MAX_HOPS {
+            return Err(invalid(format!(
+                "a relationship spans 1 to {MAX_HOPS} edges"
+            )));
+        }
+        Ok((min, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use schnuffel_types::graph::{Domain, EmailAddress, Node};
+
+    use super::Query;
+    use crate::views::graph::{EdgeKind, VisualGraph};
+
+    #[test]
+    fn returns_only_the_named_entities() {
+        let mut graph = VisualGraph::empty();
+        let email = graph.add_node(Node::EmailAddress(EmailAddress {
+            email: "jane@example.com".to_string(),
+        }));
+        let domain = graph.add_node(Node::Domain(Domain {
+            domain: "example.com".to_string(),
+        }));
+        let ip = graph.add_node(Node::IP(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 5))));
+        graph.add_edge(email, domain, EdgeKind::Related);
+        graph.add_edge(domain, ip, EdgeKind::ResolvesTo);
+
+        let query: Query = "(e:EmailAddress)--(Domain) RETURN e".parse().unwrap();
+        let result = query.run(&graph);
+        assert_eq!(result.nodes, HashSet::from([email]));
+        assert!(result.edges.is_empty());
+
+        let query: Query = "(EmailAddress)--(Domain)".parse().unwrap();
+        let result = query.run(&graph);
+        assert_eq!(result.nodes, HashSet::from([email, domain]));
+        assert!(result.has_edge(domain, email));
+    }
+
+    #[test]
+    fn relationships_span_at_least_one_edge() {
+        assert!("(a)-[*0..3]-(b)".parse::<Query>().is_err());
+        assert!("(a)-[*4..2]-(b)".parse::<Query>().is_err());
+        assert!("(a)-[*1..3]-(b)".parse::<Query>().is_ok());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::entity::{self, Entity};
+use crate::views::graph::{VisualEdge, VisualGraph, VisualNode};
+
+/// The formats of timestamps without a time zone, which are taken as UTC.
+const NAIVE_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// A span of time, including its start and end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    /// The range between two points in time, in either order.
+    #[must_use]
+    pub fn new(a: DateTime<Utc>, b: DateTime<Utc>) -> Self {
+        Self {
+            start: a.min(b),
+            end: a.max(b),
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.start <= time && time <= self.end
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// The point in time at a fraction of the range, 0 being its start and 1
+    /// its end.
+    #[must_use]
+    pub fn at(&self, fraction: f64) -> DateTime<Utc> {
+        self.start + self.part(fraction)
+    }
+
+    /// How long a fraction of the range is.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn part(&self, fraction: f64) -> Duration {
+        let milliseconds = self.duration().num_milliseconds() as f64 * fraction;
+        Duration::milliseconds(milliseconds as i64)
+    }
+
+    /// Where a point in time is in the range, 0 being its start and 1 its end.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fraction(&self, time: DateTime<Utc>) -> f64 {
+        let total = self.duration().num_milliseconds().max(1) as f64;
+        (time - self.start).num_milliseconds() as f64 / total
+    }
+
+    /// The range moved by a duration, `None` if it would be moved beyond the
+    /// times that can be represented.
+    #[must_use]
+    pub fn shifted(&self, by: Duration) -> Option<Self> {
+        Some(Self {
+            start: self.start.checked_add_signed(by)?,
+            end: self.end.checked_add_signed(by)?,
+        })
+    }
+}
+
+/// What an event of the timeline happened to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    /// The node with this id.
+    Entity(usize),
+    /// The edge between these nodes.
+    Edge(usize, usize),
+}
+
+/// Something that happened at a known time, like the registration of a domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub time: DateTime<Utc>,
+    pub subject: Subject,
+    /// What happened, e.g. the name of the property holding the time.
+    pub label: String,
+}
+
+/// Read a timestamp as written by most tools: RFC 3339, RFC 2822 as used by
+/// emails, or dates and times without a time zone, which are taken as UTC.
+#[must_use]
+pub fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    // only look closer at text starting like a date, which is most of them
+    if !text.starts_with(|c: char| c.is_ascii_digit() || c.is_ascii_alphabetic()) {
+        return None;
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Some(time) = NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    {
+        return Some(Utc.from_utc_datetime(&time));
+    }
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?))
+}
+
+/// The timestamps of an entity by what they mean, taken from the properties
+/// of generic entities and the annotated properties holding a point in time.
+#[must_use]
+pub fn entity_times(node: &VisualNode) -> Vec<(String, DateTime<Utc>)> {
+    let properties = match &node.node {
+        Entity::Generic(generic) => generic.properties.as_slice(),
+        Entity::Node(_) => &[],
+    };
+    properties
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(
+            node.annotations
+                .properties
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        )
+        .filter_map(|(name, value)| Some((name.to_string(), parse_time(value)?)))
+        .collect()
+}
+
+/// Everything in a graph that happened at a known time, the earliest first.
+#[must_use]
+pub fn events(graph: &VisualGraph) -> Vec<Event> {
+    let mut events: Vec<Event> = graph
+        .nodes
+        .iter()
+        .flat_map(|node| {
+            entity_times(node).into_iter().map(|(label, time)| Event {
+                time,
+                subject: Subject::Entity(node.id),
+                label,
+            })
+        })
+        .chain(graph.edges.iter().filter_map(|edge| {
+            Some(Event {
+                time: edge.time?,
+                subject: Subject::Edge(edge.from, edge.to),
+                label: edge.kind.name().to_string(),
+            })
+        }))
+        .collect();
+    events.sort_by_key(|event| event.time);
+    events
+}
+
+/// The time from the first to the last event, `None` if there are none.
+#[must_use]
+pub fn span(events: &[Event]) -> Option<TimeRange> {
+    Some(TimeRange::new(events.first()?.time, events.last()?.time))
+}
+
+/// A short description of the subject of an event, e.g. `Domain example.com`.
+#[must_use]
+pub fn describe(graph: &VisualGraph, subject: Subject) -> String {
+    let label = |id| {
+        graph
+            .node(id)
+            .map(|node| entity::label(&node.node))
+            .unwrap_or_default()
+    };
+    match subject {
+        Subject::Entity(id) => graph.node(id).map_or_else(String::new, |node| {
+            format!(
+                "{} {}",
+                entity::display_name(&node.node),
+                entity::label(&node.node)
+            )
+        }),
+        Subject::Edge(from, to) => format!("{} -> {}", label(from), label(to)),
+    }
+}
+
+/// The nodes that have timestamps, none of which is in a range.
+///
+/// Nodes without timestamps aren't left out, so the context of the entities
+/// in the range stays visible.
+#[must_use]
+pub fn out_of_range(graph: &VisualGraph, range: TimeRange) -> HashSet<usize> {
+    graph
+        .nodes
+        .iter()
+        .filter(|node| {
+            let times = entity_times(node);
+            !times.is_empty() && !times.iter().any(|(_, time)| range.contains(*time))
+        })
+        .map(|node| node.id)
+        .collect()
+}
+
+/// Whether an edge has a timestamp outside of a range.
+#[must_use]
+pub fn is_edge_out_of_range(edge: &VisualEdge, range: TimeRange) -> bool {
+    edge.time.is_some_and(|time| !range.contains(time))
+}
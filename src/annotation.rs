@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, Utc};
+
+/// The character separating tags when they are written as one text.
+pub const TAG_SEPARATOR: char = ',';
+
+/// How sure the analysts are about an entity, from unchecked to verified.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    #[default]
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Verified,
+}
+
+impl Confidence {
+    pub const ALL: [Confidence; 5] = [
+        Confidence::Unknown,
+        Confidence::Low,
+        Confidence::Medium,
+        Confidence::High,
+        Confidence::Verified,
+    ];
+
+    /// The name of the confidence, used in exports.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Confidence::Unknown => "unknown",
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+            Confidence::Verified => "verified",
+        }
+    }
+
+    /// Get the confidence with the given name, ignoring case.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Confidence::ALL
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+impl Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Confidence::Unknown => write!(f, "Unknown confidence"),
+            Confidence::Low => write!(f, "Low confidence"),
+            Confidence::Medium => write!(f, "Medium confidence"),
+            Confidence::High => write!(f, "High confidence"),
+            Confidence::Verified => write!(f, "Verified"),
+        }
+    }
+}
+
+/// Which plugin found an entity or a relationship, and when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// The URL the plugin was loaded from.
+    pub source: String,
+    pub time: DateTime<Utc>,
+}
+
+impl Provenance {
+    /// Something found by the plugin at an URL just now.
+    #[must_use]
+    pub fn now(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            time: Utc::now(),
+        }
+    }
+}
+
+/// What analysts (or plugins) noted about an entity, on top of its fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    pub notes: String,
+    /// Tags like `suspect` or `victim`, in the order they were added.
+    pub tags: Vec<String>,
+    pub confidence: Confidence,
+    /// Properties by their names, e.g. `first seen`.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Annotations {
+    /// Whether nothing was noted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Annotations::default()
+    }
+
+    /// Add the tags of a text separated by [`TAG_SEPARATOR`], skipping the
+    /// ones the entity has already.
+    pub fn add_tags(&mut self, text: &str) {
+        for tag in text.split(TAG_SEPARATOR).map(str::trim) {
+            if !tag.is_empty() && !self.tags.iter().any(|t| t == tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    /// The tags as one text, separated by [`TAG_SEPARATOR`].
+    #[must_use]
+    pub fn tag_text(&self) -> String {
+        self.tags.join(&format!("{TAG_SEPARATOR} "))
+    }
+
+    /// Combine these annotations with the ones of the same entity from
+    /// somewhere else, e.g. a duplicate or a newer import.
+    ///
+    /// Notes and tags are combined, the higher confidence is kept and the
+    /// properties of the other annotations replace the ones with the same name.
+    pub fn merge(&mut self, other: Annotations) {
+        if !other.notes.is_empty() && !self.notes.contains(&other.notes) {
+            if !self.notes.is_empty() {
+                self.notes.push_str("\n\n");
+            }
+            self.notes.push_str(&other.notes);
+        }
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self.confidence = self.confidence.max(other.confidence);
+        self.properties.extend(other.properties);
+    }
+
+    /// Everything that was noted as text, for searching.
+    #[must_use]
+    pub fn texts(&self) -> Vec<String> {
+        let mut texts = vec![self.notes.clone()];
+        // every entity is unknown at first, which isn't worth finding
+        if self.confidence != Confidence::Unknown {
+            texts.push(self.confidence.name().to_string());
+        }
+        texts.extend(self.tags.iter().cloned());
+        for (name, value) in &self.properties {
+            texts.push(format!("{name}: {value}"));
+        }
+        texts
+    }
+}
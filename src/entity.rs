@@ -0,0 +1,442 @@
+use std::fmt::{self, Display, Write};
+use std::net::IpAddr;
+
+use schnuffel_types::graph::{DNSRecord, Domain, EmailAddress, Node, PhoneNumber};
+use url::Url;
+
+use crate::error::Error;
+use crate::formats::email;
+
+/// An entity of an investigation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Entity {
+    /// An entity of a type schnuffel knows.
+    Node(Node),
+    /// An entity of a type only known to the tool it was imported from.
+    Generic(GenericEntity),
+}
+
+impl From<Node> for Entity {
+    fn from(node: Node) -> Self {
+        Entity::Node(node)
+    }
+}
+
+impl Entity {
+    /// The node of an entity of a known type.
+    #[must_use]
+    pub fn as_node(&self) -> Option<&Node> {
+        match self {
+            Entity::Node(node) => Some(node),
+            Entity::Generic(_) => None,
+        }
+    }
+}
+
+/// Whether two entities describe the same thing, even if they are written
+/// differently or e.g. the properties of generic entities differ because one
+/// of them is more recent.
+#[must_use]
+pub fn is_same(a: &Entity, b: &Entity) -> bool {
+    identity_key(a) == identity_key(b)
+}
+
+/// The properties that tell generic entities of a type apart when their values
+/// are the same, e.g. messages with the same subject.
+const KEY_PROPERTIES: [(&str, &[&str]); 1] = [(email::MESSAGE_TYPE, &["message-id", "date"])];
+
+/// A key that is equal for all entities describing the same thing.
+///
+/// Domains are lowercased and lose their trailing dot, IPv4 addresses mapped
+/// to IPv6 are written as IPv4, phone numbers are reduced to E.164 digits and
+/// the domains of email addresses are lowercased. Names of people and
+/// organizations are compared ignoring case and repeated whitespace. Generic
+/// entities are compared by their type and value, and the properties in
+/// [`KEY_PROPERTIES`] if their type has some.
+#[must_use]
+pub fn identity_key(entity: &Entity) -> String {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => return generic_key(generic),
+    };
+    let key = match node {
+        Node::SocialMedia { account_url, .. } => url_key(account_url.as_str()),
+        Node::IP(ip) => ip.to_canonical().to_string(),
+        Node::Person(name) | Node::Organization(name) => name
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase(),
+        Node::Domain(domain) => domain_key(&domain.domain),
+        Node::Website { url } => url_key(url.as_str()),
+        Node::DNSEntry { nameserver, record } => {
+            let data = match record {
+                DNSRecord::A(addr) => addr.to_string(),
+                DNSRecord::AAAA(addr) => addr.to_string(),
+                DNSRecord::MX(domain) => domain_key(&domain.domain),
+                DNSRecord::TXT(txt) => txt.clone(),
+                DNSRecord::SRV {
+                    service,
+                    protocol,
+                    from,
+                    to,
+                    to_port,
+                } => format!(
+                    "_{}._{}.{} {} {to_port}",
+                    service.to_lowercase(),
+                    protocol.to_lowercase(),
+                    domain_key(from),
+                    domain_key(to)
+                ),
+                DNSRecord::CNAME { from, to } => {
+                    format!("{} {}", domain_key(from), domain_key(&to.domain))
+                }
+            };
+            format!(
+                "{} {} {data}",
+                domain_key(&nameserver.domain),
+                record_type_name(record)
+            )
+        }
+        Node::PhoneNumber(number) => phone_key(&number.number),
+        Node::EmailAddress(email) => match email.email.trim().rsplit_once('@') {
+            Some((local, domain)) => format!("{local}@{}", domain_key(domain)),
+            None => email.email.trim().to_string(),
+        },
+    };
+    format!("{}:{key}", type_name(entity))
+}
+
+fn generic_key(generic: &GenericEntity) -> String {
+    let mut key = format!("{}:{}", generic.entity_type, generic.value.trim());
+    let names = KEY_PROPERTIES
+        .iter()
+        .find(|(entity_type, _)| *entity_type == generic.entity_type)
+        .map_or(&[][..], |(_, names)| names);
+    for name in names {
+        if let Some((_, value)) = generic.properties.iter().find(|(n, _)| n == name) {
+            // writing to a string can't fail
+            let _ = write!(key, "\n{name}={}", value.trim());
+        }
+    }
+    key
+}
+
+fn domain_key(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// URLs are already normalized when parsed, only a trailing dot of the host and
+/// the slash of an empty path are left to drop.
+fn url_key(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            if let Some(host) = parsed.host_str().map(domain_key) {
+                // only fails for URLs without a host, which have no dot to drop
+                let _ = parsed.set_host(Some(&host));
+            }
+            parsed.as_str().trim_end_matches('/').to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// A phone number in E.164 form, e.g. `+49301234567`, if it has a country code.
+/// National numbers are reduced to their digits.
+fn phone_key(number: &str) -> String {
+    // the trunk prefix written in international numbers, e.g. +49 (0)30
+    let number = number.trim().replace("(0)", "");
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    if number.starts_with('+') {
+        format!("+{digits}")
+    } else if let Some(digits) = digits.strip_prefix("00") {
+        // the international call prefix used in most countries
+        format!("+{digits}")
+    } else {
+        digits
+    }
+}
+
+/// An entity of a type schnuffel has no [`Node`] variant for, like the custom
+/// entities of other tools. It is kept as is so it isn't lost on import.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GenericEntity {
+    /// The name of the type in the tool the entity comes from, e.g.
+    /// `maltego.Phrase`.
+    pub entity_type: String,
+    /// The main value of the entity.
+    pub value: String,
+    /// All other properties of the entity.
+    pub properties: Vec<(String, String)>,
+}
+
+/// The entity types that can be created from a single value, like a cell of a
+/// spreadsheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    Person,
+    Organization,
+    IP,
+    Domain,
+    Website,
+    EmailAddress,
+    PhoneNumber,
+    SocialMedia,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 8] = [
+        EntityKind::Person,
+        EntityKind::Organization,
+        EntityKind::IP,
+        EntityKind::Domain,
+        EntityKind::Website,
+        EntityKind::EmailAddress,
+        EntityKind::PhoneNumber,
+        EntityKind::SocialMedia,
+    ];
+
+    /// Create an entity of this kind from a value.
+    ///
+    /// Social media accounts are created from the URL of the account, the network
+    /// is the site the account belongs to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value isn't valid for the kind, e.g. a malformed IP.
+    pub fn create(self, value: &str) -> Result<Node, Error> {
+        let value = value.trim();
+        let invalid =
+            |e: &dyn Display| Error::InvalidFormat(format!("invalid {self} {value}: {e}"));
+
+        let node = match self {
+            EntityKind::Person => Node::Person(value.to_string()),
+            EntityKind::Organization => Node::Organization(value.to_string()),
+            EntityKind::IP => Node::IP(value.parse().map_err(|e| invalid(&e))?),
+            EntityKind::Domain => Node::Domain(Domain {
+                domain: value.to_string(),
+            }),
+            EntityKind::Website => Node::Website {
+                url: value.parse().map_err(|e| invalid(&e))?,
+            },
+            EntityKind::EmailAddress => Node::EmailAddress(EmailAddress {
+                email: value.to_string(),
+            }),
+            EntityKind::PhoneNumber => Node::PhoneNumber(PhoneNumber {
+                number: value.to_string(),
+            }),
+            EntityKind::SocialMedia => {
+                let account = Url::parse(value).map_err(|e| invalid(&e))?;
+                let network = account.join("/").map_err(|e| invalid(&e))?;
+                Node::SocialMedia {
+                    social_media_url: network.as_str().parse().map_err(|e| invalid(&e))?,
+                    account_url: account.as_str().parse().map_err(|e| invalid(&e))?,
+                }
+            }
+        };
+        Ok(node)
+    }
+
+    /// The kind of a node, `None` for DNS entries, which aren't created from a
+    /// single value.
+    #[must_use]
+    pub fn of(node: &Node) -> Option<EntityKind> {
+        match node {
+            Node::Person(_) => Some(EntityKind::Person),
+            Node::Organization(_) => Some(EntityKind::Organization),
+            Node::IP(_) => Some(EntityKind::IP),
+            Node::Domain(_) => Some(EntityKind::Domain),
+            Node::Website { .. } => Some(EntityKind::Website),
+            Node::EmailAddress(_) => Some(EntityKind::EmailAddress),
+            Node::PhoneNumber(_) => Some(EntityKind::PhoneNumber),
+            Node::SocialMedia { .. } => Some(EntityKind::SocialMedia),
+            Node::DNSEntry { .. } => None,
+        }
+    }
+
+    /// Guess the kind of entity a value describes, e.g. for data from tools that
+    /// don't store the type of their entities.
+    ///
+    /// Values that don't look like anything else are taken as names of people.
+    #[must_use]
+    pub fn guess(value: &str) -> EntityKind {
+        let value = value.trim();
+        let is_phone_number = value.len() > 1
+            && value.starts_with('+')
+            && value[1..]
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')'));
+
+        if value.parse::<IpAddr>().is_ok() {
+            EntityKind::IP
+        } else if is_phone_number {
+            EntityKind::PhoneNumber
+        } else if value.contains(char::is_whitespace) {
+            EntityKind::Person
+        } else if value.contains('@') {
+            EntityKind::EmailAddress
+        } else if Url::parse(value).is_ok_and(|url| url.has_host()) {
+            EntityKind::Website
+        } else if value.contains('.') && !value.starts_with('.') && !value.ends_with('.') {
+            EntityKind::Domain
+        } else {
+            EntityKind::Person
+        }
+    }
+}
+
+impl Display for EntityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityKind::Person => write!(f, "Person"),
+            EntityKind::Organization => write!(f, "Organization"),
+            EntityKind::IP => write!(f, "IP Address"),
+            EntityKind::Domain => write!(f, "Domain"),
+            EntityKind::Website => write!(f, "Website"),
+            EntityKind::EmailAddress => write!(f, "Email Address"),
+            EntityKind::PhoneNumber => write!(f, "Phone Number"),
+            EntityKind::SocialMedia => write!(f, "Social Media"),
+        }
+    }
+}
+
+/// The name of the variant of a node, e.g. `"EmailAddress"`, or the type of a
+/// generic entity.
+#[must_use]
+pub fn type_name(entity: &Entity) -> &str {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => return &generic.entity_type,
+    };
+    match node {
+        Node::SocialMedia { .. } => "SocialMedia",
+        Node::IP(_) => "IP",
+        Node::Person(_) => "Person",
+        Node::Domain(_) => "Domain",
+        Node::Website { .. } => "Website",
+        Node::DNSEntry { .. } => "DNSEntry",
+        Node::PhoneNumber(_) => "PhoneNumber",
+        Node::EmailAddress(_) => "EmailAddress",
+        Node::Organization(_) => "Organization",
+    }
+}
+
+/// The name of the variant of a DNS record, e.g. `"CNAME"`.
+#[must_use]
+pub fn record_type_name(record: &DNSRecord) -> &'static str {
+    match record {
+        DNSRecord::A(_) => "A",
+        DNSRecord::AAAA(_) => "AAAA",
+        DNSRecord::MX(_) => "MX",
+        DNSRecord::TXT(_) => "TXT",
+        DNSRecord::SRV { .. } => "SRV",
+        DNSRecord::CNAME { .. } => "CNAME",
+    }
+}
+
+/// A short, human readable label for an entity.
+#[must_use]
+pub fn label(entity: &Entity) -> String {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => return generic.value.clone(),
+    };
+    match node {
+        Node::SocialMedia { account_url, .. } => account_url.to_string(),
+        Node::IP(ip) => ip.to_string(),
+        Node::Person(name) | Node::Organization(name) => name.clone(),
+        Node::Domain(domain) => domain.domain.clone(),
+        Node::Website { url } => url.to_string(),
+        Node::DNSEntry { record, .. } => match record {
+            DNSRecord::A(addr) => format!("A {addr}"),
+            DNSRecord::AAAA(addr) => format!("AAAA {addr}"),
+            DNSRecord::MX(domain) => format!("MX {}", domain.domain),
+            DNSRecord::TXT(txt) => format!("TXT {txt}"),
+            DNSRecord::SRV { from, to, .. } => format!("SRV {from} -> {to}"),
+            DNSRecord::CNAME { from, to } => format!("CNAME {from} -> {}", to.domain),
+        },
+        Node::PhoneNumber(number) => number.number.clone(),
+        Node::EmailAddress(email) => email.email.clone(),
+    }
+}
+
+/// The human readable name of the type of an entity, e.g. `"Email Address"`.
+#[must_use]
+pub fn display_name(entity: &Entity) -> &str {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => return &generic.entity_type,
+    };
+    match node {
+        Node::SocialMedia { .. } => "Social Media",
+        Node::IP(_) => "IP Address",
+        Node::Person(_) => "Person",
+        Node::Domain(_) => "Domain",
+        Node::Website { .. } => "Website",
+        Node::DNSEntry { .. } => "DNS Entry",
+        Node::PhoneNumber(_) => "Phone Number",
+        Node::EmailAddress(_) => "Email Address",
+        Node::Organization(_) => "Organization",
+    }
+}
+
+/// All fields of an entity with human readable names, in the order they are
+/// shown to the user.
+#[must_use]
+pub fn fields(entity: &Entity) -> Vec<(&str, String)> {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => {
+            let mut fields = vec![("Value", generic.value.clone())];
+            for (name, value) in &generic.properties {
+                fields.push((name, value.clone()));
+            }
+            return fields;
+        }
+    };
+    match node {
+        Node::SocialMedia {
+            social_media_url,
+            account_url,
+        } => vec![
+            ("Network URL", social_media_url.to_string()),
+            ("Account URL", account_url.to_string()),
+        ],
+        Node::IP(ip) => vec![("IP", ip.to_string())],
+        Node::Person(name) | Node::Organization(name) => vec![("Name", name.clone())],
+        Node::Domain(domain) => vec![("Domain", domain.domain.clone())],
+        Node::Website { url } => vec![("URL", url.to_string())],
+        Node::DNSEntry { nameserver, record } => {
+            let mut fields = vec![
+                ("NS", nameserver.domain.clone()),
+                ("Record Type", record_type_name(record).to_string()),
+            ];
+            match record {
+                DNSRecord::A(addr) => fields.push(("Address", addr.to_string())),
+                DNSRecord::AAAA(addr) => fields.push(("Address", addr.to_string())),
+                DNSRecord::MX(domain) => fields.push(("Domain", domain.domain.clone())),
+                DNSRecord::TXT(txt) => fields.push(("Text", txt.clone())),
+                DNSRecord::SRV {
+                    service,
+                    protocol,
+                    from,
+                    to,
+                    to_port,
+                } => fields.extend([
+                    ("Service", service.clone()),
+                    ("Protocol", protocol.clone()),
+                    ("From", from.clone()),
+                    ("To", to.clone()),
+                    ("To port", to_port.to_string()),
+                ]),
+                DNSRecord::CNAME { from, to } => {
+                    fields.extend([("From", from.clone()), ("To", to.domain.clone())]);
+                }
+            }
+            fields
+        }
+        Node::PhoneNumber(number) => vec![("Number", number.number.clone())],
+        Node::EmailAddress(email) => vec![("Address", email.email.clone())],
+    }
+}
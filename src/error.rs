@@ -0,0 +1,81 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::sync::Arc;
+
+/// Errors that can happen while importing or exporting investigations,
+/// querying them or running plugins on them.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The user closed a file dialog without picking a file.
+    DialogClosed,
+    /// Reading or writing a file failed.
+    Io(Arc<io::Error>),
+    /// The XML of a file could not be parsed or written.
+    Xml(quick_xml::Error),
+    /// The JSON of a file could not be parsed or written.
+    Json(Arc<serde_json::Error>),
+    /// A CSV file could not be parsed or written.
+    Csv(Arc<csv::Error>),
+    /// An archive could not be unpacked.
+    Zip(Arc<zip::result::ZipError>),
+    /// A file was readable but its content doesn't make sense.
+    InvalidFormat(String),
+    /// A query has a syntax error.
+    InvalidQuery(String),
+    /// A plugin could not be loaded or failed while running.
+    Plugin(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DialogClosed => write!(f, "no file was selected"),
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Xml(e) => write!(f, "XML error: {e}"),
+            Error::Json(e) => write!(f, "JSON error: {e}"),
+            Error::Csv(e) => write!(f, "CSV error: {e}"),
+            Error::Zip(e) => write!(f, "archive error: {e}"),
+            Error::InvalidFormat(reason) => write!(f, "invalid file: {reason}"),
+            Error::InvalidQuery(reason) => write!(f, "invalid query: {reason}"),
+            Error::Plugin(reason) => write!(f, "plugin error: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(Arc::new(e))
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        Error::Xml(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(Arc::new(e))
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv(Arc::new(e))
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(e: zip::result::ZipError) -> Self {
+        Error::Zip(Arc::new(e))
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for Error {
+    fn from(e: quick_xml::events::attributes::AttrError) -> Self {
+        Error::Xml(e.into())
+    }
+}
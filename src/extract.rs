@@ -0,0 +1,249 @@
+use std::net::{IpAddr, SocketAddr};
+
+use schnuffel_types::graph::{Domain, EmailAddress, Node};
+use url::Url;
+
+use crate::entity::EntityKind;
+
+/// The ways indicators are commonly defanged in reports so they can't be
+/// clicked, and what they stand for.
+const DEFANGED: [(&str, &str); 15] = [
+    ("hxxp", "http"),
+    ("[://]", "://"),
+    ("[:]", ":"),
+    ("[/]", "/"),
+    ("[.]", "."),
+    ("(.)", "."),
+    ("{.}", "."),
+    ("[dot]", "."),
+    ("(dot)", "."),
+    ("{dot}", "."),
+    ("[@]", "@"),
+    ("(@)", "@"),
+    ("[at]", "@"),
+    ("(at)", "@"),
+    ("{at}", "@"),
+];
+
+/// Sites whose URLs with a path are profiles of accounts.
+const SOCIAL_NETWORKS: [&str; 14] = [
+    "twitter.com",
+    "x.com",
+    "facebook.com",
+    "instagram.com",
+    "linkedin.com",
+    "github.com",
+    "gitlab.com",
+    "reddit.com",
+    "tiktok.com",
+    "youtube.com",
+    "t.me",
+    "telegram.me",
+    "mastodon.social",
+    "threads.net",
+];
+
+/// File extensions that aren't top level domains, so file names in the text
+/// aren't taken for domains.
+const FILE_EXTENSIONS: [&str; 16] = [
+    "bat", "bin", "dll", "doc", "docx", "exe", "gif", "jpg", "js", "pdf", "php", "png", "ps1",
+    "txt", "xls", "xlsx",
+];
+
+/// Characters that end a word in pasted text.
+const SEPARATORS: [char; 8] = ['"', '\'', '`', '<', '>', '|', ',', ';'];
+
+/// Find the IP addresses, domains, URLs, email addresses, phone numbers and
+/// social media profiles mentioned in a text, in the order they appear.
+///
+/// Defanged indicators like `hxxp://example[.]com` are recognized. Phone
+/// numbers are only found in international format, e.g. `+49 30 1234567`, as
+/// other numbers can't be told apart from dates or amounts.
+#[must_use]
+pub fn extract(text: &str) -> Vec<Node> {
+    let text = refang(text);
+    let mut found: Vec<(usize, Node)> = phone_numbers(&text);
+    for (position, word) in words(&text) {
+        if let Some(node) = entity(word) {
+            found.push((position, node));
+        }
+    }
+    found.sort_by_key(|(position, _)| *position);
+
+    let mut nodes: Vec<Node> = Vec::new();
+    for (_, node) in found {
+        if !nodes.contains(&node) {
+            nodes.push(node);
+        }
+    }
+    nodes
+}
+
+/// Undo the defanging of indicators.
+#[must_use]
+pub fn refang(text: &str) -> String {
+    let mut text = text.to_string();
+    for (defanged, original) in DEFANGED {
+        text = replace_ignore_case(&text, defanged, original);
+    }
+    text
+}
+
+fn replace_ignore_case(text: &str, pattern: &str, replacement: &str) -> String {
+    // ASCII lowercasing keeps all byte positions
+    let lowercase = text.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in lowercase.match_indices(pattern) {
+        out.push_str(&text[last..start]);
+        out.push_str(replacement);
+        last = start + pattern.len();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Split a text into words with their positions.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let is_separator = c.is_whitespace() || SEPARATORS.contains(&c);
+        match (start, is_separator) {
+            (Some(s), true) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            (None, false) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+    words
+}
+
+/// The entity a single word describes, if any.
+fn entity(word: &str) -> Option<Node> {
+    let word = word
+        .trim_start_matches(['(', '[', '{'])
+        .trim_end_matches([')', ']', '}', '.', '!', '?']);
+    let lowercase = word.to_ascii_lowercase();
+
+    if lowercase.starts_with("http://") || lowercase.starts_with("https://") {
+        return url(word.trim_end_matches(':'));
+    }
+    let address = lowercase.strip_prefix("mailto:").unwrap_or(&lowercase);
+    if let Some((local, domain)) = address.split_once('@') {
+        let is_email = !local.is_empty()
+            && local
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c))
+            && is_domain(domain.trim_end_matches(':'));
+        return is_email.then(|| {
+            Node::EmailAddress(EmailAddress {
+                email: address.trim_end_matches(':').to_string(),
+            })
+        });
+    }
+    if let Some(ip) = ip(word).or_else(|| ip(word.trim_end_matches(':'))) {
+        return Some(Node::IP(ip));
+    }
+
+    // a URL without a scheme still names its host
+    let host = lowercase
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(':');
+    is_domain(host).then(|| {
+        Node::Domain(Domain {
+            domain: host.to_string(),
+        })
+    })
+}
+
+/// An IP address, also with a port or as a network like `10.0.0.0/8`.
+fn ip(word: &str) -> Option<IpAddr> {
+    let address = word.split('/').next().unwrap_or(word);
+    address
+        .parse()
+        .ok()
+        .or_else(|| address.parse::<SocketAddr>().ok().map(|a| a.ip()))
+}
+
+/// A website or, for the profile pages of social networks, a social media
+/// account.
+fn url(word: &str) -> Option<Node> {
+    let url = Url::parse(word).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let site = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(&host);
+    let is_profile = SOCIAL_NETWORKS.contains(&site)
+        && url
+            .path_segments()
+            .is_some_and(|mut s| s.any(|s| !s.is_empty()));
+
+    let kind = if is_profile {
+        EntityKind::SocialMedia
+    } else {
+        EntityKind::Website
+    };
+    kind.create(url.as_str()).ok()
+}
+
+/// Whether a name looks like a domain name with a top level domain.
+fn is_domain(name: &str) -> bool {
+    let labels: Vec<&str> = name.split('.').collect();
+    let is_label = |label: &&str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    let tld = labels.last().copied().unwrap_or_default();
+    labels.len() >= 2
+        && labels.iter().all(is_label)
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && !FILE_EXTENSIONS.contains(&tld)
+}
+
+/// Phone numbers in international format with their positions.
+fn phone_numbers(text: &str) -> Vec<(usize, Node)> {
+    let mut numbers = Vec::new();
+    let mut previous = ' ';
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '+' || previous.is_alphanumeric() {
+            previous = c;
+            continue;
+        }
+        let mut end = start + 1;
+        while let Some(&(i, c)) = chars.peek() {
+            if !(c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')' | '.')) {
+                break;
+            }
+            if c.is_ascii_digit() {
+                end = i + 1;
+            }
+            chars.next();
+        }
+
+        let number = &text[start..end];
+        let digits = number.chars().filter(char::is_ascii_digit).count();
+        if (7..=15).contains(&digits) {
+            if let Ok(node) = EntityKind::PhoneNumber.create(number) {
+                numbers.push((start, node));
+            }
+        }
+        previous = text[..end].chars().last().unwrap_or(' ');
+    }
+    numbers
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::BuildHasher;
+use std::str::FromStr;
+
+use schnuffel_types::graph::{DNSRecord, Domain, EmailAddress, Node, PhoneNumber};
+
+use crate::analysis::timeline;
+use crate::annotation::{Annotations, Confidence, Provenance};
+use crate::entity::{self, Entity, GenericEntity};
+use crate::error::Error;
+use crate::views::graph::VisualGraph;
+
+/// The type of an attribute value in formats that declare their attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    String,
+    Int,
+    Double,
+}
+
+impl AttributeType {
+    /// The name of the type as used by `GraphML` and GEXF.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            AttributeType::String => "string",
+            AttributeType::Int => "int",
+            AttributeType::Double => "double",
+        }
+    }
+}
+
+/// Every attribute a node can have when it is flattened, together with its type.
+pub const NODE_ATTRIBUTES: [(&str, AttributeType); 19] = [
+    ("type", AttributeType::String),
+    ("value", AttributeType::String),
+    ("name", AttributeType::String),
+    ("ip", AttributeType::String),
+    ("domain", AttributeType::String),
+    ("url", AttributeType::String),
+    ("social_media_url", AttributeType::String),
+    ("account_url", AttributeType::String),
+    ("number", AttributeType::String),
+    ("email", AttributeType::String),
+    ("nameserver", AttributeType::String),
+    ("record_type", AttributeType::String),
+    ("address", AttributeType::String),
+    ("text", AttributeType::String),
+    ("service", AttributeType::String),
+    ("protocol", AttributeType::String),
+    ("from", AttributeType::String),
+    ("to", AttributeType::String),
+    ("to_port", AttributeType::Int),
+];
+
+/// Attributes describing how a node is drawn.
+pub const VISUAL_ATTRIBUTES: [(&str, AttributeType); 3] = [
+    ("x", AttributeType::Double),
+    ("y", AttributeType::Double),
+    ("radius", AttributeType::Double),
+];
+
+/// The prefix of the attributes holding the properties of generic entities.
+pub const PROPERTY_PREFIX: &str = "property.";
+
+/// Attributes holding what analysts noted about a node. Tags are separated by
+/// [`crate::annotation::TAG_SEPARATOR`].
+pub const ANNOTATION_ATTRIBUTES: [(&str, AttributeType); 3] = [
+    ("notes", AttributeType::String),
+    ("tags", AttributeType::String),
+    ("confidence", AttributeType::String),
+];
+
+/// The prefix of the attributes holding the annotated properties of nodes,
+/// which all entities can have unlike the properties of generic entities.
+pub const ANNOTATED_PROPERTY_PREFIX: &str = "annotation.";
+
+/// Every attribute an edge can have. The `time` is written as RFC 3339.
+pub const EDGE_ATTRIBUTES: [(&str, AttributeType); 3] = [
+    ("kind", AttributeType::String),
+    ("weight", AttributeType::Double),
+    ("time", AttributeType::String),
+];
+
+/// Attributes recording which plugin found a node or an edge. The time is
+/// written as RFC 3339.
+pub const PROVENANCE_ATTRIBUTES: [(&str, AttributeType); 2] = [
+    ("found_by", AttributeType::String),
+    ("found_at", AttributeType::String),
+];
+
+/// The attributes holding properties of generic entities and annotated
+/// properties that appear in a graph.
+///
+/// Formats that declare their attributes need these on top of [`NODE_ATTRIBUTES`]
+/// and [`ANNOTATION_ATTRIBUTES`].
+#[must_use]
+pub fn property_attributes(graph: &VisualGraph) -> Vec<String> {
+    let mut names: Vec<String> = graph
+        .nodes
+        .iter()
+        .filter_map(|node| match &node.node {
+            Entity::Generic(generic) => Some(&generic.properties),
+            Entity::Node(_) => None,
+        })
+        .flatten()
+        .map(|(name, _)| format!("{PROPERTY_PREFIX}{name}"))
+        .chain(graph.nodes.iter().flat_map(|node| {
+            node.annotations
+                .properties
+                .keys()
+                .map(|name| format!("{ANNOTATED_PROPERTY_PREFIX}{name}"))
+        }))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Flatten an entity into a list of attributes, including its `type`.
+///
+/// Generic entities keep their type, their `value` and their properties prefixed
+/// with [`PROPERTY_PREFIX`].
+#[must_use]
+pub fn node_attributes(entity: &Entity) -> Vec<(String, String)> {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => {
+            let mut attributes = vec![
+                ("type".to_string(), generic.entity_type.clone()),
+                ("value".to_string(), generic.value.clone()),
+            ];
+            for (name, value) in &generic.properties {
+                attributes.push((format!("{PROPERTY_PREFIX}{name}"), value.clone()));
+            }
+            return attributes;
+        }
+    };
+
+    let mut attributes = vec![("type", entity::type_name(entity).to_string())];
+    match node {
+        Node::SocialMedia {
+            social_media_url,
+            account_url,
+        } => {
+            attributes.push(("social_media_url", social_media_url.to_string()));
+            attributes.push(("account_url", account_url.to_string()));
+        }
+        Node::IP(ip) => attributes.push(("ip", ip.to_string())),
+        Node::Person(name) | Node::Organization(name) => attributes.push(("name", name.clone())),
+        Node::Domain(domain) => attributes.push(("domain", domain.domain.clone())),
+        Node::Website { url } => attributes.push(("url", url.to_string())),
+        Node::DNSEntry { nameserver, record } => {
+            attributes.push(("nameserver", nameserver.domain.clone()));
+            attributes.push(("record_type", entity::record_type_name(record).to_string()));
+            match record {
+                DNSRecord::A(addr) => attributes.push(("address", addr.to_string())),
+                DNSRecord::AAAA(addr) => attributes.push(("address", addr.to_string())),
+                DNSRecord::MX(domain) => attributes.push(("domain", domain.domain.clone())),
+                DNSRecord::TXT(txt) => attributes.push(("text", txt.clone())),
+                DNSRecord::SRV {
+                    service,
+                    protocol,
+                    from,
+                    to,
+                    to_port,
+                } => {
+                    attributes.push(("service", service.clone()));
+                    attributes.push(("protocol", protocol.clone()));
+                    attributes.push(("from", from.clone()));
+                    attributes.push(("to", to.clone()));
+                    attributes.push(("to_port", to_port.to_string()));
+                }
+                DNSRecord::CNAME { from, to } => {
+                    attributes.push(("from", from.clone()));
+                    attributes.push(("to", to.domain.clone()));
+                }
+            }
+        }
+        Node::PhoneNumber(number) => attributes.push(("number", number.number.clone())),
+        Node::EmailAddress(email) => attributes.push(("email", email.email.clone())),
+    }
+    attributes
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+/// Flatten annotations into attributes, leaving out the empty ones.
+#[must_use]
+pub fn annotation_attributes(annotations: &Annotations) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    if !annotations.notes.is_empty() {
+        attributes.push(("notes".to_string(), annotations.notes.clone()));
+    }
+    if !annotations.tags.is_empty() {
+        attributes.push(("tags".to_string(), annotations.tag_text()));
+    }
+    if annotations.confidence != Confidence::Unknown {
+        attributes.push((
+            "confidence".to_string(),
+            annotations.confidence.name().to_string(),
+        ));
+    }
+    for (name, value) in &annotations.properties {
+        attributes.push((format!("{ANNOTATED_PROPERTY_PREFIX}{name}"), value.clone()));
+    }
+    attributes
+}
+
+/// Rebuild annotations from attributes created by [`annotation_attributes`].
+///
+/// Missing attributes and unknown confidences are left empty.
+#[must_use]
+pub fn annotations_from_attributes<S: BuildHasher>(
+    attributes: &HashMap<String, String, S>,
+) -> Annotations {
+    let mut annotations = Annotations {
+        notes: attributes.get("notes").cloned().unwrap_or_default(),
+        confidence: attributes
+            .get("confidence")
+            .and_then(|c| Confidence::from_name(c))
+            .unwrap_or_default(),
+        properties: attributes
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    name.strip_prefix(ANNOTATED_PROPERTY_PREFIX)?.to_string(),
+                    value.clone(),
+                ))
+            })
+            .collect(),
+        ..Annotations::default()
+    };
+    if let Some(tags) = attributes.get("tags") {
+        annotations.add_tags(tags);
+    }
+    annotations
+}
+
+/// Flatten a provenance into attributes, none if nothing is known.
+#[must_use]
+pub fn provenance_attributes(provenance: Option<&Provenance>) -> Vec<(String, String)> {
+    provenance
+        .map(|provenance| {
+            vec![
+                ("found_by".to_string(), provenance.source.clone()),
+                ("found_at".to_string(), provenance.time.to_rfc3339()),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+/// Rebuild a provenance from attributes created by [`provenance_attributes`],
+/// `None` if one of them is missing or malformed.
+#[must_use]
+pub fn provenance_from_attributes<S: BuildHasher>(
+    attributes: &HashMap<String, String, S>,
+) -> Option<Provenance> {
+    Some(Provenance {
+        source: attributes.get("found_by")?.clone(),
+        time: timeline::parse_time(attributes.get("found_at")?)?,
+    })
+}
+
+/// Rebuild an entity from attributes created by [`node_attributes`].
+///
+/// Entities of unknown types become generic entities.
+///
+/// # Errors
+///
+/// Returns an error if the `type` is missing or an attribute of the type is
+/// missing or malformed.
+pub fn node_from_attributes<S: BuildHasher>(
+    attributes: &HashMap<String, String, S>,
+) -> Result<Entity, Error> {
+    let node = match get(attributes, "type")? {
+        "SocialMedia" => Node::SocialMedia {
+            social_media_url: parse(attributes, "social_media_url")?,
+            account_url: parse(attributes, "account_url")?,
+        },
+        "IP" => Node::IP(parse(attributes, "ip")?),
+        "Person" => Node::Person(get(attributes, "name")?.to_string()),
+        "Domain" => Node::Domain(Domain {
+            domain: get(attributes, "domain")?.to_string(),
+        }),
+        "Website" => Node::Website {
+            url: parse(attributes, "url")?,
+        },
+        "DNSEntry" => Node::DNSEntry {
+            nameserver: Domain {
+                domain: get(attributes, "nameserver")?.to_string(),
+            },
+            record: record_from_attributes(attributes)?,
+        },
+        "PhoneNumber" => Node::PhoneNumber(PhoneNumber {
+            number: get(attributes, "number")?.to_string(),
+        }),
+        "EmailAddress" => Node::EmailAddress(EmailAddress {
+            email: get(attributes, "email")?.to_string(),
+        }),
+        "Organization" => Node::Organization(get(attributes, "name")?.to_string()),
+        other => {
+            let mut properties: Vec<(String, String)> = attributes
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((
+                        name.strip_prefix(PROPERTY_PREFIX)?.to_string(),
+                        value.clone(),
+                    ))
+                })
+                .collect();
+            properties.sort();
+            return Ok(Entity::Generic(GenericEntity {
+                entity_type: other.to_string(),
+                value: attributes.get("value").cloned().unwrap_or_default(),
+                properties,
+            }));
+        }
+    };
+    Ok(node.into())
+}
+
+fn record_from_attributes<S: BuildHasher>(
+    attributes: &HashMap<String, String, S>,
+) -> Result<DNSRecord, Error> {
+    let record = match get(attributes, "record_type")? {
+        "A" => DNSRecord::A(parse(attributes, "address")?),
+        "AAAA" => DNSRecord::AAAA(parse(attributes, "address")?),
+        "MX" => DNSRecord::MX(Domain {
+            domain: get(attributes, "domain")?.to_string(),
+        }),
+        "TXT" => DNSRecord::TXT(get(attributes, "text")?.to_string()),
+        "SRV" => DNSRecord::SRV {
+            service: get(attributes, "service")?.to_string(),
+            protocol: get(attributes, "protocol")?.to_string(),
+            from: get(attributes, "from")?.to_string(),
+            to: get(attributes, "to")?.to_string(),
+            to_port: parse(attributes, "to_port")?,
+        },
+        "CNAME" => DNSRecord::CNAME {
+            from: get(attributes, "from")?.to_string(),
+            to: Domain {
+                domain: get(attributes, "to")?.to_string(),
+            },
+        },
+        other => {
+            return Err(Error::InvalidFormat(format!(
+                "unknown DNS record type {other}"
+            )));
+        }
+    };
+    Ok(record)
+}
+
+fn get<'a, S: BuildHasher>(
+    attributes: &'a HashMap<String, String, S>,
+    key: &str,
+) -> Result<&'a str, Error> {
+    attributes
+        .get(key)
+        .map(String::as_str)
+        .ok_or_else(|| Error::InvalidFormat(format!("missing attribute {key}")))
+}
+
+fn parse<T, S: BuildHasher>(attributes: &HashMap<String, String, S>, key: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    get(attributes, key)?
+        .parse()
+        .map_err(|e| Error::InvalidFormat(format!("malformed attribute {key}: {e}")))
+}
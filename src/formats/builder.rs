@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use schnuffel_types::graph::{Domain, Node};
+
+use crate::entity::Entity;
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph};
+
+/// Builds a graph from data that mentions the same entities many times, like
+/// logs or DNS records, adding every entity and edge only once.
+pub struct GraphBuilder {
+    graph: VisualGraph,
+    ids: HashMap<Entity, usize>,
+    /// The index of every edge in the graph.
+    edges: HashMap<(usize, usize, EdgeKind), usize>,
+}
+
+impl GraphBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            graph: VisualGraph::empty(),
+            ids: HashMap::new(),
+            edges: HashMap::new(),
+        }
+    }
+
+    /// Get the id of an entity, adding it if it isn't in the graph yet.
+    pub fn node(&mut self, entity: impl Into<Entity>) -> usize {
+        let entity = entity.into();
+        if let Some(id) = self.ids.get(&entity) {
+            return *id;
+        }
+        let id = self.graph.add_node(entity.clone());
+        self.ids.insert(entity, id);
+        id
+    }
+
+    /// Get the id of the entity for a domain name.
+    pub fn domain(&mut self, name: &str) -> usize {
+        self.node(Node::Domain(Domain {
+            domain: name.to_string(),
+        }))
+    }
+
+    /// Connect two nodes, unless they are connected by the same kind of edge
+    /// already.
+    pub fn edge(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        self.edge_mut(from, to, kind);
+    }
+
+    /// Connect two nodes like [`GraphBuilder::edge`], keeping the earliest
+    /// time the relationship was observed at.
+    pub fn edge_at(&mut self, from: usize, to: usize, kind: EdgeKind, time: DateTime<Utc>) {
+        let edge = self.edge_mut(from, to, kind);
+        edge.time = Some(edge.time.map_or(time, |earlier| earlier.min(time)));
+    }
+
+    /// Add to the weight of the edge between two nodes, connecting them first
+    /// if needed.
+    pub fn add_weight(&mut self, from: usize, to: usize, kind: EdgeKind, weight: f64) {
+        *self.edge_mut(from, to, kind).weight.get_or_insert(0.0) += weight;
+    }
+
+    fn edge_mut(&mut self, from: usize, to: usize, kind: EdgeKind) -> &mut VisualEdge {
+        let edges = &mut self.graph.edges;
+        let index = *self
+            .edges
+            .entry((from, to, kind.clone()))
+            .or_insert_with(|| {
+                edges.push(VisualEdge {
+                    from,
+                    to,
+                    kind,
+                    weight: None,
+                    time: None,
+                    provenance: None,
+                });
+                edges.len() - 1
+            });
+        &mut edges[index]
+    }
+
+    #[must_use]
+    pub fn finish(self) -> VisualGraph {
+        self.graph
+    }
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use ::csv::{ReaderBuilder, Writer};
+
+use crate::entity::{self, EntityKind};
+use crate::error::Error;
+use crate::formats::attributes::{self, ANNOTATION_ATTRIBUTES, NODE_ATTRIBUTES};
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// The raw content of a CSV file.
+#[derive(Debug, Clone, Default)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Which columns of a table become entities and how they are connected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMapping {
+    pub column: usize,
+    pub entity: EntityKind,
+    /// A second column holding entities related to the ones of the first column.
+    pub related: Option<RelatedColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelatedColumn {
+    pub column: usize,
+    pub entity: EntityKind,
+    pub edge: EdgeKind,
+}
+
+/// Read a CSV file whose first row holds the column names.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't valid CSV.
+pub fn read(data: &[u8]) -> Result<CsvTable, Error> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader.headers()?.iter().map(str::to_string).collect();
+    let rows = reader
+        .records()
+        .map(|record| Ok(record?.iter().map(str::to_string).collect()))
+        .collect::<Result<_, Error>>()?;
+
+    Ok(CsvTable { headers, rows })
+}
+
+/// Create the entities described by a mapping.
+///
+/// Empty cells are skipped and cells with the same value become the same entity.
+///
+/// # Errors
+///
+/// Returns an error naming the row if a cell isn't a valid value for its entity
+/// kind.
+pub fn build(table: &CsvTable, mapping: &ColumnMapping) -> Result<VisualGraph, Error> {
+    let mut graph = VisualGraph::empty();
+    let mut ids: HashMap<(EntityKind, String), usize> = HashMap::new();
+
+    let mut entity_at =
+        |graph: &mut VisualGraph, row: &[String], column: usize, kind: EntityKind| {
+            let Some(value) = row.get(column).map(|v| v.trim()).filter(|v| !v.is_empty()) else {
+                return Ok(None);
+            };
+            if let Some(id) = ids.get(&(kind, value.to_string())) {
+                return Ok(Some(*id));
+            }
+            let id = graph.add_node(kind.create(value)?);
+            ids.insert((kind, value.to_string()), id);
+            Ok::<_, Error>(Some(id))
+        };
+
+    for (i, row) in table.rows.iter().enumerate() {
+        // the header is the first line of the file
+        let in_row = |e: Error| Error::InvalidFormat(format!("line {}: {e}", i + 2));
+
+        let entity = entity_at(&mut graph, row, mapping.column, mapping.entity).map_err(in_row)?;
+        let Some(related) = &mapping.related else {
+            continue;
+        };
+        let related_entity =
+            entity_at(&mut graph, row, related.column, related.entity).map_err(in_row)?;
+
+        if let (Some(from), Some(to)) = (entity, related_entity) {
+            graph.add_edge(from, to, related.edge.clone());
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Write a graph as CSV files, one per entity type plus one for the edges.
+///
+/// Returns the file names together with their content.
+///
+/// # Errors
+///
+/// Returns an error if writing the CSV fails.
+pub fn export(graph: &VisualGraph) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    // group the nodes by type, keeping the order they were added in
+    let mut groups: Vec<(&str, Vec<_>)> = Vec::new();
+    for node in &graph.nodes {
+        let type_name = entity::type_name(&node.node);
+        let attributes: HashMap<String, String> = attributes::node_attributes(&node.node)
+            .into_iter()
+            .chain(attributes::annotation_attributes(&node.annotations))
+            .collect();
+        match groups.iter_mut().find(|(name, _)| *name == type_name) {
+            Some((_, group)) => group.push((node.id, attributes)),
+            None => groups.push((type_name, vec![(node.id, attributes)])),
+        }
+    }
+
+    let properties = attributes::property_attributes(graph);
+    let mut files = Vec::new();
+    for (type_name, group) in groups {
+        // DNS entries have different fields per record type, so use all that appear
+        let columns: Vec<&str> = NODE_ATTRIBUTES
+            .iter()
+            .chain(&ANNOTATION_ATTRIBUTES)
+            .map(|(name, _)| *name)
+            .chain(properties.iter().map(String::as_str))
+            .filter(|name| *name != "type")
+            .filter(|name| group.iter().any(|(_, a)| a.contains_key(*name)))
+            .collect();
+
+        let mut writer = Writer::from_writer(Vec::new());
+        writer.write_record(std::iter::once("id").chain(columns.iter().copied()))?;
+        for (id, attributes) in &group {
+            let values = columns
+                .iter()
+                .map(|column| attributes.get(*column).map_or("", String::as_str));
+            writer.write_record(std::iter::once(id.to_string().as_str()).chain(values))?;
+        }
+        files.push((format!("{type_name}.csv"), finish(writer)?));
+    }
+
+    let mut writer = Writer::from_writer(Vec::new());
+    writer.write_record(["from", "to", "kind", "weight", "time"])?;
+    for edge in &graph.edges {
+        writer.write_record([
+            edge.from.to_string().as_str(),
+            edge.to.to_string().as_str(),
+            edge.kind.name(),
+            edge.weight
+                .map(|w| w.to_string())
+                .unwrap_or_default()
+                .as_str(),
+            edge.time
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default()
+                .as_str(),
+        ])?;
+    }
+    files.push(("edges.csv".to_string(), finish(writer)?));
+
+    Ok(files)
+}
+
+fn finish(writer: Writer<Vec<u8>>) -> Result<Vec<u8>, Error> {
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
@@ -0,0 +1,622 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use schnuffel_types::graph::Node;
+
+use crate::analysis::timeline;
+use crate::entity::{self, Entity, EntityKind};
+use crate::error::Error;
+use crate::formats::attributes;
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph, VisualNode};
+
+/// How the attributes of DOT nodes map to entities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotConvention {
+    /// The prefix of the attributes holding the fields of an entity.
+    ///
+    /// With the prefix `schnuffel_`, `schnuffel_type` holds the type of the
+    /// entity as written by [`entity::type_name`] and e.g. `schnuffel_ip` its IP.
+    pub prefix: String,
+    /// The kind of the nodes without a type attribute, whose label is taken as
+    /// their value. `None` guesses the kind from the label.
+    pub fallback: Option<EntityKind>,
+}
+
+impl Default for DotConvention {
+    fn default() -> Self {
+        Self {
+            prefix: "schnuffel_".to_string(),
+            fallback: None,
+        }
+    }
+}
+
+/// Write a graph in the DOT language of Graphviz.
+///
+/// Nodes are labeled and styled by their type and carry their fields as
+/// attributes following the default [`DotConvention`], so the file can be read
+/// back without losing anything.
+///
+/// # Errors
+///
+/// Never fails, the signature matches the other formats.
+pub fn export(graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+    let convention = DotConvention::default();
+
+    // writing to a string can't fail
+    let mut dot = String::from("digraph investigation {\n");
+    dot.push_str("  node [style=filled, fontcolor=white];\n");
+    for node in &graph.nodes {
+        let (shape, color) = style(&node.node);
+        let _ = write!(
+            dot,
+            "  n{} [label={}, shape={shape}, fillcolor=\"{color}\", pos=\"{},{}\"",
+            node.id,
+            quote(&entity::label(&node.node)),
+            node.x,
+            // Graphviz' y axis points up
+            -node.y,
+        );
+        for (name, value) in attributes::node_attributes(&node.node)
+            .into_iter()
+            .chain(attributes::annotation_attributes(&node.annotations))
+        {
+            let name = format!("{}{name}", convention.prefix);
+            let _ = write!(dot, ", {}={}", quote(&name), quote(&value));
+        }
+        dot.push_str("];\n");
+    }
+    for edge in &graph.edges {
+        let _ = write!(
+            dot,
+            "  n{} -> n{} [label={}",
+            edge.from,
+            edge.to,
+            quote(edge.kind.name())
+        );
+        if let Some(weight) = edge.weight {
+            let _ = write!(dot, ", weight={}", quote(&weight.to_string()));
+        }
+        if let Some(time) = edge.time {
+            let _ = write!(dot, ", time={}", quote(&time.to_rfc3339()));
+        }
+        dot.push_str("];\n");
+    }
+    dot.push_str("}\n");
+
+    Ok(dot.into_bytes())
+}
+
+/// Read a graph written in the DOT language with the default [`DotConvention`].
+///
+/// # Errors
+///
+/// Returns an error if the file isn't valid DOT or a node can't be turned into
+/// an entity.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    import_with(data, &DotConvention::default())
+}
+
+/// Read a graph written in the DOT language.
+///
+/// Nodes with a type attribute are rebuilt from their fields, all other nodes
+/// become entities whose value is their label. Notes, tags and the like are read
+/// from the prefixed attributes of all nodes. Edge labels become the kind of the
+/// edges.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't valid DOT or a node can't be turned into
+/// an entity.
+pub fn import_with(data: &[u8], convention: &DotConvention) -> Result<VisualGraph, Error> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| Error::InvalidFormat("the file isn't valid UTF-8".to_string()))?;
+    let parsed = Parser::new(tokenize(text)?).parse()?;
+
+    let mut graph = VisualGraph::empty();
+    let mut ids = Vec::new();
+    for (dot_id, dot_attributes) in &parsed.nodes {
+        let fields = fields_of(dot_attributes, convention);
+        let node = node_from_attributes(dot_id, dot_attributes, &fields, convention)?;
+        let annotations = attributes::annotations_from_attributes(&fields);
+
+        // keep our own ids if the file was written by us
+        let id = dot_id
+            .strip_prefix('n')
+            .and_then(|id| id.parse().ok())
+            .filter(|id| graph.node(*id).is_none())
+            .unwrap_or_else(|| graph.next_id());
+        let position = dot_attributes
+            .get("pos")
+            .and_then(|pos| {
+                let (x, y) = pos.trim_end_matches('!').split_once(',')?;
+                Some((
+                    x.trim().parse::<f32>().ok()?,
+                    -y.trim().parse::<f32>().ok()?,
+                ))
+            })
+            .unwrap_or_else(|| {
+                let free = graph.free_position();
+                (free.x, free.y)
+            });
+
+        graph.push_node(VisualNode {
+            node,
+            annotations,
+            id,
+            x: position.0,
+            y: position.1,
+            ..Default::default()
+        });
+        ids.push(id);
+    }
+
+    for (from, to, attributes) in parsed.edges {
+        graph.edges.push(VisualEdge {
+            from: ids[from],
+            to: ids[to],
+            kind: attributes
+                .get("label")
+                .map_or(EdgeKind::default(), |label| EdgeKind::from_name(label)),
+            weight: attributes
+                .get("weight")
+                .and_then(|weight| weight.parse().ok()),
+            time: attributes
+                .get("time")
+                .and_then(|time| timeline::parse_time(time)),
+            provenance: None,
+        });
+    }
+
+    Ok(graph)
+}
+
+/// The attributes of a node following the convention, without their prefix.
+fn fields_of(
+    dot_attributes: &HashMap<String, String>,
+    convention: &DotConvention,
+) -> HashMap<String, String> {
+    dot_attributes
+        .iter()
+        .filter_map(|(name, value)| {
+            let field = name.strip_prefix(&convention.prefix)?;
+            Some((field.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+fn node_from_attributes(
+    dot_id: &str,
+    dot_attributes: &HashMap<String, String>,
+    fields: &HashMap<String, String>,
+    convention: &DotConvention,
+) -> Result<Entity, Error> {
+    if fields.contains_key("type") {
+        return attributes::node_from_attributes(fields);
+    }
+
+    // Graphviz uses the id if a node has no label
+    let value = dot_attributes
+        .get("label")
+        .map_or(dot_id, String::as_str)
+        .replace('\n', " ");
+    let node = convention
+        .fallback
+        .unwrap_or_else(|| EntityKind::guess(&value))
+        .create(&value)?;
+    Ok(node.into())
+}
+
+/// The Graphviz shape and fill color of an entity.
+fn style(entity: &Entity) -> (&'static str, &'static str) {
+    let Entity::Node(node) = entity else {
+        return ("box", "#bcbd22");
+    };
+    match node {
+        Node::Person(_) => ("ellipse", "#1f77b4"),
+        Node::Organization(_) => ("box", "#2ca02c"),
+        Node::IP(_) => ("diamond", "#d62728"),
+        Node::Domain(_) => ("hexagon", "#9467bd"),
+        Node::Website { .. } => ("component", "#8c564b"),
+        Node::DNSEntry { .. } => ("cylinder", "#7f7f7f"),
+        Node::PhoneNumber(_) => ("octagon", "#e377c2"),
+        Node::EmailAddress(_) => ("note", "#ff7f0e"),
+        Node::SocialMedia { .. } => ("house", "#17becf"),
+    }
+}
+
+/// Write a string as a quoted DOT id.
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// An identifier, number, quoted or HTML string.
+    Id {
+        text: String,
+        quoted: bool,
+    },
+    Edge,
+    OpenBrace,
+    CloseBrace,
+    OpenBracket,
+    CloseBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+    // whether the last string is followed by a +
+    let mut concatenate = false;
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // preprocessor output
+            '#' if line_start => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+                continue;
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+                line_start = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                continue;
+            }
+            '-' if matches!(chars.peek(), Some('>' | '-')) => {
+                chars.next();
+                Token::Edge
+            }
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '=' => Token::Equals,
+            ';' => Token::Semicolon,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '"' => {
+                let text = quoted_string(&mut chars)?;
+                // "a" + "b" is the same as "ab"
+                if let (true, Some(Token::Id { text: previous, .. })) =
+                    (concatenate, tokens.last_mut())
+                {
+                    previous.push_str(&text);
+                    concatenate = false;
+                    continue;
+                }
+                Token::Id { text, quoted: true }
+            }
+            '<' => Token::Id {
+                text: html_string(&mut chars),
+                quoted: true,
+            },
+            '+' if matches!(tokens.last(), Some(Token::Id { quoted: true, .. })) => {
+                concatenate = true;
+                continue;
+            }
+            c if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') => {
+                let mut text = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || matches!(c, '_' | '.'))
+                {
+                    text.push(c);
+                }
+                Token::Id {
+                    text,
+                    quoted: false,
+                }
+            }
+            c => return Err(Error::InvalidFormat(format!("unexpected {c} in DOT file"))),
+        };
+        line_start = false;
+        concatenate = false;
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Read a string up to the closing quote, undoing the escapes of [`quote`].
+fn quoted_string(chars: &mut Peekable<Chars>) -> Result<String, Error> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                // the escapes written by `quote`
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('n') => text.push('\n'),
+                // an escaped line break continues the string, a missing closing
+                // quote is reported by the next iteration
+                Some('\n') | None => {}
+                Some(c) => {
+                    text.push('\\');
+                    text.push(c);
+                }
+            },
+            Some(c) => text.push(c),
+            None => {
+                return Err(Error::InvalidFormat(
+                    "a DOT string isn't closed".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+/// Read a HTML string up to its closing angle bracket, keeping only its text.
+fn html_string(chars: &mut Peekable<Chars>) -> String {
+    let mut text = String::new();
+    let mut depth = 1;
+    for c in chars.by_ref() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            // text outside of tags
+            c if depth == 1 => text.push(c),
+            _ => {}
+        }
+        if depth == 0 {
+            break;
+        }
+    }
+    text
+}
+
+/// The nodes and edges of a DOT file with their attributes.
+///
+/// Edges refer to nodes by their index.
+#[derive(Default)]
+struct Parsed {
+    nodes: Vec<(String, HashMap<String, String>)>,
+    edges: Vec<(usize, usize, HashMap<String, String>)>,
+}
+
+/// The `node [...]` and `edge [...]` attributes that apply to a scope.
+#[derive(Clone, Default)]
+struct Defaults {
+    node: HashMap<String, String>,
+    edge: HashMap<String, String>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    parsed: Parsed,
+    indices: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            parsed: Parsed::default(),
+            indices: HashMap::new(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Parsed, Error> {
+        self.keyword("strict");
+        if !self.keyword("graph") && !self.keyword("digraph") {
+            return Err(Error::InvalidFormat(
+                "the file contains no graph".to_string(),
+            ));
+        }
+        if !matches!(self.peek(), Some(Token::OpenBrace)) {
+            self.id()?;
+        }
+        self.expect(&Token::OpenBrace)?;
+        self.statements(&mut Defaults::default())?;
+        Ok(self.parsed)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matches = self.peek() == Some(token);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), Error> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        match self.peek() {
+            Some(token) => Error::InvalidFormat(format!("unexpected {token:?} in DOT file")),
+            None => Error::InvalidFormat("the DOT file ends too early".to_string()),
+        }
+    }
+
+    /// Consume an unquoted keyword, which DOT compares case insensitively.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matches = matches!(
+            self.peek(),
+            Some(Token::Id { text, quoted: false }) if text.eq_ignore_ascii_case(keyword)
+        );
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn id(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Id { text, .. }) => {
+                let text = text.clone();
+                self.position += 1;
+                Ok(text)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parse statements up to the closing brace of the current scope and return
+    /// the nodes used in them.
+    fn statements(&mut self, defaults: &mut Defaults) -> Result<Vec<usize>, Error> {
+        let mut nodes = Vec::new();
+        while !self.eat(&Token::CloseBrace) {
+            nodes.append(&mut self.statement(defaults)?);
+            self.eat(&Token::Semicolon);
+        }
+        Ok(nodes)
+    }
+
+    fn statement(&mut self, defaults: &mut Defaults) -> Result<Vec<usize>, Error> {
+        if self.keyword("graph") {
+            self.attributes()?;
+            return Ok(Vec::new());
+        }
+        if self.keyword("node") {
+            defaults.node.extend(self.attributes()?);
+            return Ok(Vec::new());
+        }
+        if self.keyword("edge") {
+            defaults.edge.extend(self.attributes()?);
+            return Ok(Vec::new());
+        }
+
+        // graph attributes like rankdir=LR
+        if matches!(self.tokens.get(self.position + 1), Some(Token::Equals))
+            && matches!(self.peek(), Some(Token::Id { .. }))
+        {
+            self.position += 2;
+            self.id()?;
+            return Ok(Vec::new());
+        }
+
+        let mut nodes = self.endpoint(defaults)?;
+        if !matches!(self.peek(), Some(Token::Edge)) {
+            // a lone node or subgraph
+            if matches!(self.peek(), Some(Token::OpenBracket)) {
+                let attributes = self.attributes()?;
+                for node in &nodes {
+                    self.parsed.nodes[*node].1.extend(attributes.clone());
+                }
+            }
+            return Ok(nodes);
+        }
+
+        let mut chain = vec![nodes.clone()];
+        while self.eat(&Token::Edge) {
+            let next = self.endpoint(defaults)?;
+            nodes.extend(&next);
+            chain.push(next);
+        }
+        let mut attributes = defaults.edge.clone();
+        if matches!(self.peek(), Some(Token::OpenBracket)) {
+            attributes.extend(self.attributes()?);
+        }
+        for pair in chain.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    self.parsed.edges.push((*from, *to, attributes.clone()));
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// A node or a subgraph, as used on either side of an edge.
+    fn endpoint(&mut self, defaults: &Defaults) -> Result<Vec<usize>, Error> {
+        if self.keyword("subgraph") && !matches!(self.peek(), Some(Token::OpenBrace)) {
+            self.id()?;
+        }
+        if self.eat(&Token::OpenBrace) {
+            // attributes set in a subgraph only apply inside of it
+            return self.statements(&mut defaults.clone());
+        }
+
+        let id = self.id()?;
+        // ports only say where edges attach to the node
+        while self.eat(&Token::Colon) {
+            self.id()?;
+        }
+        let index = *self.indices.entry(id.clone()).or_insert_with(|| {
+            self.parsed.nodes.push((id, defaults.node.clone()));
+            self.parsed.nodes.len() - 1
+        });
+        Ok(vec![index])
+    }
+
+    /// One or more attribute lists like `[label="a", color=red]`.
+    fn attributes(&mut self) -> Result<HashMap<String, String>, Error> {
+        let mut attributes = HashMap::new();
+        while self.eat(&Token::OpenBracket) {
+            while !self.eat(&Token::CloseBracket) {
+                let name = self.id()?;
+                let value = if self.eat(&Token::Equals) {
+                    self.id()?
+                } else {
+                    "true".to_string()
+                };
+                attributes.insert(name, value);
+                if !self.eat(&Token::Comma) {
+                    self.eat(&Token::Semicolon);
+                }
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schnuffel_types::graph::Node;
+
+    use super::{import_with, DotConvention};
+    use crate::entity::{Entity, EntityKind};
+
+    #[test]
+    fn line_breaks_in_labels_become_spaces() {
+        let convention = DotConvention {
+            fallback: Some(EntityKind::Person),
+            ..DotConvention::default()
+        };
+        let graph = import_with(br#"digraph { a [label="Jane\nDoe"] }"#, &convention).unwrap();
+
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(
+            graph.nodes[0].node,
+            Entity::Node(Node::Person("Jane Doe".to_string()))
+        );
+    }
+}
@@ -0,0 +1,481 @@
+use std::net::IpAddr;
+
+use schnuffel_types::graph::{EmailAddress, Node};
+
+use crate::analysis::timeline;
+use crate::entity::{Entity, EntityKind, GenericEntity};
+use crate::error::Error;
+use crate::formats::builder::GraphBuilder;
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// The type of the entities for the messages themselves.
+pub const MESSAGE_TYPE: &str = "Email Message";
+
+/// The headers holding addresses, with the kind of the edge from the message to
+/// the address. The sender is connected the other way round.
+const ADDRESS_HEADERS: [(&str, &str); 7] = [
+    ("sender", "sent"),
+    ("reply-to", "reply-to"),
+    ("return-path", "return-path"),
+    ("to", "to"),
+    ("cc", "cc"),
+    ("bcc", "bcc"),
+    ("delivered-to", "delivered-to"),
+];
+
+/// A message or a part of a multipart message.
+struct Part {
+    /// The headers with lowercase names, unfolded.
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+/// A `Received:` header: who handed the message to whom.
+struct Hop {
+    from_name: Option<String>,
+    from_ip: Option<IpAddr>,
+    by: Option<String>,
+}
+
+/// Read the headers and links of the messages in an `.eml` or mbox file.
+///
+/// Every message becomes an entity connected to the addresses of its sender,
+/// recipients, reply-to and return path, the domain it was sent from and the
+/// websites linked in its body. The `Received:` headers are followed from the
+/// first hop on, connecting the hosts and IPs the message passed with
+/// relayed-to edges. The edges from the senders are observed at the date
+/// of the message.
+///
+/// # Errors
+///
+/// Returns an error if the file contains no message.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    let text = String::from_utf8_lossy(data).replace("\r\n", "\n");
+    let mut builder = GraphBuilder::new();
+    let mut found = false;
+    for message in messages(&text) {
+        let message = Part::parse(message);
+        if message.header("from").is_none() && message.header("received").is_none() {
+            continue;
+        }
+        add_message(&mut builder, &message);
+        found = true;
+    }
+
+    if !found {
+        return Err(Error::InvalidFormat(
+            "the file contains no email".to_string(),
+        ));
+    }
+    Ok(builder.finish())
+}
+
+/// Split an mbox file into its messages. Anything else is a single message.
+fn messages(text: &str) -> Vec<&str> {
+    if !text.starts_with("From ") {
+        return vec![text];
+    }
+    // every message starts with a "From " line, which isn't a header
+    text.split("\nFrom ")
+        .filter_map(|message| message.split_once('\n').map(|(_, message)| message))
+        .collect()
+}
+
+fn add_message(builder: &mut GraphBuilder, message: &Part) {
+    let subject = message.header("subject").map(decode_words);
+    let mut properties = Vec::new();
+    for name in ["date", "message-id"] {
+        if let Some(value) = message.header(name) {
+            properties.push((name.to_string(), value.to_string()));
+        }
+    }
+    let id = builder.node(Entity::Generic(GenericEntity {
+        entity_type: MESSAGE_TYPE.to_string(),
+        value: subject.unwrap_or_else(|| "(no subject)".to_string()),
+        properties,
+    }));
+
+    let sent = message.header("date").and_then(timeline::parse_time);
+    let from = message.header("from").map(addresses).unwrap_or_default();
+    for address in &from {
+        let sender = builder.node(email(address));
+        let kind = EdgeKind::Custom("sent".to_string());
+        match sent {
+            Some(time) => builder.edge_at(sender, id, kind, time),
+            None => builder.edge(sender, id, kind),
+        }
+    }
+    if let Some((_, domain)) = from.first().and_then(|address| address.rsplit_once('@')) {
+        let domain = builder.domain(&domain.to_ascii_lowercase());
+        builder.edge(id, domain, EdgeKind::Custom("sending-domain".to_string()));
+    }
+    for (header, kind) in ADDRESS_HEADERS {
+        for address in message.headers(header).flat_map(addresses) {
+            let address = builder.node(email(&address));
+            match header {
+                "sender" => builder.edge(address, id, EdgeKind::Custom(kind.to_string())),
+                _ => builder.edge(id, address, EdgeKind::Custom(kind.to_string())),
+            }
+        }
+    }
+
+    add_delivery_path(builder, message, id);
+
+    let mut text = String::new();
+    message.text(&mut text);
+    for link in links(&text) {
+        if let Ok(website) = EntityKind::Website.create(&link) {
+            let website = builder.node(website);
+            builder.edge(id, website, EdgeKind::Custom("links-to".to_string()));
+        }
+    }
+}
+
+/// Connect the hosts the message passed, from the first hop to the last.
+fn add_delivery_path(builder: &mut GraphBuilder, message: &Part, id: usize) {
+    // every server adds its header on top, so the first hop is the last header
+    let hops: Vec<Hop> = message.headers("received").map(Hop::parse).collect();
+    let mut previous = None;
+    for hop in hops.iter().rev() {
+        let name = hop.from_name.as_deref().map(|name| builder.domain(name));
+        let ip = hop.from_ip.map(|ip| builder.node(Node::IP(ip)));
+        if let (Some(name), Some(ip)) = (name, ip) {
+            builder.edge(name, ip, EdgeKind::ResolvesTo);
+        }
+        // the address the connection came from is more reliable than the name
+        let from = ip.or(name);
+
+        match (previous, from) {
+            // the name of a host already leads to its address
+            (Some(previous), Some(from)) if previous != from && name != Some(previous) => {
+                builder.edge(previous, from, EdgeKind::Custom("relayed-to".to_string()));
+            }
+            (None, Some(from)) => {
+                builder.edge(id, from, EdgeKind::Custom("first-hop".to_string()));
+            }
+            _ => {}
+        }
+
+        let by = hop.by.as_deref().map(|by| builder.domain(by));
+        if let (Some(from), Some(by)) = (from, by) {
+            builder.edge(from, by, EdgeKind::Custom("relayed-to".to_string()));
+        }
+        previous = by.or(from);
+    }
+}
+
+fn email(address: &str) -> Node {
+    Node::EmailAddress(EmailAddress {
+        email: address.to_string(),
+    })
+}
+
+impl Part {
+    fn parse(text: &str) -> Part {
+        let (head, body) = text
+            .split_once("\n\n")
+            .unwrap_or((text.trim_end_matches('\n'), ""));
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in head.lines() {
+            if line.starts_with([' ', '\t']) {
+                // a folded line continues the previous header
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        Part {
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn headers<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Append the decoded text of all text parts.
+    fn text(&self, out: &mut String) {
+        let content_type = self.header("content-type").unwrap_or("text/plain");
+        let lowercase = content_type.to_ascii_lowercase();
+
+        if lowercase.starts_with("multipart/") {
+            let Some(boundary) = parameter(content_type, "boundary") else {
+                return;
+            };
+            let delimiter = format!("--{boundary}");
+            for part in self.body.split(&delimiter).skip(1) {
+                // the last delimiter ends with "--"
+                if part.starts_with("--") {
+                    break;
+                }
+                Part::parse(part.strip_prefix('\n').unwrap_or(part)).text(out);
+            }
+        } else if lowercase.starts_with("text/") {
+            let encoding = self
+                .header("content-transfer-encoding")
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            let body = match encoding.as_str() {
+                "quoted-printable" => quoted_printable(&self.body),
+                "base64" => base64(&self.body),
+                _ => self.body.clone().into_bytes(),
+            };
+            out.push_str(&String::from_utf8_lossy(&body));
+            out.push('\n');
+        }
+    }
+}
+
+impl Hop {
+    /// Parse a header like
+    /// `from mail.example.com (mail.example.com [203.0.113.5]) by mx.example.org with ESMTPS; <date>`.
+    fn parse(value: &str) -> Hop {
+        let value = value.split(';').next().unwrap_or_default();
+        let words: Vec<&str> = value.split_whitespace().collect();
+        let position = |keyword: &str| {
+            words
+                .iter()
+                .position(|word| word.eq_ignore_ascii_case(keyword))
+        };
+        let from = position("from");
+        let by = position("by");
+
+        // the clause after "from" lists the name the host gave and its address
+        let from_clause = from.map_or(&[][..], |from| {
+            let end = by.filter(|by| *by > from).unwrap_or(words.len());
+            &words[from + 1..end]
+        });
+        let from_ip = from_clause.iter().find_map(|word| {
+            word.split(['[', ']', '(', ')', '='])
+                .find_map(|part| part.trim_start_matches("IPv6:").parse().ok())
+        });
+
+        Hop {
+            from_name: from_clause.first().and_then(|name| host_name(name)),
+            from_ip,
+            by: by
+                .and_then(|by| words.get(by + 1))
+                .and_then(|name| host_name(name)),
+        }
+    }
+}
+
+/// A host name from a `Received:` header, if the word is one.
+fn host_name(word: &str) -> Option<String> {
+    let name = word.trim_matches(['(', ')']).trim_end_matches('.');
+    let is_name = name.contains('.')
+        && name.parse::<IpAddr>().is_err()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    is_name.then(|| name.to_ascii_lowercase())
+}
+
+/// The addresses in a header like `"Doe, Jane" <jane@example.com>, bob@example.com`.
+fn addresses(value: &str) -> Vec<String> {
+    // drop display names and comments, they may contain commas
+    let mut stripped = String::new();
+    let mut quoted = false;
+    let mut comment = 0;
+    for c in value.chars() {
+        match c {
+            '"' if comment == 0 => quoted = !quoted,
+            '(' if !quoted => comment += 1,
+            ')' if !quoted && comment > 0 => comment -= 1,
+            c if !quoted && comment == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+
+    stripped
+        .split(',')
+        .filter_map(|mailbox| {
+            let address = match mailbox.split_once('<') {
+                Some((_, address)) => address.split('>').next().unwrap_or_default(),
+                None => mailbox,
+            };
+            let address = address.trim();
+            address.contains('@').then(|| address.to_string())
+        })
+        .collect()
+}
+
+/// Get a parameter of a header like `multipart/mixed; boundary="abc"`.
+fn parameter<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    value.split(';').skip(1).find_map(|parameter| {
+        let (key, value) = parameter.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// All web links in a text.
+fn links(text: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("http") {
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>'))
+            .unwrap_or(rest.len());
+        let link = rest[..end]
+            .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']'])
+            .replace("&amp;", "&");
+        if (link.starts_with("http://") || link.starts_with("https://")) && !links.contains(&link) {
+            links.push(link);
+        }
+        rest = &rest[end..];
+    }
+    links
+}
+
+/// Decode `=?charset?B?...?=` and `=?charset?Q?...?=` words in a header,
+/// assuming all text is UTF-8.
+fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, word) = rest.split_at(start);
+        let decoded = (|| {
+            let (_charset, word) = word[2..].split_once('?')?;
+            let (encoding, word) = word.split_once('?')?;
+            let (text, rest) = word.split_once("?=")?;
+            let bytes = match encoding {
+                "B" | "b" => base64(text),
+                "Q" | "q" => quoted_printable(&text.replace('_', " ")),
+                _ => return None,
+            };
+            Some((String::from_utf8_lossy(&bytes).into_owned(), rest))
+        })();
+
+        // the space between two encoded words isn't part of the text
+        if !(after_word && before.trim().is_empty()) {
+            out.push_str(before);
+        }
+        if let Some((text, remaining)) = decoded {
+            out.push_str(&text);
+            rest = remaining;
+            after_word = true;
+        } else {
+            out.push_str("=?");
+            rest = &word[2..];
+            after_word = false;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn quoted_printable(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            // a soft line break
+            if bytes.get(i + 1) == Some(&b'\n') {
+                i += 2;
+                continue;
+            }
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = hex {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn base64(text: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut bits: u32 = 0;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            // padding and line breaks
+            _ => continue,
+        };
+        bits = (bits << 6) | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import, MESSAGE_TYPE};
+    use crate::entity::Entity;
+
+    #[test]
+    fn messages_with_the_same_subject_stay_apart() {
+        let mbox = "From alice@example.com Mon Jan  1 00:00:00 2024\n\
+                    From: alice@example.com\n\
+                    Date: Mon, 1 Jan 2024 10:00:00 +0000\n\
+                    Message-ID: <1@example.com>\n\
+                    \n\
+                    first\n\
+                    From bob@example.org Tue Jan  2 00:00:00 2024\n\
+                    From: bob@example.org\n\
+                    Date: Tue, 2 Jan 2024 10:00:00 +0000\n\
+                    Message-ID: <2@example.org>\n\
+                    \n\
+                    second\n";
+
+        let graph = import(mbox.as_bytes()).unwrap();
+
+        let messages: Vec<_> = graph
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.node {
+                Entity::Generic(generic) if generic.entity_type == MESSAGE_TYPE => {
+                    Some((node.id, generic))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages.len(), 2);
+        for (id, message) in messages {
+            assert_eq!(message.value, "(no subject)");
+            assert!(message
+                .properties
+                .iter()
+                .any(|(name, _)| name == "message-id"));
+            let senders = graph.edges.iter().filter(|edge| edge.to == id).count();
+            assert_eq!(senders, 1);
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesDecl, Event};
+use quick_xml::Writer;
+
+use crate::analysis::timeline;
+use crate::entity;
+use crate::error::Error;
+use crate::formats::attributes::{
+    self, AttributeType, ANNOTATION_ATTRIBUTES, EDGE_ATTRIBUTES, NODE_ATTRIBUTES,
+    PROVENANCE_ATTRIBUTES,
+};
+use crate::formats::xml::{self, Element};
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph, VisualNode};
+
+const NAMESPACE: &str = "http://gexf.net/1.3";
+const VIZ_NAMESPACE: &str = "http://gexf.net/1.3/viz";
+
+/// Write a graph as GEXF 1.3.
+///
+/// Node fields become typed attribute values, positions and sizes are stored
+/// using the viz extension so Gephi shows the graph like schnuffel does.
+///
+/// # Errors
+///
+/// Returns an error if writing the XML fails.
+pub fn export(graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer
+        .create_element("gexf")
+        .with_attributes([
+            ("xmlns", NAMESPACE),
+            ("xmlns:viz", VIZ_NAMESPACE),
+            ("version", "1.3"),
+        ])
+        .write_inner_content(|writer| {
+            writer
+                .create_element("graph")
+                .with_attributes([("defaultedgetype", "directed"), ("mode", "static")])
+                .write_inner_content(|writer| {
+                    write_attribute_declarations(writer, graph)?;
+
+                    writer
+                        .create_element("nodes")
+                        .write_inner_content(|writer| {
+                            for node in &graph.nodes {
+                                write_node(writer, node)?;
+                            }
+                            Ok::<(), Error>(())
+                        })?;
+
+                    writer
+                        .create_element("edges")
+                        .write_inner_content(|writer| {
+                            for (i, edge) in graph.edges.iter().enumerate() {
+                                write_edge(writer, i, edge)?;
+                            }
+                            Ok::<(), Error>(())
+                        })?;
+                    Ok::<(), Error>(())
+                })?;
+            Ok::<(), Error>(())
+        })?;
+
+    Ok(writer.into_inner())
+}
+
+/// Read a graph from GEXF.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't valid GEXF or contains nodes that aren't
+/// schnuffel entities.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    let root = xml::parse(data)?;
+    let graph_element = root
+        .child("graph")
+        .ok_or_else(|| Error::InvalidFormat("the file contains no graph".to_string()))?;
+
+    // map attribute ids to their titles, other tools use numeric ids
+    let titles: HashMap<&str, &str> = graph_element
+        .children_named("attributes")
+        .flat_map(|attributes| attributes.children_named("attribute"))
+        .filter_map(|attribute| {
+            let id = attribute.attribute("id")?;
+            Some((id, attribute.attribute("title").unwrap_or(id)))
+        })
+        .collect();
+
+    let mut graph = VisualGraph::empty();
+    let mut ids = HashMap::new();
+    for element in graph_element
+        .child("nodes")
+        .into_iter()
+        .flat_map(|nodes| nodes.children_named("node"))
+    {
+        let xml_id = element
+            .attribute("id")
+            .ok_or_else(|| Error::InvalidFormat("a node has no id".to_string()))?;
+
+        // keep our own ids if the file was written by us
+        let id = xml_id
+            .parse()
+            .ok()
+            .filter(|id| graph.node(*id).is_none())
+            .unwrap_or_else(|| graph.next_id());
+        let free_position = graph.free_position();
+        let default = VisualNode::default();
+        let position = element.child("position");
+        let values = values_of(element, &titles);
+
+        graph.push_node(VisualNode {
+            node: attributes::node_from_attributes(&values)?,
+            annotations: attributes::annotations_from_attributes(&values),
+            provenance: attributes::provenance_from_attributes(&values),
+            id,
+            x: parse_or(position, "x", free_position.x),
+            y: parse_or(position, "y", free_position.y),
+            radius: parse_or(element.child("size"), "value", default.radius),
+            ..default
+        });
+        ids.insert(xml_id, id);
+    }
+
+    for element in graph_element
+        .child("edges")
+        .into_iter()
+        .flat_map(|edges| edges.children_named("edge"))
+    {
+        let endpoint = |name| {
+            element
+                .attribute(name)
+                .and_then(|xml_id| ids.get(xml_id))
+                .copied()
+                .ok_or_else(|| Error::InvalidFormat(format!("an edge has an unknown {name}")))
+        };
+        // fall back to the label for files that weren't written by us
+        let values = values_of(element, &titles);
+        let kind = values
+            .get("kind")
+            .map(String::as_str)
+            .or_else(|| element.attribute("label"));
+
+        let weight = values
+            .get("weight")
+            .map(String::as_str)
+            .or_else(|| element.attribute("weight"));
+
+        graph.edges.push(VisualEdge {
+            from: endpoint("source")?,
+            to: endpoint("target")?,
+            kind: kind.map_or(EdgeKind::default(), EdgeKind::from_name),
+            weight: weight.and_then(|weight| weight.parse().ok()),
+            time: values
+                .get("time")
+                .and_then(|time| timeline::parse_time(time)),
+            provenance: attributes::provenance_from_attributes(&values),
+        });
+    }
+
+    Ok(graph)
+}
+
+fn write_attribute_declarations(
+    writer: &mut Writer<Vec<u8>>,
+    graph: &VisualGraph,
+) -> Result<(), Error> {
+    let property_names = attributes::property_attributes(graph);
+    let properties: Vec<(&str, AttributeType)> = property_names
+        .iter()
+        .map(|name| (name.as_str(), AttributeType::String))
+        .collect();
+    for (class, declared) in [
+        (
+            "node",
+            [
+                &NODE_ATTRIBUTES[..],
+                &ANNOTATION_ATTRIBUTES,
+                &PROVENANCE_ATTRIBUTES,
+                &properties,
+            ]
+            .concat(),
+        ),
+        (
+            "edge",
+            [&EDGE_ATTRIBUTES[..], &PROVENANCE_ATTRIBUTES].concat(),
+        ),
+    ] {
+        writer
+            .create_element("attributes")
+            .with_attribute(("class", class))
+            .write_inner_content(|writer| {
+                for (name, kind) in &declared {
+                    writer
+                        .create_element("attribute")
+                        .with_attributes([("id", *name), ("title", *name), ("type", kind.name())])
+                        .write_empty()?;
+                }
+                Ok::<(), Error>(())
+            })?;
+    }
+    Ok(())
+}
+
+fn write_node(writer: &mut Writer<Vec<u8>>, node: &VisualNode) -> Result<(), Error> {
+    writer
+        .create_element("node")
+        .with_attributes([
+            ("id", node.id.to_string().as_str()),
+            ("label", entity::label(&node.node).as_str()),
+        ])
+        .write_inner_content(|writer| {
+            writer
+                .create_element("attvalues")
+                .write_inner_content(|writer| {
+                    for (key, value) in attributes::node_attributes(&node.node)
+                        .into_iter()
+                        .chain(attributes::annotation_attributes(&node.annotations))
+                        .chain(attributes::provenance_attributes(node.provenance.as_ref()))
+                    {
+                        writer
+                            .create_element("attvalue")
+                            .with_attributes([("for", key.as_str()), ("value", value.as_str())])
+                            .write_empty()?;
+                    }
+                    Ok::<(), Error>(())
+                })?;
+            writer
+                .create_element("viz:position")
+                .with_attributes([
+                    ("x", node.x.to_string().as_str()),
+                    ("y", node.y.to_string().as_str()),
+                    ("z", "0.0"),
+                ])
+                .write_empty()?;
+            writer
+                .create_element("viz:size")
+                .with_attribute(("value", node.radius.to_string().as_str()))
+                .write_empty()?;
+            Ok::<(), Error>(())
+        })?;
+    Ok(())
+}
+
+fn write_edge(writer: &mut Writer<Vec<u8>>, id: usize, edge: &VisualEdge) -> Result<(), Error> {
+    let weight = edge.weight.map(|weight| weight.to_string());
+    let time = edge.time.map(|time| time.to_rfc3339());
+    writer
+        .create_element("edge")
+        .with_attributes([
+            ("id", id.to_string().as_str()),
+            ("source", edge.from.to_string().as_str()),
+            ("target", edge.to.to_string().as_str()),
+            ("label", edge.kind.name()),
+        ])
+        // GEXF has a weight of its own, which Gephi uses
+        .with_attributes(weight.as_deref().map(|weight| ("weight", weight)))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("attvalues")
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("attvalue")
+                        .with_attributes([("for", "kind"), ("value", edge.kind.name())])
+                        .write_empty()?;
+                    if let Some(weight) = &weight {
+                        writer
+                            .create_element("attvalue")
+                            .with_attributes([("for", "weight"), ("value", weight.as_str())])
+                            .write_empty()?;
+                    }
+                    if let Some(time) = &time {
+                        writer
+                            .create_element("attvalue")
+                            .with_attributes([("for", "time"), ("value", time.as_str())])
+                            .write_empty()?;
+                    }
+                    for (key, value) in attributes::provenance_attributes(edge.provenance.as_ref())
+                    {
+                        writer
+                            .create_element("attvalue")
+                            .with_attributes([("for", key.as_str()), ("value", value.as_str())])
+                            .write_empty()?;
+                    }
+                    Ok::<(), Error>(())
+                })?;
+            Ok::<(), Error>(())
+        })?;
+    Ok(())
+}
+
+/// Collect the attribute values of an element, keyed by their titles.
+fn values_of(element: &Element, titles: &HashMap<&str, &str>) -> HashMap<String, String> {
+    element
+        .child("attvalues")
+        .into_iter()
+        .flat_map(|values| values.children_named("attvalue"))
+        .filter_map(|value| {
+            let id = value.attribute("for")?;
+            let title = titles.get(id).copied().unwrap_or(id);
+            Some((title.to_string(), value.attribute("value")?.to_string()))
+        })
+        .collect()
+}
+
+fn parse_or(element: Option<&Element>, attribute: &str, default: f32) -> f32 {
+    element
+        .and_then(|element| element.attribute(attribute))
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use schnuffel_types::graph::{Domain, Node};
+
+    use super::{export, import};
+    use crate::annotation::{Annotations, Confidence, Provenance};
+    use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph};
+
+    #[test]
+    #[allow(clippy::float_cmp)] // the numbers have to come back exactly
+    fn round_trip() {
+        let mut graph = VisualGraph::empty();
+        let person = graph.add_node(Node::Person("Jane Doe".to_string()));
+        let domain = graph.add_node(Node::Domain(Domain {
+            domain: "example.com".to_string(),
+        }));
+        graph.annotate(
+            person,
+            Annotations {
+                notes: "seen twice".to_string(),
+                tags: vec!["suspect".to_string()],
+                confidence: Confidence::Verified,
+                properties: [("first seen".to_string(), "2024".to_string())].into(),
+            },
+        );
+        graph.nodes[1].x = -12.5;
+        graph.nodes[1].y = 40.25;
+        graph.edges.push(VisualEdge {
+            from: person,
+            to: domain,
+            kind: EdgeKind::Custom("owns".to_string()),
+            weight: Some(2.5),
+            time: Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap()),
+            provenance: Some(Provenance {
+                source: "https://example.com/whois.wasm".to_string(),
+                time: Utc.with_ymd_and_hms(2024, 5, 2, 8, 0, 0).unwrap(),
+            }),
+        });
+        graph.nodes[1].provenance = graph.edges[0].provenance.clone();
+
+        let read = import(&export(&graph).unwrap()).unwrap();
+
+        assert_eq!(read.nodes.len(), graph.nodes.len());
+        for (read, written) in read.nodes.iter().zip(&graph.nodes) {
+            assert_eq!(read.node, written.node);
+            assert_eq!(read.id, written.id);
+            assert_eq!((read.x, read.y), (written.x, written.y));
+            assert_eq!(read.radius, written.radius);
+            assert_eq!(read.annotations, written.annotations);
+            assert_eq!(read.provenance, written.provenance);
+        }
+        assert_eq!(read.edges.len(), graph.edges.len());
+        for (read, written) in read.edges.iter().zip(&graph.edges) {
+            assert_eq!((read.from, read.to), (written.from, written.to));
+            assert_eq!(read.kind, written.kind);
+            assert_eq!(read.weight, written.weight);
+            assert_eq!(read.time, written.time);
+            assert_eq!(read.provenance, written.provenance);
+        }
+    }
+}
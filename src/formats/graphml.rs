@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::Writer;
+
+use crate::analysis::timeline;
+use crate::error::Error;
+use crate::formats::attributes::{
+    self, AttributeType, ANNOTATION_ATTRIBUTES, EDGE_ATTRIBUTES, NODE_ATTRIBUTES,
+    PROVENANCE_ATTRIBUTES, VISUAL_ATTRIBUTES,
+};
+use crate::formats::xml::{self, Element};
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph, VisualNode};
+
+const NAMESPACE: &str = "http://graphml.graphdrawing.org/xmlns";
+
+/// Write a graph as `GraphML`.
+///
+/// Every node field is written as its own typed `data` element, so tools like yEd
+/// and Gephi can show and filter by them.
+///
+/// # Errors
+///
+/// Returns an error if writing the XML fails.
+pub fn export(graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer
+        .create_element("graphml")
+        .with_attribute(("xmlns", NAMESPACE))
+        .write_inner_content(|writer| {
+            // declare all attributes up front
+            for (name, kind) in NODE_ATTRIBUTES
+                .iter()
+                .chain(&VISUAL_ATTRIBUTES)
+                .chain(&ANNOTATION_ATTRIBUTES)
+                .chain(&PROVENANCE_ATTRIBUTES)
+            {
+                write_key(writer, name, name, "node", kind.name())?;
+            }
+            for name in attributes::property_attributes(graph) {
+                write_key(writer, &name, &name, "node", AttributeType::String.name())?;
+            }
+            for (name, kind) in EDGE_ATTRIBUTES {
+                write_key(writer, name, name, "edge", kind.name())?;
+            }
+            // key ids have to be unique, so the edge ones get a prefix
+            for (name, kind) in PROVENANCE_ATTRIBUTES {
+                write_key(writer, &edge_key(name), name, "edge", kind.name())?;
+            }
+
+            writer
+                .create_element("graph")
+                .with_attributes([("id", "investigation"), ("edgedefault", "directed")])
+                .write_inner_content(|writer| {
+                    for node in &graph.nodes {
+                        write_node(writer, node)?;
+                    }
+                    for (i, edge) in graph.edges.iter().enumerate() {
+                        writer
+                            .create_element("edge")
+                            .with_attributes([
+                                ("id", format!("e{i}").as_str()),
+                                ("source", format!("n{}", edge.from).as_str()),
+                                ("target", format!("n{}", edge.to).as_str()),
+                            ])
+                            .write_inner_content(|writer| {
+                                write_data(writer, "kind", edge.kind.name())?;
+                                if let Some(weight) = edge.weight {
+                                    write_data(writer, "weight", &weight.to_string())?;
+                                }
+                                if let Some(time) = edge.time {
+                                    write_data(writer, "time", &time.to_rfc3339())?;
+                                }
+                                for (key, value) in
+                                    attributes::provenance_attributes(edge.provenance.as_ref())
+                                {
+                                    write_data(writer, &edge_key(&key), &value)?;
+                                }
+                                Ok::<(), Error>(())
+                            })?;
+                    }
+                    Ok::<(), Error>(())
+                })?;
+            Ok::<(), Error>(())
+        })?;
+
+    Ok(writer.into_inner())
+}
+
+/// Read a graph from `GraphML`.
+///
+/// Attributes are matched by their `attr.name`, so files that were edited and
+/// saved by other tools can still be read.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't valid `GraphML` or contains nodes that aren't
+/// schnuffel entities.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    let root = xml::parse(data)?;
+
+    // other tools name their keys "d0", "d1", ... so map them to the attribute names
+    let keys: HashMap<&str, &str> = root
+        .children_named("key")
+        .filter_map(|key| {
+            let id = key.attribute("id")?;
+            Some((id, key.attribute("attr.name").unwrap_or(id)))
+        })
+        .collect();
+
+    let graph_element = root
+        .child("graph")
+        .ok_or_else(|| Error::InvalidFormat("the file contains no graph".to_string()))?;
+
+    let mut graph = VisualGraph::empty();
+    let mut ids = HashMap::new();
+    for element in graph_element.children_named("node") {
+        let xml_id = element
+            .attribute("id")
+            .ok_or_else(|| Error::InvalidFormat("a node has no id".to_string()))?;
+        let data = data_of(element, &keys);
+
+        // keep our own ids if the file was written by us
+        let id = xml_id
+            .strip_prefix('n')
+            .and_then(|id| id.parse().ok())
+            .filter(|id| graph.node(*id).is_none())
+            .unwrap_or_else(|| graph.next_id());
+        let position = graph.free_position();
+        let default = VisualNode::default();
+
+        graph.push_node(VisualNode {
+            node: attributes::node_from_attributes(&data)?,
+            annotations: attributes::annotations_from_attributes(&data),
+            provenance: attributes::provenance_from_attributes(&data),
+            id,
+            x: parse_or(&data, "x", position.x),
+            y: parse_or(&data, "y", position.y),
+            radius: parse_or(&data, "radius", default.radius),
+            ..default
+        });
+        ids.insert(xml_id, id);
+    }
+
+    for element in graph_element.children_named("edge") {
+        let endpoint = |name| {
+            element
+                .attribute(name)
+                .and_then(|xml_id| ids.get(xml_id))
+                .copied()
+                .ok_or_else(|| Error::InvalidFormat(format!("an edge has an unknown {name}")))
+        };
+        let data = data_of(element, &keys);
+
+        graph.edges.push(VisualEdge {
+            from: endpoint("source")?,
+            to: endpoint("target")?,
+            kind: data
+                .get("kind")
+                .map_or(EdgeKind::default(), |kind| EdgeKind::from_name(kind)),
+            weight: data.get("weight").and_then(|weight| weight.parse().ok()),
+            time: data.get("time").and_then(|time| timeline::parse_time(time)),
+            provenance: attributes::provenance_from_attributes(&data),
+        });
+    }
+
+    Ok(graph)
+}
+
+/// The id of the key for an edge attribute that nodes have as well.
+fn edge_key(name: &str) -> String {
+    format!("edge.{name}")
+}
+
+fn write_key(
+    writer: &mut Writer<Vec<u8>>,
+    id: &str,
+    name: &str,
+    target: &str,
+    kind: &str,
+) -> Result<(), Error> {
+    writer
+        .create_element("key")
+        .with_attributes([
+            ("id", id),
+            ("for", target),
+            ("attr.name", name),
+            ("attr.type", kind),
+        ])
+        .write_empty()?;
+    Ok(())
+}
+
+fn write_data(writer: &mut Writer<Vec<u8>>, key: &str, value: &str) -> Result<(), Error> {
+    writer
+        .create_element("data")
+        .with_attribute(("key", key))
+        .write_text_content(BytesText::new(value))?;
+    Ok(())
+}
+
+fn write_node(writer: &mut Writer<Vec<u8>>, node: &VisualNode) -> Result<(), Error> {
+    writer
+        .create_element("node")
+        .with_attribute(("id", format!("n{}", node.id).as_str()))
+        .write_inner_content(|writer| {
+            for (key, value) in attributes::node_attributes(&node.node)
+                .into_iter()
+                .chain(attributes::annotation_attributes(&node.annotations))
+                .chain(attributes::provenance_attributes(node.provenance.as_ref()))
+            {
+                write_data(writer, &key, &value)?;
+            }
+            write_data(writer, "x", &node.x.to_string())?;
+            write_data(writer, "y", &node.y.to_string())?;
+            write_data(writer, "radius", &node.radius.to_string())
+        })?;
+    Ok(())
+}
+
+/// Collect the `data` children of an element, keyed by their attribute names.
+fn data_of(element: &Element, keys: &HashMap<&str, &str>) -> HashMap<String, String> {
+    element
+        .children_named("data")
+        .filter_map(|data| {
+            let key = data.attribute("key")?;
+            let name = keys.get(key).copied().unwrap_or(key);
+            Some((name.to_string(), data.text.clone()))
+        })
+        .collect()
+}
+
+fn parse_or(data: &HashMap<String, String>, key: &str, default: f32) -> f32 {
+    data.get(key)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{TimeZone, Utc};
+    use schnuffel_types::graph::{DNSRecord, Domain, Node};
+
+    use super::{export, import};
+    use crate::annotation::{Annotations, Confidence, Provenance};
+    use crate::formats::xml;
+    use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph};
+
+    #[test]
+    #[allow(clippy::float_cmp)] // the numbers have to come back exactly
+    fn round_trip() {
+        let mut graph = VisualGraph::empty();
+        let person = graph.add_node(Node::Person("Jane Doe".to_string()));
+        let record = graph.add_node(Node::DNSEntry {
+            nameserver: Domain {
+                domain: "ns1.example.com".to_string(),
+            },
+            record: DNSRecord::TXT("  v=spf1 -all ".to_string()),
+        });
+        graph.annotate(
+            person,
+            Annotations {
+                notes: "  seen twice\nat the office ".to_string(),
+                tags: vec!["suspect".to_string()],
+                confidence: Confidence::Verified,
+                properties: [("first seen".to_string(), " 2024 ".to_string())].into(),
+            },
+        );
+        graph.nodes[1].x = -12.5;
+        graph.nodes[1].y = 40.25;
+        graph.edges.push(VisualEdge {
+            from: person,
+            to: record,
+            kind: EdgeKind::Custom("wrote".to_string()),
+            weight: Some(2.5),
+            time: Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap()),
+            provenance: Some(Provenance {
+                source: "https://example.com/dns.wasm".to_string(),
+                time: Utc.with_ymd_and_hms(2024, 5, 2, 8, 0, 0).unwrap(),
+            }),
+        });
+        graph.nodes[1].provenance = graph.edges[0].provenance.clone();
+
+        let data = export(&graph).unwrap();
+        let keys: Vec<_> = xml::parse(&data)
+            .unwrap()
+            .children_named("key")
+            .filter_map(|key| key.attribute("id"))
+            .map(str::to_string)
+            .collect();
+        let unique: HashSet<_> = keys.iter().collect();
+        assert_eq!(unique.len(), keys.len(), "duplicate key ids in {keys:?}");
+
+        let read = import(&data).unwrap();
+
+        assert_eq!(read.nodes.len(), graph.nodes.len());
+        for (read, written) in read.nodes.iter().zip(&graph.nodes) {
+            assert_eq!(read.node, written.node);
+            assert_eq!(read.id, written.id);
+            assert_eq!((read.x, read.y), (written.x, written.y));
+            assert_eq!(read.radius, written.radius);
+            assert_eq!(read.annotations, written.annotations);
+            assert_eq!(read.provenance, written.provenance);
+        }
+        assert_eq!(read.edges.len(), graph.edges.len());
+        for (read, written) in read.edges.iter().zip(&graph.edges) {
+            assert_eq!((read.from, read.to), (written.from, written.to));
+            assert_eq!(read.kind, written.kind);
+            assert_eq!(read.weight, written.weight);
+            assert_eq!(read.time, written.time);
+            assert_eq!(read.provenance, written.provenance);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt::{self, Display, Write};
+
+use iced::{Color, Point, Rectangle, Size};
+use iced_graphics::text::cosmic_text::{self, Attrs, Buffer, Metrics, Shaping, SwashCache};
+use iced_graphics::text::font_system;
+use quick_xml::escape::escape;
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::error::Error;
+use crate::registry::{self, Registry};
+use crate::views::graph::{annotations, constants, VisualGraph};
+
+/// Space left around the nodes when the whole graph is exported.
+const PADDING: f32 = 20.0;
+
+/// How many pixels of a PNG make up one pixel of the canvas.
+const PNG_SCALE: f32 = 3.0;
+
+/// The image formats the canvas can be exported as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    /// The file extension of the format.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+impl Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageFormat::Svg => write!(f, "SVG"),
+            ImageFormat::Png => write!(f, "PNG"),
+        }
+    }
+}
+
+/// Which part of the canvas ends up in an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageArea {
+    Graph,
+    Viewport,
+}
+
+impl ImageArea {
+    pub const ALL: [ImageArea; 2] = [ImageArea::Graph, ImageArea::Viewport];
+}
+
+impl Display for ImageArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageArea::Graph => write!(f, "Whole graph"),
+            ImageArea::Viewport => write!(f, "Viewport"),
+        }
+    }
+}
+
+/// A node as it is drawn on the canvas.
+struct Circle {
+    center: Point,
+    radius: f32,
+    /// The color of the custom type, black for built-in entities.
+    color: Color,
+    /// The icon of the custom type drawn on the circle.
+    icon: Option<String>,
+    /// The color of the first tag, drawn as a ring around the circle.
+    ring: Option<Color>,
+}
+
+/// An edge as it is drawn on the canvas.
+struct Line {
+    from: Point,
+    to: Point,
+    width: f32,
+}
+
+/// The part of the canvas the whole graph is drawn in.
+#[must_use]
+pub fn graph_bounds(graph: &VisualGraph, zoom_factor: f32) -> Rectangle {
+    let mut circles = circles(graph, &Registry::default(), zoom_factor).into_iter();
+    let Some(first) = circles.next() else {
+        return Rectangle::new(Point::ORIGIN, Size::new(PADDING * 2.0, PADDING * 2.0));
+    };
+
+    let extent = |c: &Circle| {
+        (
+            c.center.x - c.radius,
+            c.center.y - c.radius,
+            c.center.x + c.radius,
+            c.center.y + c.radius,
+        )
+    };
+    let (left, top, right, bottom) = circles.fold(extent(&first), |bounds, circle| {
+        let (left, top, right, bottom) = extent(&circle);
+        (
+            bounds.0.min(left),
+            bounds.1.min(top),
+            bounds.2.max(right),
+            bounds.3.max(bottom),
+        )
+    });
+
+    Rectangle::new(
+        Point::new(left - PADDING, top - PADDING),
+        Size::new(right - left + PADDING * 2.0, bottom - top + PADDING * 2.0),
+    )
+}
+
+/// Draw the part of the canvas inside `area` in the same style as the canvas,
+/// with the colors and icons of the custom types in `types` and the rings of
+/// the tags.
+///
+/// # Errors
+///
+/// Returns an error if the image is too large to be rendered.
+pub fn export(
+    graph: &VisualGraph,
+    types: &Registry,
+    zoom_factor: f32,
+    area: Rectangle,
+    format: ImageFormat,
+) -> Result<Vec<u8>, Error> {
+    // move the area to the origin of the image
+    let offset = |p: Point| Point::new(p.x - area.x, p.y - area.y);
+    let circles: Vec<Circle> = circles(graph, types, zoom_factor)
+        .into_iter()
+        .map(|c| Circle {
+            center: offset(c.center),
+            ..c
+        })
+        .collect();
+    let lines: Vec<Line> = lines(graph, zoom_factor)
+        .into_iter()
+        .map(|l| Line {
+            from: offset(l.from),
+            to: offset(l.to),
+            ..l
+        })
+        .collect();
+
+    match format {
+        ImageFormat::Svg => Ok(svg(area.size(), &circles, &lines, zoom_factor)),
+        ImageFormat::Png => png(area.size(), &circles, &lines, zoom_factor),
+    }
+}
+
+fn circles(graph: &VisualGraph, types: &Registry, zoom_factor: f32) -> Vec<Circle> {
+    graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let entity_type = types.type_of(&node.node);
+            Circle {
+                center: Point::new(node.x, node.y),
+                radius: node.radius * zoom_factor * constants::NODE_ZOOM_SCALING,
+                color: entity_type.map_or(Color::BLACK, |t| t.color),
+                icon: entity_type
+                    .filter(|t| !t.icon.is_empty())
+                    .map(|t| t.icon.clone()),
+                ring: node
+                    .annotations
+                    .tags
+                    .first()
+                    .map(|tag| annotations::tag_color(tag)),
+            }
+        })
+        .collect()
+}
+
+fn lines(graph: &VisualGraph, zoom_factor: f32) -> Vec<Line> {
+    graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let from = graph.node(edge.from)?;
+            let to = graph.node(edge.to)?;
+            Some(Line {
+                from: Point::new(from.x, from.y),
+                to: Point::new(to.x, to.y),
+                width: zoom_factor,
+            })
+        })
+        .collect()
+}
+
+fn svg(size: Size, circles: &[Circle], lines: &[Line], zoom_factor: f32) -> Vec<u8> {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        size.width, size.height
+    );
+    svg.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+    // writing to a string can't fail
+    for circle in circles {
+        let _ = writeln!(
+            svg,
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+            circle.center.x,
+            circle.center.y,
+            circle.radius,
+            registry::color_to_hex(circle.color)
+        );
+        if let Some(icon) = &circle.icon {
+            let _ = writeln!(
+                svg,
+                "  <text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"white\" \
+                 text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                circle.center.x,
+                circle.center.y,
+                circle.radius,
+                escape(icon)
+            );
+        }
+        if let Some(ring) = circle.ring {
+            let _ = writeln!(
+                svg,
+                "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" \
+                 stroke-width=\"{}\"/>",
+                circle.center.x,
+                circle.center.y,
+                circle.radius,
+                registry::color_to_hex(ring),
+                constants::TAG_RING_WIDTH * zoom_factor
+            );
+        }
+    }
+    for line in lines {
+        let _ = writeln!(
+            svg,
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"{}\"/>",
+            line.from.x, line.from.y, line.to.x, line.to.y, line.width
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg.into_bytes()
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn png(size: Size, circles: &[Circle], lines: &[Line], zoom_factor: f32) -> Result<Vec<u8>, Error> {
+    let too_large = || Error::InvalidFormat("the image is too large".to_string());
+
+    let width = (size.width * PNG_SCALE).ceil().max(1.0) as u32;
+    let height = (size.height * PNG_SCALE).ceil().max(1.0) as u32;
+    let mut pixmap = Pixmap::new(width, height).ok_or_else(too_large)?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Paint::default()
+    };
+    let transform = Transform::from_scale(PNG_SCALE, PNG_SCALE);
+
+    for circle in circles {
+        let Some(path) = PathBuilder::from_circle(circle.center.x, circle.center.y, circle.radius)
+        else {
+            continue;
+        };
+        paint.set_color(skia_color(circle.color));
+        pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+        if let Some(icon) = &circle.icon {
+            draw_text(&mut pixmap, icon, circle.center, circle.radius);
+        }
+        if let Some(ring) = circle.ring {
+            paint.set_color(skia_color(ring));
+            let stroke = Stroke {
+                width: constants::TAG_RING_WIDTH * zoom_factor,
+                ..Stroke::default()
+            };
+            pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+        }
+    }
+    paint.set_color(tiny_skia::Color::BLACK);
+    for line in lines {
+        let mut path = PathBuilder::new();
+        path.move_to(line.from.x, line.from.y);
+        path.line_to(line.to.x, line.to.y);
+        if let Some(path) = path.finish() {
+            let stroke = Stroke {
+                width: line.width,
+                ..Stroke::default()
+            };
+            pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+        }
+    }
+
+    pixmap.encode_png().map_err(|_| too_large())
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(color.r, color.g, color.b, color.a)
+        .unwrap_or(tiny_skia::Color::BLACK)
+}
+
+/// Draw white text of a size centered on a point of the canvas, using the
+/// fonts the canvas is drawn with.
+#[allow(clippy::cast_precision_loss)]
+fn draw_text(pixmap: &mut Pixmap, content: &str, center: Point, size: f32) {
+    // a poisoned font system only means another text failed to be drawn
+    let mut fonts = font_system()
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let fonts = fonts.raw();
+
+    let size = size * PNG_SCALE;
+    let mut buffer = Buffer::new(fonts, Metrics::new(size, size));
+    buffer.set_size(fonts, f32::INFINITY, f32::INFINITY);
+    buffer.set_text(fonts, content, Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(fonts);
+
+    let text_width = buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0, f32::max);
+    let left = center.x * PNG_SCALE - text_width / 2.0;
+    let top = center.y * PNG_SCALE - size / 2.0;
+
+    let mut paint = Paint::default();
+    let white = cosmic_text::Color::rgb(0xFF, 0xFF, 0xFF);
+    buffer.draw(fonts, &mut SwashCache::new(), white, |x, y, w, h, color| {
+        paint.set_color_rgba8(color.r(), color.g(), color.b(), color.a());
+        if let Some(rect) =
+            tiny_skia::Rect::from_xywh(left + x as f32, top + y as f32, w as f32, h as f32)
+        {
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+    });
+}
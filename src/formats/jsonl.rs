@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::io::{BufRead, Write};
+
+use schnuffel_types::graph::Node;
+use serde_json::{json, Map, Value};
+
+use crate::analysis::timeline;
+use crate::annotation::{Annotations, Confidence};
+use crate::entity::{self, Entity, GenericEntity};
+use crate::error::Error;
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph, VisualNode};
+
+/// Which entities are exported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeFilter {
+    All,
+    /// Only the entities with this [`entity::type_name`].
+    Type(String),
+}
+
+impl TypeFilter {
+    /// The filters that make sense for a graph: all entities or the entities of
+    /// one of the types in the graph.
+    #[must_use]
+    pub fn options(graph: &VisualGraph) -> Vec<TypeFilter> {
+        let mut types: Vec<&str> = graph
+            .nodes
+            .iter()
+            .map(|node| entity::type_name(&node.node))
+            .collect();
+        types.sort_unstable();
+        types.dedup();
+        std::iter::once(TypeFilter::All)
+            .chain(types.into_iter().map(|t| TypeFilter::Type(t.to_string())))
+            .collect()
+    }
+
+    /// Whether an entity passes the filter.
+    #[must_use]
+    pub fn keeps(&self, entity: &Entity) -> bool {
+        match self {
+            TypeFilter::All => true,
+            TypeFilter::Type(name) => entity::type_name(entity) == name,
+        }
+    }
+}
+
+impl Display for TypeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeFilter::All => write!(f, "All types"),
+            TypeFilter::Type(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Write a graph as JSON Lines, one entity or edge per line, so it can be
+/// processed line by line by tools like `jq`.
+///
+/// Entities look like
+/// `{"type":"entity","id":0,"node":{"IP":"1.2.3.4"},"x":10.0,"y":20.0}` where
+/// `node` is the serde representation of the [`Node`]. Generic entities have a
+/// `generic` object with their type, value and properties instead. Entities
+/// that were annotated have an `annotations` object with their `notes`, `tags`,
+/// `confidence` and `properties`. Edges look like
+/// `{"type":"edge","from":0,"to":1,"kind":"resolves-to"}`, with a `weight` and
+/// a `time` in RFC 3339 if they have one.
+///
+/// All entities are written before the edges.
+///
+/// # Errors
+///
+/// Returns an error if writing fails.
+pub fn write(graph: &VisualGraph, mut writer: impl Write) -> Result<(), Error> {
+    for node in &graph.nodes {
+        serde_json::to_writer(&mut writer, &entity_line(node)?)?;
+        writer.write_all(b"\n")?;
+    }
+    for edge in &graph.edges {
+        let mut line = json!({
+            "type": "edge",
+            "from": edge.from,
+            "to": edge.to,
+            "kind": edge.kind.name(),
+        });
+        if let Some(weight) = edge.weight {
+            line["weight"] = json!(weight);
+        }
+        if let Some(time) = edge.time {
+            line["time"] = json!(time.to_rfc3339());
+        }
+        serde_json::to_writer(&mut writer, &line)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write a graph as JSON Lines into memory, see [`write`].
+///
+/// # Errors
+///
+/// Returns an error if an entity can't be represented as JSON.
+pub fn export(graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    write(graph, &mut data)?;
+    Ok(data)
+}
+
+/// Read a graph from JSON Lines written by [`write`], one line at a time, so
+/// large files don't have to be loaded at once.
+///
+/// Lines that are just the serde representation of a [`Node`], like
+/// `{"Person":"Jane Doe"}`, are read as entities too, which makes it easy to
+/// feed the output of other tools in. Empty lines are skipped, and so are edges
+/// to entities that aren't in the stream, e.g. because it was filtered.
+///
+/// # Errors
+///
+/// Returns an error if reading fails or a line isn't an entity or edge.
+pub fn read(reader: impl BufRead) -> Result<VisualGraph, Error> {
+    let mut graph = VisualGraph::empty();
+    let mut ids = HashMap::new();
+    // edges may come before the entities they connect
+    let mut edges = Vec::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid =
+            |reason: &dyn Display| Error::InvalidFormat(format!("line {}: {reason}", number + 1));
+        let value: Value = serde_json::from_str(&line).map_err(|e| invalid(&e))?;
+
+        match value.get("type").and_then(Value::as_str) {
+            Some("edge") => {
+                let endpoint = |name| {
+                    value
+                        .get(name)
+                        .and_then(Value::as_u64)
+                        .ok_or_else(|| invalid(&format!("the edge has no {name}")))
+                };
+                let kind = value
+                    .get("kind")
+                    .and_then(Value::as_str)
+                    .map_or(EdgeKind::default(), EdgeKind::from_name);
+                // the endpoints are set once the ids of the entities are known
+                edges.push((
+                    endpoint("from")?,
+                    endpoint("to")?,
+                    VisualEdge {
+                        kind,
+                        weight: value.get("weight").and_then(Value::as_f64),
+                        time: value
+                            .get("time")
+                            .and_then(Value::as_str)
+                            .and_then(timeline::parse_time),
+                        ..VisualEdge::default()
+                    },
+                ));
+            }
+            Some("entity") => {
+                let (node, x, y) = entity_from_line(&value).map_err(|e| invalid(&e))?;
+                let annotations = value.get("annotations").map(annotations_from_value);
+                let is_new = graph.find(&node).is_none();
+                let id = graph.add_node(node);
+                if let Some(file_id) = value.get("id").and_then(Value::as_u64) {
+                    ids.insert(file_id, id);
+                }
+                if is_new {
+                    // the new node is the last one, so it doesn't have to be
+                    // looked up, which would make large files slow
+                    let Some(added) = graph.nodes.last_mut() else {
+                        continue;
+                    };
+                    added.annotations = annotations.unwrap_or_default();
+                    if let (Some(x), Some(y)) = (x, y) {
+                        added.x = x;
+                        added.y = y;
+                    }
+                } else if let Some(annotations) = annotations {
+                    // entities that appear twice keep the position of the first line
+                    graph.annotate(id, annotations);
+                }
+            }
+            _ => {
+                let node: Node = serde_json::from_value(value).map_err(|e| invalid(&e))?;
+                graph.add_node(node);
+            }
+        }
+    }
+
+    for (from, to, edge) in edges {
+        if let (Some(&from), Some(&to)) = (ids.get(&from), ids.get(&to)) {
+            graph.edges.push(VisualEdge { from, to, ..edge });
+        }
+    }
+    Ok(graph)
+}
+
+fn entity_line(node: &VisualNode) -> Result<Value, Error> {
+    let mut line = json!({
+        "type": "entity",
+        "id": node.id,
+    });
+    match &node.node {
+        Entity::Node(entity) => {
+            line["node"] = serde_json::to_value(entity)?;
+        }
+        Entity::Generic(generic) => {
+            let properties: Map<String, Value> = generic
+                .properties
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::from(value.as_str())))
+                .collect();
+            line["generic"] = json!({
+                "type": generic.entity_type,
+                "value": generic.value,
+                "properties": properties,
+            });
+        }
+    }
+    if !node.annotations.is_empty() {
+        line["annotations"] = annotations_value(&node.annotations);
+    }
+    line["x"] = json!(node.x);
+    line["y"] = json!(node.y);
+    Ok(line)
+}
+
+fn annotations_value(annotations: &Annotations) -> Value {
+    json!({
+        "notes": annotations.notes,
+        "tags": annotations.tags,
+        "confidence": annotations.confidence.name(),
+        "properties": annotations.properties,
+    })
+}
+
+/// Read annotations written by [`annotations_value`], skipping what is
+/// missing or malformed.
+fn annotations_from_value(value: &Value) -> Annotations {
+    let text = |name| value.get(name).and_then(Value::as_str);
+    Annotations {
+        notes: text("notes").unwrap_or_default().to_string(),
+        tags: value
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        confidence: text("confidence")
+            .and_then(Confidence::from_name)
+            .unwrap_or_default(),
+        properties: value
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|properties| {
+                properties
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Get the entity of an entity line and its position, if the line has one.
+#[allow(clippy::cast_possible_truncation)]
+fn entity_from_line(line: &Value) -> Result<(Entity, Option<f32>, Option<f32>), String> {
+    let entity = if let Some(node) = line.get("node") {
+        Entity::Node(serde_json::from_value(node.clone()).map_err(|e| e.to_string())?)
+    } else if let Some(generic) = line.get("generic") {
+        let text = |name| generic.get(name).and_then(Value::as_str);
+        let entity_type = text("type").ok_or_else(|| "the entity has no type".to_string())?;
+        let properties = generic
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = value.as_str().map_or(value.to_string(), str::to_string);
+                        (name.clone(), value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Entity::Generic(GenericEntity {
+            entity_type: entity_type.to_string(),
+            value: text("value").unwrap_or_default().to_string(),
+            properties,
+        })
+    } else {
+        return Err("the entity has neither a node nor a generic entity".to_string());
+    };
+
+    let coordinate = |name| line.get(name).and_then(Value::as_f64).map(|v| v as f32);
+    Ok((entity, coordinate("x"), coordinate("y")))
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use crate::entity::{Entity, EntityKind, GenericEntity};
+use crate::error::Error;
+use crate::formats::xml::{self, Element};
+use crate::views::graph::{EdgeKind, VisualEdge, VisualGraph, VisualNode};
+
+/// The Maltego entity types schnuffel has a node for, with the property holding
+/// the value of the entity.
+const ENTITY_TYPES: [(&str, EntityKind, &str); 13] = [
+    ("maltego.Domain", EntityKind::Domain, "fqdn"),
+    ("maltego.DNSName", EntityKind::Domain, "fqdn"),
+    ("maltego.MXRecord", EntityKind::Domain, "fqdn"),
+    ("maltego.NSRecord", EntityKind::Domain, "fqdn"),
+    ("maltego.IPv4Address", EntityKind::IP, "ipv4-address"),
+    ("maltego.IPv6Address", EntityKind::IP, "ipv6-address"),
+    ("maltego.EmailAddress", EntityKind::EmailAddress, "email"),
+    ("maltego.Person", EntityKind::Person, "person.fullname"),
+    (
+        "maltego.PhoneNumber",
+        EntityKind::PhoneNumber,
+        "phonenumber",
+    ),
+    ("maltego.Website", EntityKind::Website, "fqdn"),
+    ("maltego.URL", EntityKind::Website, "url"),
+    ("maltego.Organization", EntityKind::Organization, "title"),
+    ("maltego.Company", EntityKind::Organization, "title"),
+];
+
+/// The prefix of the social network entities, e.g. `maltego.affiliation.Twitter`.
+const AFFILIATION_PREFIX: &str = "maltego.affiliation.";
+
+/// The properties of a link holding its label.
+const LINK_LABELS: [&str; 2] = ["maltego.link.manual.type", "maltego.link.label"];
+
+/// Read a graph from a Maltego graph file (`.mtgx`).
+///
+/// Entities of types schnuffel knows become nodes of that type. Entities of
+/// other types, and entities whose value isn't valid for their type, are kept
+/// as generic entities with all their properties. Link labels become the kind
+/// of the edges.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't a zip archive or the graphs inside it
+/// aren't valid `GraphML`.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.starts_with("Graphs/") && name.ends_with(".graphml"))
+        .map(ToString::to_string)
+        .collect();
+    if names.is_empty() {
+        return Err(Error::InvalidFormat(
+            "the archive contains no graph".to_string(),
+        ));
+    }
+    names.sort();
+
+    let mut graph = VisualGraph::empty();
+    for name in names {
+        let mut content = Vec::new();
+        archive.by_name(&name)?.read_to_end(&mut content)?;
+        graph.merge(import_graph(&content)?);
+    }
+    Ok(graph)
+}
+
+fn import_graph(data: &[u8]) -> Result<VisualGraph, Error> {
+    let root = xml::parse(data)?;
+    let graph_element = root
+        .child("graph")
+        .ok_or_else(|| Error::InvalidFormat("the file contains no graph".to_string()))?;
+
+    let mut graph = VisualGraph::empty();
+    let mut ids = HashMap::new();
+    for element in graph_element.children_named("node") {
+        let xml_id = element
+            .attribute("id")
+            .ok_or_else(|| Error::InvalidFormat("a node has no id".to_string()))?;
+        let Some(maltego_entity) = element.descendants("MaltegoEntity").into_iter().next() else {
+            continue;
+        };
+
+        let id = graph.next_id();
+        let free = graph.free_position();
+        let position = element.descendants("Position").into_iter().next();
+        let coordinate = |name, default| {
+            position
+                .and_then(|p| p.attribute(name))
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        graph.push_node(VisualNode {
+            node: entity(maltego_entity),
+            id,
+            x: coordinate("x", free.x),
+            y: coordinate("y", free.y),
+            ..Default::default()
+        });
+        ids.insert(xml_id, id);
+    }
+
+    for element in graph_element.children_named("edge") {
+        let endpoint = |name| {
+            element
+                .attribute(name)
+                .and_then(|xml_id| ids.get(xml_id))
+                .copied()
+        };
+        // links to entities we couldn't read are dropped with them
+        let (Some(from), Some(to)) = (endpoint("source"), endpoint("target")) else {
+            continue;
+        };
+        let properties = element
+            .descendants("MaltegoLink")
+            .into_iter()
+            .next()
+            .map(properties)
+            .unwrap_or_default();
+        let label = LINK_LABELS.iter().find_map(|label| {
+            properties
+                .iter()
+                .find(|(name, value)| name == label && !value.is_empty())
+        });
+
+        graph.edges.push(VisualEdge {
+            from,
+            to,
+            kind: label.map_or(EdgeKind::default(), |(_, value)| EdgeKind::from_name(value)),
+            weight: None,
+            time: None,
+            provenance: None,
+        });
+    }
+
+    Ok(graph)
+}
+
+/// The properties of an entity or link by their name, in the order of the file.
+fn properties(element: &Element) -> Vec<(String, String)> {
+    element
+        .descendants("Property")
+        .into_iter()
+        .filter_map(|property| {
+            let name = property.attribute("name")?;
+            let value = property.child("Value").map_or("", |v| v.text.trim());
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn entity(element: &Element) -> Entity {
+    let entity_type = element.attribute("type").unwrap_or_default();
+    let properties = properties(element);
+    let property = |name: &str| {
+        properties
+            .iter()
+            .find(|(property, value)| property == name && !value.is_empty())
+            .map(|(_, value)| value.as_str())
+    };
+
+    let node = if let Some((_, kind, name)) = ENTITY_TYPES.iter().find(|t| t.0 == entity_type) {
+        property(name).and_then(|value| match kind {
+            // websites are stored by their domain name
+            EntityKind::Website if !value.contains("://") => {
+                kind.create(&format!("http://{value}")).ok()
+            }
+            _ => kind.create(value).ok(),
+        })
+    } else if entity_type.starts_with(AFFILIATION_PREFIX) {
+        property("affiliation.profile-url")
+            .and_then(|value| EntityKind::SocialMedia.create(value).ok())
+    } else {
+        None
+    };
+    if let Some(node) = node {
+        return node.into();
+    }
+
+    // keep the first property with a value as the value of the entity
+    let mut properties = properties
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty());
+    let value = properties
+        .next()
+        .map(|(_, value)| value)
+        .unwrap_or_default();
+    Entity::Generic(GenericEntity {
+        entity_type: entity_type.to_string(),
+        value,
+        properties: properties.collect(),
+    })
+}
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::Utc;
+use schnuffel_types::graph::{DNSRecord, Domain, EmailAddress, Node, PhoneNumber};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::annotation::{Annotations, Confidence};
+use crate::entity::{Entity, GenericEntity};
+use crate::error::Error;
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// A MISP attribute as `(object relation, type, value)`.
+type Attribute<'a> = (&'a str, &'a str, &'a str);
+
+/// The namespace of the machine tags holding the confidence and properties of
+/// entities, like `schnuffel:confidence="high"`.
+const TAG_NAMESPACE: &str = "schnuffel";
+
+/// Write a graph as a MISP event.
+///
+/// Every entity becomes a MISP object holding typed attributes, edges become
+/// references between those objects. The notes of an entity become the comment
+/// of its object, its tags, confidence and properties become tags of the
+/// attributes, see [`tags`].
+///
+/// # Errors
+///
+/// Returns an error if the event can't be serialized.
+pub fn export(graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+    let uuids: HashMap<usize, Uuid> = graph.nodes.iter().map(|n| (n.id, Uuid::new_v4())).collect();
+
+    let mut objects = Vec::new();
+    for node in &graph.nodes {
+        let (name, mut attributes) = misp_object(&node.node);
+        let tags = tags(&node.annotations);
+        if !tags.is_empty() {
+            for attribute in &mut attributes {
+                attribute["Tag"] = json!(tags);
+            }
+        }
+        let references: Vec<Value> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.from == node.id)
+            .filter_map(|edge| {
+                Some(json!({
+                    "referenced_uuid": uuids.get(&edge.to)?.to_string(),
+                    "relationship_type": relationship_type(&edge.kind),
+                }))
+            })
+            .collect();
+
+        objects.push(json!({
+            "name": name,
+            "meta-category": meta_category(name),
+            "uuid": uuids[&node.id].to_string(),
+            "distribution": "5",
+            "comment": node.annotations.notes,
+            "Attribute": attributes,
+            "ObjectReference": references,
+        }));
+    }
+
+    let event = json!({
+        "Event": {
+            "uuid": Uuid::new_v4().to_string(),
+            "info": "schnuffel investigation",
+            "date": Utc::now().format("%Y-%m-%d").to_string(),
+            "threat_level_id": "4",
+            "analysis": "0",
+            "distribution": "0",
+            "published": false,
+            "Attribute": [],
+            "Object": objects,
+        }
+    });
+    Ok(serde_json::to_vec_pretty(&event)?)
+}
+
+/// Read the entities of a MISP event.
+///
+/// Both loose attributes and objects are read. Attributes of types schnuffel has
+/// no entity for are skipped, and so are attributes and objects with malformed
+/// values, see [`import_counting`].
+///
+/// # Errors
+///
+/// Returns an error if the data isn't a MISP event.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    import_counting(data).map(|(graph, _)| graph)
+}
+
+/// Read the entities of a MISP event like [`import`], also returning how many
+/// attributes and objects were skipped because of malformed values, like an
+/// `ip-dst` holding a network instead of an address.
+///
+/// # Errors
+///
+/// Returns an error if the data isn't a MISP event.
+pub fn import_counting(data: &[u8]) -> Result<(VisualGraph, usize), Error> {
+    let json: Value = serde_json::from_slice(data)?;
+    let event = json.get("Event").unwrap_or(&json);
+    if !event.is_object() {
+        return Err(Error::InvalidFormat(
+            "the file is no MISP event".to_string(),
+        ));
+    }
+
+    let mut graph = VisualGraph::empty();
+    // every MISP uuid and the node it was turned into
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut skipped = 0;
+
+    for attribute in list(event, "Attribute") {
+        let Ok(nodes) =
+            nodes_from_attribute(string(attribute, "type")?, string(attribute, "value")?)
+        else {
+            skipped += 1;
+            continue;
+        };
+        if let Some(id) = add_linked(&mut graph, nodes) {
+            graph.annotate(id, annotations(attribute, [attribute]));
+            ids.insert(string(attribute, "uuid")?, id);
+        }
+    }
+
+    for object in list(event, "Object") {
+        let attributes = list(object, "Attribute")
+            .map(|attribute| {
+                Ok((
+                    string(attribute, "object_relation")?,
+                    string(attribute, "type")?,
+                    string(attribute, "value")?,
+                ))
+            })
+            .collect::<Result<Vec<Attribute>, Error>>()?;
+
+        let Ok(nodes) = nodes_from_object(string(object, "name")?, &attributes) else {
+            skipped += 1;
+            continue;
+        };
+        if let Some(id) = add_linked(&mut graph, nodes) {
+            graph.annotate(id, annotations(object, list(object, "Attribute")));
+            ids.insert(string(object, "uuid")?, id);
+            // references to single attributes of the object point to the whole object
+            for attribute in list(object, "Attribute") {
+                ids.insert(string(attribute, "uuid")?, id);
+            }
+        }
+    }
+
+    for object in list(event, "Object") {
+        let Some(from) = ids.get(string(object, "uuid")?) else {
+            continue;
+        };
+        for reference in list(object, "ObjectReference") {
+            if let Some(to) = ids.get(string(reference, "referenced_uuid")?) {
+                let kind = match string(reference, "relationship_type")? {
+                    "related-to" => EdgeKind::Related,
+                    other => EdgeKind::from_name(other),
+                };
+                graph.add_edge(*from, *to, kind);
+            }
+        }
+    }
+
+    Ok((graph, skipped))
+}
+
+/// The MISP object template and the attributes of an entity.
+///
+/// Generic entities keep their type as the object name, so they can be read back.
+fn misp_object(entity: &Entity) -> (&str, Vec<Value>) {
+    let node = match entity {
+        Entity::Node(node) => node,
+        Entity::Generic(generic) => {
+            let mut attributes = vec![attribute("value", "text", &generic.value)];
+            for (name, value) in &generic.properties {
+                attributes.push(attribute(name, "text", value));
+            }
+            return (&generic.entity_type, attributes);
+        }
+    };
+    match node {
+        Node::IP(ip) => ("domain-ip", vec![attribute("ip", "ip-dst", ip)]),
+        Node::Domain(domain) => (
+            "domain-ip",
+            vec![attribute("domain", "domain", &domain.domain)],
+        ),
+        Node::EmailAddress(email) => ("email", vec![attribute("from", "email-src", &email.email)]),
+        Node::Website { url } => ("url", vec![attribute("url", "url", url)]),
+        Node::Person(name) => ("person", vec![attribute("full-name", "full-name", name)]),
+        Node::Organization(name) => ("organization", vec![attribute("name", "text", name)]),
+        Node::PhoneNumber(number) => (
+            "phone",
+            vec![attribute("phone-number", "phone-number", &number.number)],
+        ),
+        Node::SocialMedia {
+            social_media_url,
+            account_url,
+        } => (
+            "user-account",
+            vec![
+                attribute("link", "link", account_url),
+                attribute("social-media-url", "url", social_media_url),
+            ],
+        ),
+        Node::DNSEntry { nameserver, record } => {
+            let mut attributes = vec![attribute("ns-record", "domain", &nameserver.domain)];
+            match record {
+                DNSRecord::A(addr) => attributes.push(attribute("a-record", "ip-dst", addr)),
+                DNSRecord::AAAA(addr) => {
+                    attributes.push(attribute("aaaa-record", "ip-dst", addr));
+                }
+                DNSRecord::MX(domain) => {
+                    attributes.push(attribute("mx-record", "domain", &domain.domain));
+                }
+                DNSRecord::TXT(txt) => attributes.push(attribute("txt-record", "text", txt)),
+                DNSRecord::SRV {
+                    service,
+                    protocol,
+                    from,
+                    to,
+                    to_port,
+                } => {
+                    attributes.push(attribute("queried-domain", "domain", from));
+                    attributes.push(attribute("srv-record", "domain", to));
+                    attributes.push(attribute("srv-service", "text", service));
+                    attributes.push(attribute("srv-protocol", "text", protocol));
+                    attributes.push(attribute("srv-port", "port", to_port));
+                }
+                DNSRecord::CNAME { from, to } => {
+                    attributes.push(attribute("queried-domain", "domain", from));
+                    attributes.push(attribute("cname-record", "domain", &to.domain));
+                }
+            }
+            ("dns-record", attributes)
+        }
+    }
+}
+
+fn attribute(relation: &str, kind: &str, value: impl Display) -> Value {
+    json!({
+        "uuid": Uuid::new_v4().to_string(),
+        "object_relation": relation,
+        "type": kind,
+        "category": category(kind),
+        "value": value.to_string(),
+        "to_ids": false,
+    })
+}
+
+fn category(kind: &str) -> &'static str {
+    match kind {
+        "ip-dst" | "domain" | "url" | "port" => "Network activity",
+        "email-src" => "Payload delivery",
+        "full-name" | "phone-number" => "Person",
+        "link" => "Social network",
+        _ => "Other",
+    }
+}
+
+fn meta_category(name: &str) -> &'static str {
+    match name {
+        "domain-ip" | "url" | "email" | "dns-record" => "network",
+        _ => "misc",
+    }
+}
+
+fn relationship_type(kind: &EdgeKind) -> &str {
+    match kind {
+        EdgeKind::Related => "related-to",
+        other => other.name(),
+    }
+}
+
+/// The tags of the attributes of an entity: its own tags, and machine tags in
+/// the [`TAG_NAMESPACE`] for its confidence and properties.
+fn tags(annotations: &Annotations) -> Vec<Value> {
+    let mut names = annotations.tags.clone();
+    if annotations.confidence != Confidence::Unknown {
+        names.push(machine_tag("confidence", annotations.confidence.name()));
+    }
+    for (name, value) in &annotations.properties {
+        names.push(machine_tag("property", &format!("{name}={value}")));
+    }
+    names
+        .into_iter()
+        .map(|name| json!({ "name": name }))
+        .collect()
+}
+
+fn machine_tag(predicate: &str, value: &str) -> String {
+    // the value is quoted and escaped like a JSON string
+    format!("{TAG_NAMESPACE}:{predicate}={}", Value::from(value))
+}
+
+/// Read the annotations of the entities of an object or loose attribute from
+/// its comment and the tags of its attributes, see [`tags`].
+fn annotations<'a>(
+    element: &Value,
+    attributes: impl IntoIterator<Item = &'a Value>,
+) -> Annotations {
+    let mut annotations = Annotations {
+        notes: element
+            .get("comment")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        ..Annotations::default()
+    };
+    let names = attributes
+        .into_iter()
+        .flat_map(|attribute| list(attribute, "Tag"))
+        .filter_map(|tag| tag.get("name")?.as_str());
+    for name in names {
+        let machine_value = |predicate: &str| {
+            let value = name.strip_prefix(&format!("{TAG_NAMESPACE}:{predicate}="))?;
+            serde_json::from_str::<String>(value).ok()
+        };
+        if let Some(confidence) = machine_value("confidence") {
+            annotations.confidence = Confidence::from_name(&confidence).unwrap_or_default();
+        } else if let Some(property) = machine_value("property") {
+            let (name, value) = property.split_once('=').unwrap_or((&property, ""));
+            annotations
+                .properties
+                .insert(name.to_string(), value.to_string());
+        } else if !annotations.tags.iter().any(|tag| tag == name) {
+            annotations.tags.push(name.to_string());
+        }
+    }
+    annotations
+}
+
+/// Turn a loose attribute into entities.
+///
+/// Most attributes describe one entity, composite ones like `domain|ip` describe
+/// several.
+fn nodes_from_attribute(kind: &str, value: &str) -> Result<Vec<Node>, Error> {
+    let node = match kind {
+        "ip-src" | "ip-dst" => Node::IP(parse(value)?),
+        "domain" | "hostname" => Node::Domain(Domain {
+            domain: value.to_string(),
+        }),
+        "email" | "email-src" | "email-dst" | "whois-registrant-email" => {
+            Node::EmailAddress(EmailAddress {
+                email: value.to_string(),
+            })
+        }
+        "url" | "link" => Node::Website { url: parse(value)? },
+        "phone-number" | "whois-registrant-phone" => Node::PhoneNumber(PhoneNumber {
+            number: value.to_string(),
+        }),
+        "full-name" | "whois-registrant-name" => Node::Person(value.to_string()),
+        "whois-registrant-org" => Node::Organization(value.to_string()),
+        "domain|ip" | "hostname|ip" => {
+            let (domain, ip) = value
+                .split_once('|')
+                .ok_or_else(|| Error::InvalidFormat(format!("malformed {kind} {value}")))?;
+            return Ok(vec![
+                Node::Domain(Domain {
+                    domain: domain.to_string(),
+                }),
+                Node::IP(parse(ip)?),
+            ]);
+        }
+        _ => return Ok(Vec::new()),
+    };
+    Ok(vec![node])
+}
+
+/// Turn an object into entities.
+///
+/// Objects written by us become a single entity again, other objects become one
+/// entity per attribute. Objects with a `value` but no attributes schnuffel
+/// understands become a generic entity.
+fn nodes_from_object(name: &str, attributes: &[Attribute]) -> Result<Vec<Entity>, Error> {
+    let get = |relation: &str| {
+        attributes
+            .iter()
+            .find(|(r, _, _)| *r == relation)
+            .map(|(_, _, value)| *value)
+    };
+
+    match (name, get("name"), get("link"), get("social-media-url")) {
+        ("organization", Some(name), _, _) => Ok(vec![Node::Organization(name.to_string()).into()]),
+        ("user-account", _, Some(account_url), Some(social_media_url)) => {
+            Ok(vec![Node::SocialMedia {
+                social_media_url: parse(social_media_url)?,
+                account_url: parse(account_url)?,
+            }
+            .into()])
+        }
+        ("dns-record", _, _, _) => Ok(dns_entries(attributes)?
+            .into_iter()
+            .map(Entity::from)
+            .collect()),
+        _ => {
+            let mut nodes = Vec::new();
+            for (_, kind, value) in attributes {
+                nodes.extend(
+                    nodes_from_attribute(kind, value)?
+                        .into_iter()
+                        .map(Entity::from),
+                );
+            }
+            if let (true, Some(value)) = (nodes.is_empty(), get("value")) {
+                nodes.push(Entity::Generic(GenericEntity {
+                    entity_type: name.to_string(),
+                    value: value.to_string(),
+                    properties: attributes
+                        .iter()
+                        .filter(|(relation, _, _)| *relation != "value")
+                        .map(|(relation, _, value)| ((*relation).to_string(), (*value).to_string()))
+                        .collect(),
+                }));
+            }
+            Ok(nodes)
+        }
+    }
+}
+
+/// Turn a `dns-record` object into one DNS entry per record.
+fn dns_entries(attributes: &[Attribute]) -> Result<Vec<Node>, Error> {
+    let get = |relation: &str| {
+        attributes
+            .iter()
+            .find(|(r, _, _)| *r == relation)
+            .map_or("", |(_, _, value)| *value)
+    };
+    let nameserver = Domain {
+        domain: get("ns-record").to_string(),
+    };
+    let queried_domain = get("queried-domain");
+
+    let mut entries = Vec::new();
+    for (relation, _, value) in attributes {
+        let record = match *relation {
+            "a-record" => DNSRecord::A(parse(value)?),
+            "aaaa-record" => DNSRecord::AAAA(parse(value)?),
+            "mx-record" => DNSRecord::MX(Domain {
+                domain: (*value).to_string(),
+            }),
+            "txt-record" => DNSRecord::TXT((*value).to_string()),
+            "cname-record" => DNSRecord::CNAME {
+                from: queried_domain.to_string(),
+                to: Domain {
+                    domain: (*value).to_string(),
+                },
+            },
+            "srv-record" => DNSRecord::SRV {
+                service: get("srv-service").to_string(),
+                protocol: get("srv-protocol").to_string(),
+                from: queried_domain.to_string(),
+                to: (*value).to_string(),
+                to_port: parse(get("srv-port"))?,
+            },
+            _ => continue,
+        };
+        entries.push(Node::DNSEntry {
+            nameserver: nameserver.clone(),
+            record,
+        });
+    }
+    Ok(entries)
+}
+
+/// Add entities that belong together and link them to the first one, whose id is
+/// returned.
+fn add_linked<E: Into<Entity>>(graph: &mut VisualGraph, nodes: Vec<E>) -> Option<usize> {
+    let mut nodes = nodes.into_iter().map(Into::into);
+    let first = nodes.next()?;
+    let is_domain = matches!(first, Entity::Node(Node::Domain(_)));
+    let first_id = graph.add_node(first);
+
+    for node in nodes {
+        let kind = if is_domain && matches!(node, Entity::Node(Node::IP(_))) {
+            EdgeKind::ResolvesTo
+        } else {
+            EdgeKind::Related
+        };
+        let id = graph.add_node(node);
+        if id != first_id {
+            graph.add_edge(first_id, id, kind);
+        }
+    }
+    Some(first_id)
+}
+
+fn list<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> Result<&'a str, Error> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::InvalidFormat(format!("a MISP element has no {key}")))
+}
+
+fn parse<T>(value: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| Error::InvalidFormat(format!("malformed MISP value {value}: {e}")))
+}
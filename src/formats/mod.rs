@@ -0,0 +1,173 @@
+pub mod attributes;
+pub mod builder;
+pub mod csv;
+pub mod dot;
+pub mod email;
+pub mod gexf;
+pub mod graphml;
+pub mod image;
+pub mod jsonl;
+pub mod maltego;
+pub mod misp;
+pub mod nmap;
+pub mod pcap;
+pub mod report;
+pub mod stix;
+pub mod xml;
+pub mod zone;
+
+use std::fmt::{self, Display};
+
+use crate::error::Error;
+use crate::views::graph::VisualGraph;
+
+/// All file formats investigations can be imported from and exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    GraphML,
+    Gexf,
+    Stix,
+    Misp,
+    Dot,
+    Maltego,
+    JsonLines,
+    Nmap,
+    Zone,
+    Email,
+    Pcap,
+}
+
+impl Format {
+    pub const ALL: [Format; 11] = [
+        Format::GraphML,
+        Format::Gexf,
+        Format::Stix,
+        Format::Misp,
+        Format::Dot,
+        Format::Maltego,
+        Format::JsonLines,
+        Format::Nmap,
+        Format::Zone,
+        Format::Email,
+        Format::Pcap,
+    ];
+
+    /// The file extension of the format.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        self.extensions()[0]
+    }
+
+    /// All extensions files of the format commonly have, the preferred one first.
+    #[must_use]
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::GraphML => &["graphml"],
+            Format::Gexf => &["gexf"],
+            Format::Stix | Format::Misp => &["json"],
+            Format::Dot => &["dot", "gv"],
+            Format::Maltego => &["mtgx"],
+            Format::JsonLines => &["jsonl", "ndjson"],
+            Format::Nmap => &["xml"],
+            // dig output is usually saved as plain text
+            Format::Zone => &["zone", "db", "txt"],
+            Format::Email => &["eml", "mbox"],
+            Format::Pcap => &["pcap", "pcapng", "cap"],
+        }
+    }
+
+    /// Whether imported files are added to the current investigation instead of
+    /// replacing it.
+    ///
+    /// Formats that store whole investigations replace the graph, formats used to
+    /// exchange findings with others add to it.
+    #[must_use]
+    pub fn merges(self) -> bool {
+        match self {
+            Format::GraphML | Format::Gexf | Format::Dot | Format::Maltego => false,
+            Format::Stix
+            | Format::Misp
+            | Format::JsonLines
+            | Format::Nmap
+            | Format::Zone
+            | Format::Email
+            | Format::Pcap => true,
+        }
+    }
+
+    /// Whether imported entities that are already in the investigation update
+    /// them instead of being added again, so repeated scans don't duplicate
+    /// their results.
+    #[must_use]
+    pub fn updates(self) -> bool {
+        matches!(self, Format::Nmap | Format::Zone | Format::Pcap)
+    }
+
+    /// Whether graphs can be written in this format.
+    #[must_use]
+    pub fn exports(self) -> bool {
+        !matches!(
+            self,
+            Format::Maltego | Format::Nmap | Format::Zone | Format::Email | Format::Pcap
+        )
+    }
+
+    /// Read a graph from the content of a file in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data isn't valid for the format.
+    pub fn import(self, data: &[u8]) -> Result<VisualGraph, Error> {
+        match self {
+            Format::GraphML => graphml::import(data),
+            Format::Gexf => gexf::import(data),
+            Format::Stix => stix::import(data),
+            Format::Misp => misp::import(data),
+            Format::Dot => dot::import(data),
+            Format::Maltego => maltego::import(data),
+            Format::JsonLines => jsonl::read(data),
+            Format::Nmap => nmap::import(data),
+            Format::Zone => zone::import(data),
+            Format::Email => email::import(data),
+            Format::Pcap => pcap::import(data),
+        }
+    }
+
+    /// Write a graph in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph can't be represented in the format or the
+    /// format can only be imported.
+    pub fn export(self, graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+        match self {
+            Format::GraphML => graphml::export(graph),
+            Format::Gexf => gexf::export(graph),
+            Format::Stix => stix::export(graph),
+            Format::Misp => misp::export(graph),
+            Format::Dot => dot::export(graph),
+            Format::JsonLines => jsonl::export(graph),
+            Format::Maltego | Format::Nmap | Format::Zone | Format::Email | Format::Pcap => Err(
+                Error::InvalidFormat(format!("{self} files can only be imported")),
+            ),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::GraphML => write!(f, "GraphML"),
+            Format::Gexf => write!(f, "GEXF"),
+            Format::Stix => write!(f, "STIX 2.1"),
+            Format::Misp => write!(f, "MISP event"),
+            Format::Dot => write!(f, "Graphviz DOT"),
+            Format::Maltego => write!(f, "Maltego graph"),
+            Format::JsonLines => write!(f, "JSON Lines"),
+            Format::Nmap => write!(f, "Nmap XML"),
+            Format::Zone => write!(f, "DNS zone / dig"),
+            Format::Email => write!(f, "Email (.eml / mbox)"),
+            Format::Pcap => write!(f, "Packet capture"),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::net::{IpAddr, SocketAddr};
+
+use schnuffel_types::graph::{Domain, Node};
+
+use crate::entity::{Entity, GenericEntity};
+use crate::error::Error;
+use crate::formats::xml::{self, Element};
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// The type of the entity grouping the results of a scan.
+pub const TARGET_TYPE: &str = "Scan Target";
+
+/// The type of the entities for open ports.
+pub const PORT_TYPE: &str = "Port";
+
+/// The options of nmap that take a value as the next argument, so the value
+/// isn't taken for a target.
+const VALUE_OPTIONS: [&str; 26] = [
+    "-p",
+    "-e",
+    "-g",
+    "-D",
+    "-S",
+    "-iL",
+    "-iR",
+    "-oN",
+    "-oX",
+    "-oG",
+    "-oA",
+    "-oS",
+    "--exclude",
+    "--excludefile",
+    "--exclude-ports",
+    "--top-ports",
+    "--port-ratio",
+    "--script",
+    "--script-args",
+    "--source-port",
+    "--data-length",
+    "--ttl",
+    "--max-retries",
+    "--host-timeout",
+    "--dns-servers",
+    "--stylesheet",
+];
+
+/// Read the result of an nmap scan written with `-oX`.
+///
+/// Every host that is up becomes an IP entity, connected to its hostnames with
+/// resolves-to edges and to an entity for each open port, which carries the
+/// service nmap detected as properties. All hosts are grouped under an entity
+/// for the targets of the scan.
+///
+/// The result is meant to be added with [`VisualGraph::update`], so scanning
+/// the same targets again updates the entities instead of duplicating them.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't an nmap XML report.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    let root = xml::parse(data)?;
+    if root.name != "nmaprun" {
+        return Err(Error::InvalidFormat(
+            "the file isn't an nmap XML report".to_string(),
+        ));
+    }
+
+    let command = root.attribute("args").unwrap_or_default();
+    let targets = targets(command);
+    let mut properties = vec![("command".to_string(), command.to_string())];
+    if let Some(start) = root.attribute("startstr") {
+        properties.push(("started".to_string(), start.to_string()));
+    }
+
+    let mut graph = VisualGraph::empty();
+    let target = graph.add_node(Entity::Generic(GenericEntity {
+        entity_type: TARGET_TYPE.to_string(),
+        value: if targets.is_empty() {
+            command.to_string()
+        } else {
+            targets.join(" ")
+        },
+        properties,
+    }));
+
+    for host in root.children_named("host") {
+        let is_up = host
+            .child("status")
+            .and_then(|status| status.attribute("state"))
+            .is_none_or(|state| state == "up");
+        let address = host
+            .children_named("address")
+            .filter(|a| matches!(a.attribute("addrtype"), Some("ipv4" | "ipv6")))
+            .find_map(|a| a.attribute("addr")?.parse::<IpAddr>().ok());
+        let Some(address) = address.filter(|_| is_up) else {
+            continue;
+        };
+
+        let ip = graph.add_node(Node::IP(address));
+        graph.add_edge(target, ip, EdgeKind::Custom("scanned".to_string()));
+        add_hostnames(&mut graph, host, ip);
+        add_ports(&mut graph, host, ip, address);
+    }
+
+    Ok(graph)
+}
+
+/// The targets on the command line of a scan.
+fn targets(command: &str) -> Vec<&str> {
+    let mut targets = Vec::new();
+    // skip the name of the program
+    let mut arguments = command.split_whitespace().skip(1);
+    while let Some(argument) = arguments.next() {
+        if VALUE_OPTIONS.contains(&argument) {
+            arguments.next();
+        } else if !argument.starts_with('-') {
+            targets.push(argument);
+        }
+    }
+    targets
+}
+
+fn add_hostnames(graph: &mut VisualGraph, host: &Element, ip: usize) {
+    // nmap lists a name twice if it was given by the user and found by reverse DNS
+    let mut names: Vec<&str> = host
+        .child("hostnames")
+        .into_iter()
+        .flat_map(|hostnames| hostnames.children_named("hostname"))
+        .filter_map(|hostname| hostname.attribute("name"))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    for name in names {
+        let domain = graph.add_node(Node::Domain(Domain {
+            domain: name.to_string(),
+        }));
+        graph.add_edge(domain, ip, EdgeKind::ResolvesTo);
+    }
+}
+
+fn add_ports(graph: &mut VisualGraph, host: &Element, ip: usize, address: IpAddr) {
+    let ports = host
+        .child("ports")
+        .into_iter()
+        .flat_map(|ports| ports.children_named("port"));
+    for port in ports {
+        let is_open = port
+            .child("state")
+            .and_then(|state| state.attribute("state"))
+            == Some("open");
+        let number = port.attribute("portid").and_then(|p| p.parse().ok());
+        let Some(number) = number.filter(|_| is_open) else {
+            continue;
+        };
+
+        let protocol = port.attribute("protocol").unwrap_or("tcp");
+        let mut properties = Vec::new();
+        if let Some(service) = port.child("service") {
+            for (attribute, property) in [
+                ("name", "service"),
+                ("product", "product"),
+                ("version", "version"),
+                ("extrainfo", "extra info"),
+            ] {
+                if let Some(value) = service.attribute(attribute) {
+                    properties.push((property.to_string(), value.to_string()));
+                }
+            }
+        }
+
+        let port = graph.add_node(Entity::Generic(GenericEntity {
+            entity_type: PORT_TYPE.to_string(),
+            value: format!("{}/{protocol}", SocketAddr::new(address, number)),
+            properties,
+        }));
+        graph.add_edge(ip, port, EdgeKind::Custom("open-port".to_string()));
+    }
+}
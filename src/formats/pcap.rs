@@ -0,0 +1,779 @@
+use std::fmt::{self, Display};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use schnuffel_types::graph::{DNSRecord, Domain, Node};
+use url::{Host, Url};
+
+use crate::entity::EntityKind;
+use crate::error::Error;
+use crate::formats::builder::GraphBuilder;
+use crate::formats::zone;
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// What the weights of the edges between communicating IPs count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PcapWeight {
+    #[default]
+    Packets,
+    Bytes,
+}
+
+impl PcapWeight {
+    pub const ALL: [PcapWeight; 2] = [PcapWeight::Packets, PcapWeight::Bytes];
+}
+
+impl Display for PcapWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcapWeight::Packets => write!(f, "Count packets"),
+            PcapWeight::Bytes => write!(f, "Count bytes"),
+        }
+    }
+}
+
+// pcapng block types
+const SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION: u32 = 1;
+const PACKET_BLOCK: u32 = 2;
+const SIMPLE_PACKET: u32 = 3;
+const ENHANCED_PACKET: u32 = 6;
+
+// link types, see https://www.tcpdump.org/linktypes.html
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+/// Link types whose frames are bare IP packets. 12 and 14 are used for raw IP
+/// by some systems instead of 101.
+const RAW_LINK_TYPES: [u32; 5] = [12, 14, 101, 228, 229];
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: [u16; 2] = [0x8100, 0x88a8];
+
+const PROTOCOL_TCP: u8 = 6;
+const PROTOCOL_UDP: u8 = 17;
+
+const DNS_PORT: u16 = 53;
+
+// DNS record types
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const TYPE_MX: u16 = 15;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+
+/// How many compression pointers are followed in a DNS name, so malformed
+/// packets can't send the parser in circles.
+const MAX_NAME_POINTERS: usize = 16;
+
+/// The start of the first line of HTTP requests.
+const HTTP_METHODS: [&str; 9] = [
+    "GET ", "POST ", "HEAD ", "PUT ", "DELETE ", "OPTIONS ", "PATCH ", "CONNECT ", "TRACE ",
+];
+
+/// A frame as it was captured.
+struct Frame<'a> {
+    link_type: u32,
+    data: &'a [u8],
+    /// The length of the frame on the wire, which may be more than was captured.
+    length: u32,
+}
+
+/// The parts of an IP packet that are used.
+struct Packet<'a> {
+    source: IpAddr,
+    destination: IpAddr,
+    protocol: u8,
+    /// The payload, empty for fragments after the first one.
+    payload: &'a [u8],
+}
+
+/// Read a packet capture with the default weights, see [`import_with`].
+///
+/// # Errors
+///
+/// Returns an error if the file isn't a pcap or pcapng capture.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    import_with(data, PcapWeight::default())
+}
+
+/// Read a packet capture in the pcap or pcapng format, e.g. written by
+/// tcpdump or Wireshark.
+///
+/// Every pair of IPs that exchanged packets is connected by a
+/// communicates-with edge, whose weight counts the packets or bytes sent. DNS
+/// queries connect the client to the domain it asked for, and the A, AAAA,
+/// MX, TXT, CNAME and SRV records in DNS responses become DNS entries with the
+/// responding server as their nameserver. The Host header of HTTP requests
+/// becomes a website and the server name of TLS client hellos a domain, both
+/// connected to the client and the server.
+///
+/// Only the first segment of a TCP stream is looked at, so requests and
+/// hellos split across segments are missed. Frames that were cut off while
+/// capturing count with their full length.
+///
+/// # Errors
+///
+/// Returns an error if the file isn't a pcap or pcapng capture or contains no
+/// IP packets.
+pub fn import_with(data: &[u8], weight: PcapWeight) -> Result<VisualGraph, Error> {
+    let mut builder = GraphBuilder::new();
+    let mut has_packets = false;
+    for frame in frames(data)? {
+        let Some(packet) = ip_data(&frame).and_then(ip_packet) else {
+            continue;
+        };
+        has_packets = true;
+
+        let source = builder.node(Node::IP(packet.source));
+        let destination = builder.node(Node::IP(packet.destination));
+        let amount = match weight {
+            PcapWeight::Packets => 1.0,
+            PcapWeight::Bytes => f64::from(frame.length),
+        };
+        let kind = EdgeKind::Custom("communicates-with".to_string());
+        builder.add_weight(source, destination, kind, amount);
+
+        let Some((source_port, destination_port, payload)) = segment(&packet) else {
+            continue;
+        };
+        if packet.protocol == PROTOCOL_UDP
+            && (source_port == DNS_PORT || destination_port == DNS_PORT)
+        {
+            add_dns(&mut builder, &packet, payload);
+        } else if packet.protocol == PROTOCOL_TCP {
+            if let Some(host) = http_host(payload) {
+                add_http(&mut builder, &packet, &host);
+            } else if let Some(name) = tls_server_name(payload) {
+                let domain = builder.domain(&name);
+                add_request(&mut builder, &packet, domain);
+            }
+        }
+    }
+
+    if !has_packets {
+        return Err(Error::InvalidFormat(
+            "the capture contains no IP packets".to_string(),
+        ));
+    }
+    Ok(builder.finish())
+}
+
+fn u16_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    })
+}
+
+fn u32_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+/// Read a number in network byte order.
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    u16_at(data, offset, true)
+}
+
+/// All frames of a capture, telling the formats apart by their magic number.
+fn frames(data: &[u8]) -> Result<Vec<Frame<'_>>, Error> {
+    match data.get(..4) {
+        // with timestamps in microseconds or nanoseconds
+        Some([0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d]) => Ok(pcap_frames(data, true)),
+        Some([0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1]) => Ok(pcap_frames(data, false)),
+        Some([0x0a, 0x0d, 0x0d, 0x0a]) => pcapng_frames(data),
+        _ => Err(Error::InvalidFormat(
+            "the file isn't a pcap or pcapng capture".to_string(),
+        )),
+    }
+}
+
+/// The frames of a classic pcap file. A frame cut off at the end, e.g. because
+/// the capture was still running, is skipped.
+fn pcap_frames(data: &[u8], big_endian: bool) -> Vec<Frame<'_>> {
+    let link_type = u32_at(data, 20, big_endian).unwrap_or_default();
+    let mut frames = Vec::new();
+    let mut offset = 24;
+    while let (Some(captured), Some(length)) = (
+        u32_at(data, offset + 8, big_endian),
+        u32_at(data, offset + 12, big_endian),
+    ) {
+        let start = offset + 16;
+        let Some(frame) = data.get(start..start + captured as usize) else {
+            break;
+        };
+        frames.push(Frame {
+            link_type,
+            data: frame,
+            length,
+        });
+        offset = start + captured as usize;
+    }
+    frames
+}
+
+/// The frames of a pcapng file, which may have several sections with
+/// different byte orders and interfaces with different link types.
+fn pcapng_frames(data: &[u8]) -> Result<Vec<Frame<'_>>, Error> {
+    let mut frames = Vec::new();
+    // the link types of the interfaces of the current section
+    let mut interfaces = Vec::new();
+    let mut big_endian = false;
+    let mut offset = 0;
+    while let Some(block_type) = u32_at(data, offset, big_endian) {
+        if block_type == SECTION_HEADER {
+            big_endian = match data.get(offset + 8..offset + 12) {
+                Some([0x1a, 0x2b, 0x3c, 0x4d]) => true,
+                Some([0x4d, 0x3c, 0x2b, 0x1a]) => false,
+                _ => {
+                    return Err(Error::InvalidFormat(
+                        "a pcapng section has an unknown byte order".to_string(),
+                    ))
+                }
+            };
+            interfaces.clear();
+        }
+        let length = u32_at(data, offset + 4, big_endian).unwrap_or_default() as usize;
+        let Some(block) = data.get(offset..offset + length).filter(|_| length >= 12) else {
+            break;
+        };
+        let body = &block[8..length - 4];
+
+        match block_type {
+            INTERFACE_DESCRIPTION => {
+                interfaces.push(u16_at(body, 0, big_endian).map_or(0, u32::from));
+            }
+            PACKET_BLOCK | ENHANCED_PACKET => {
+                frames.extend(packet_block(block_type, body, &interfaces, big_endian));
+            }
+            SIMPLE_PACKET => frames.extend(simple_packet_block(body, &interfaces, big_endian)),
+            _ => {}
+        }
+        offset += length;
+    }
+    Ok(frames)
+}
+
+/// The frame in an enhanced packet block or the obsolete packet block, which
+/// only differ in the size of the interface id.
+fn packet_block<'a>(
+    block_type: u32,
+    body: &'a [u8],
+    interfaces: &[u32],
+    big_endian: bool,
+) -> Option<Frame<'a>> {
+    let interface = if block_type == PACKET_BLOCK {
+        u16_at(body, 0, big_endian).map(u32::from)
+    } else {
+        u32_at(body, 0, big_endian)
+    }?;
+    let captured = u32_at(body, 12, big_endian)? as usize;
+    Some(Frame {
+        link_type: *interfaces.get(interface as usize)?,
+        data: body.get(20..20 + captured)?,
+        length: u32_at(body, 16, big_endian)?,
+    })
+}
+
+/// The frame in a simple packet block, which always belongs to the first
+/// interface and doesn't store how much of the frame was captured.
+fn simple_packet_block<'a>(
+    body: &'a [u8],
+    interfaces: &[u32],
+    big_endian: bool,
+) -> Option<Frame<'a>> {
+    let length = u32_at(body, 0, big_endian)?;
+    // the block is padded, so it may be longer than the frame
+    let data = body.get(4..)?;
+    Some(Frame {
+        link_type: *interfaces.first()?,
+        data: &data[..data.len().min(length as usize)],
+        length,
+    })
+}
+
+/// The IP packet carried by a frame, if any.
+fn ip_data<'a>(frame: &Frame<'a>) -> Option<&'a [u8]> {
+    let data = frame.data;
+    let (offset, ethertype) = match frame.link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = be16(data, offset)?;
+            while ETHERTYPE_VLAN.contains(&ethertype) {
+                offset += 4;
+                ethertype = be16(data, offset)?;
+            }
+            (offset + 2, Some(ethertype))
+        }
+        LINKTYPE_LINUX_SLL => (16, Some(be16(data, 14)?)),
+        LINKTYPE_LINUX_SLL2 => (20, Some(be16(data, 0)?)),
+        // the address family is in host byte order, the IP version tells enough
+        LINKTYPE_NULL | LINKTYPE_LOOP => (4, None),
+        link_type if RAW_LINK_TYPES.contains(&link_type) => (0, None),
+        _ => return None,
+    };
+    if ethertype.is_some_and(|t| t != ETHERTYPE_IPV4 && t != ETHERTYPE_IPV6) {
+        return None;
+    }
+    data.get(offset..)
+}
+
+fn ip_packet(data: &[u8]) -> Option<Packet<'_>> {
+    match data.first()? >> 4 {
+        4 => {
+            let header_length = usize::from(data[0] & 0x0f) * 4;
+            let source: [u8; 4] = data.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = data.get(16..20)?.try_into().ok()?;
+            // the total length is 0 for packets that are segmented by the network card
+            let end = match usize::from(be16(data, 2)?) {
+                0 => data.len(),
+                length => length.min(data.len()),
+            };
+            let fragment_offset = be16(data, 6)? & 0x1fff;
+            Some(Packet {
+                source: IpAddr::V4(Ipv4Addr::from(source)),
+                destination: IpAddr::V4(Ipv4Addr::from(destination)),
+                protocol: data[9],
+                payload: if fragment_offset == 0 {
+                    data.get(header_length..end)?
+                } else {
+                    &[]
+                },
+            })
+        }
+        6 => {
+            let source: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = data.get(24..40)?.try_into().ok()?;
+            let end = match usize::from(be16(data, 4)?) {
+                0 => data.len(),
+                length => (40 + length).min(data.len()),
+            };
+            let (protocol, offset, is_first_fragment) = ipv6_extension_headers(data)?;
+            Some(Packet {
+                source: IpAddr::V6(Ipv6Addr::from(source)),
+                destination: IpAddr::V6(Ipv6Addr::from(destination)),
+                protocol,
+                payload: if is_first_fragment {
+                    data.get(offset..end)?
+                } else {
+                    &[]
+                },
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Skip the extension headers of an IPv6 packet, returning the protocol of the
+/// payload, where it starts and whether it is the start of the payload.
+fn ipv6_extension_headers(data: &[u8]) -> Option<(u8, usize, bool)> {
+    let mut next = *data.get(6)?;
+    let mut offset = 40;
+    let mut is_first_fragment = true;
+    loop {
+        match next {
+            // hop-by-hop options, routing and destination options
+            0 | 43 | 60 => {
+                next = *data.get(offset)?;
+                offset += (usize::from(*data.get(offset + 1)?) + 1) * 8;
+            }
+            // fragment
+            44 => {
+                next = *data.get(offset)?;
+                is_first_fragment = be16(data, offset + 2)? >> 3 == 0;
+                offset += 8;
+            }
+            protocol => return Some((protocol, offset, is_first_fragment)),
+        }
+    }
+}
+
+/// The ports and payload of a TCP or UDP packet.
+fn segment<'a>(packet: &Packet<'a>) -> Option<(u16, u16, &'a [u8])> {
+    let data = packet.payload;
+    let header_length = match packet.protocol {
+        PROTOCOL_TCP => usize::from(data.get(12)? >> 4) * 4,
+        PROTOCOL_UDP => 8,
+        _ => return None,
+    };
+    Some((be16(data, 0)?, be16(data, 2)?, data.get(header_length..)?))
+}
+
+/// Connect the client of a request to what it asked for and that to the
+/// server that was asked.
+fn add_request(builder: &mut GraphBuilder, packet: &Packet<'_>, target: usize) {
+    let client = builder.node(Node::IP(packet.source));
+    let server = builder.node(Node::IP(packet.destination));
+    builder.edge(client, target, EdgeKind::Custom("requested".to_string()));
+    builder.edge(target, server, EdgeKind::Custom("served-by".to_string()));
+}
+
+/// Add the questions of a DNS query or the answers of a response.
+fn add_dns(builder: &mut GraphBuilder, packet: &Packet<'_>, message: &[u8]) -> Option<()> {
+    let is_response = be16(message, 2)? & 0x8000 != 0;
+    let questions = be16(message, 4)?;
+    let answers = be16(message, 6)?;
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        let (name, end) = dns_name(message, offset)?;
+        // skip the type and class
+        offset = end + 4;
+        if !is_response && !name.is_empty() {
+            let client = builder.node(Node::IP(packet.source));
+            let domain = builder.domain(&name);
+            builder.edge(client, domain, EdgeKind::Custom("queried".to_string()));
+        }
+    }
+    if !is_response {
+        return Some(());
+    }
+
+    let nameserver = packet.source.to_string();
+    for _ in 0..answers {
+        let (owner, end) = dns_name(message, offset)?;
+        let record_type = be16(message, end)?;
+        // skip the class and TTL
+        let length = usize::from(be16(message, end + 8)?);
+        let start = end + 10;
+        offset = start + length;
+        let data = message.get(start..offset)?;
+        if let Some((record, target)) = dns_record(message, start, data, record_type, &owner) {
+            zone::add_entry(builder, &owner, &nameserver, record, target);
+        }
+    }
+    Some(())
+}
+
+/// The record in the data of an answer and what it points at. Names in the
+/// data may point into the rest of the message, which is why it is needed
+/// too.
+fn dns_record(
+    message: &[u8],
+    start: usize,
+    data: &[u8],
+    record_type: u16,
+    owner: &str,
+) -> Option<(DNSRecord, Option<Node>)> {
+    let domain = |offset| dns_name(message, offset).map(|(name, _)| Domain { domain: name });
+    match record_type {
+        TYPE_A => {
+            let addr = Ipv4Addr::from(<[u8; 4]>::try_from(data).ok()?);
+            Some((DNSRecord::A(addr), Some(Node::IP(IpAddr::V4(addr)))))
+        }
+        TYPE_AAAA => {
+            let addr = Ipv6Addr::from(<[u8; 16]>::try_from(data).ok()?);
+            Some((DNSRecord::AAAA(addr), Some(Node::IP(IpAddr::V6(addr)))))
+        }
+        TYPE_CNAME => {
+            let to = domain(start)?;
+            let cname = DNSRecord::CNAME {
+                from: owner.to_string(),
+                to: to.clone(),
+            };
+            Some((cname, Some(Node::Domain(to))))
+        }
+        TYPE_MX => {
+            // after the preference
+            let exchange = domain(start + 2)?;
+            Some((
+                DNSRecord::MX(exchange.clone()),
+                Some(Node::Domain(exchange)),
+            ))
+        }
+        TYPE_TXT => Some((DNSRecord::TXT(character_strings(data)), None)),
+        TYPE_SRV => {
+            // after the priority, weight and port
+            let to = domain(start + 6)?;
+            let srv = zone::srv(owner, be16(data, 4)?, &to.domain);
+            Some((srv, Some(Node::Domain(to))))
+        }
+        _ => None,
+    }
+}
+
+/// Read a possibly compressed DNS name, returning it and where the data after
+/// it starts.
+fn dns_name(message: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    // where the name ends in the data, if it continues elsewhere
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        let length = *message.get(offset)?;
+        if length == 0 {
+            return Some((labels.join("."), end.unwrap_or(offset + 1)));
+        }
+        if length & 0xc0 == 0xc0 {
+            pointers += 1;
+            if pointers > MAX_NAME_POINTERS {
+                return None;
+            }
+            end.get_or_insert(offset + 2);
+            offset = usize::from(be16(message, offset)? & 0x3fff);
+        } else {
+            let start = offset + 1;
+            offset = start + usize::from(length);
+            let label = message.get(start..offset)?;
+            labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        }
+    }
+}
+
+/// Join the length prefixed strings of a TXT record, like zone files do.
+fn character_strings(mut data: &[u8]) -> String {
+    let mut text = String::new();
+    while let Some((&length, rest)) = data.split_first() {
+        let (string, rest) = rest.split_at(usize::from(length).min(rest.len()));
+        text.push_str(&String::from_utf8_lossy(string));
+        data = rest;
+    }
+    text
+}
+
+/// The Host header of an HTTP request.
+fn http_host(payload: &[u8]) -> Option<String> {
+    if !HTTP_METHODS
+        .iter()
+        .any(|method| payload.starts_with(method.as_bytes()))
+    {
+        return None;
+    }
+    let request = String::from_utf8_lossy(payload);
+    let head = request.split("\r\n\r\n").next().unwrap_or_default();
+    head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("host")
+            .then(|| value.trim().to_ascii_lowercase())
+    })
+}
+
+/// Add the website of an HTTP request and the domain it is on.
+fn add_http(builder: &mut GraphBuilder, packet: &Packet<'_>, host: &str) {
+    let Ok(url) = Url::parse(&format!("http://{host}/")) else {
+        return;
+    };
+    let Ok(website) = EntityKind::Website.create(url.as_str()) else {
+        return;
+    };
+    let website = builder.node(website);
+    add_request(builder, packet, website);
+    if let Some(Host::Domain(name)) = url.host() {
+        let domain = builder.domain(name);
+        builder.edge(domain, website, EdgeKind::Related);
+    }
+}
+
+/// The server name a TLS client asked for in its hello.
+fn tls_server_name(payload: &[u8]) -> Option<String> {
+    // a handshake record starting with a client hello
+    if *payload.first()? != 22 || *payload.get(5)? != 1 {
+        return None;
+    }
+    // after the record and handshake headers
+    let hello = payload.get(9..)?;
+
+    // skip the version, random, session id, cipher suites and compression methods
+    let mut offset = 34;
+    offset += 1 + usize::from(*hello.get(offset)?);
+    offset += 2 + usize::from(be16(hello, offset)?);
+    offset += 1 + usize::from(*hello.get(offset)?);
+
+    let extensions_end = offset + 2 + usize::from(be16(hello, offset)?);
+    offset += 2;
+    while offset + 4 <= extensions_end {
+        let extension_type = be16(hello, offset)?;
+        let length = usize::from(be16(hello, offset + 2)?);
+        if extension_type == 0 {
+            // a list of names, of which only host names, type 0, are defined
+            let name_type = *hello.get(offset + 6)?;
+            let name_length = usize::from(be16(hello, offset + 7)?);
+            let name = hello.get(offset + 9..offset + 9 + name_length)?;
+            return (name_type == 0).then(|| String::from_utf8_lossy(name).to_ascii_lowercase());
+        }
+        offset += 4 + length;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use schnuffel_types::graph::{DNSRecord, Domain, Node};
+
+    use super::{dns_name, import, tls_server_name, LINKTYPE_ETHERNET};
+    use crate::entity::Entity;
+    use crate::views::graph::VisualGraph;
+
+    /// An Ethernet frame with an IPv4 UDP packet from 192.0.2.53:53 to
+    /// 192.0.2.10:40000.
+    fn udp_frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        frame.extend([0x08, 0x00]);
+
+        let total_length = u16::try_from(20 + 8 + payload.len()).unwrap();
+        frame.extend([0x45, 0]);
+        frame.extend(total_length.to_be_bytes());
+        frame.extend([0, 0, 0, 0, 64, 17, 0, 0]);
+        frame.extend([192, 0, 2, 53, 192, 0, 2, 10]);
+
+        frame.extend(53u16.to_be_bytes());
+        frame.extend(40000u16.to_be_bytes());
+        frame.extend((total_length - 20).to_be_bytes());
+        frame.extend([0, 0]);
+        frame.extend(payload);
+        frame
+    }
+
+    /// A response to an A query for example.com, answered with 93.184.216.34.
+    fn dns_response() -> Vec<u8> {
+        let mut message = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        message.extend(b"\x07example\x03com\x00");
+        message.extend([0, 1, 0, 1]);
+        // the owner points at the name in the question
+        message.extend([0xc0, 12, 0, 1, 0, 1, 0, 0, 0x0e, 0x10, 0, 4]);
+        message.extend([93, 184, 216, 34]);
+        message
+    }
+
+    fn pcap(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0];
+        data.extend([0; 8]);
+        data.extend(65535u32.to_le_bytes());
+        data.extend(LINKTYPE_ETHERNET.to_le_bytes());
+        for frame in frames {
+            let length = u32::try_from(frame.len()).unwrap();
+            data.extend([0; 8]);
+            data.extend(length.to_le_bytes());
+            data.extend(length.to_le_bytes());
+            data.extend(frame);
+        }
+        data
+    }
+
+    fn has_node(graph: &VisualGraph, node: Node) -> bool {
+        let entity = Entity::Node(node);
+        graph.nodes.iter().any(|visual| visual.node == entity)
+    }
+
+    #[test]
+    fn dns_responses_become_entries() {
+        let graph = import(&pcap(&[udp_frame(&dns_response())])).unwrap();
+
+        let address = Ipv4Addr::new(93, 184, 216, 34);
+        assert!(has_node(
+            &graph,
+            Node::Domain(Domain {
+                domain: "example.com".to_string()
+            })
+        ));
+        assert!(has_node(
+            &graph,
+            Node::DNSEntry {
+                nameserver: Domain {
+                    domain: "192.0.2.53".to_string()
+                },
+                record: DNSRecord::A(address),
+            }
+        ));
+        assert!(has_node(&graph, Node::IP(IpAddr::V4(address))));
+        assert!(has_node(
+            &graph,
+            Node::IP(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10)))
+        ));
+    }
+
+    #[test]
+    fn compression_loops_end() {
+        let mut message = dns_response();
+        // let the name of the question point at itself
+        message[12] = 0xc0;
+        message[13] = 12;
+        assert_eq!(dns_name(&message, 12), None);
+
+        // the packet still counts
+        let graph = import(&pcap(&[udp_frame(&message)])).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+    }
+
+    #[test]
+    fn tls_server_names() {
+        let name = b"www.example.com";
+        let name_length = u16::try_from(name.len()).unwrap();
+        let mut extension = vec![0, 0];
+        extension.extend((name_length + 5).to_be_bytes());
+        extension.extend((name_length + 3).to_be_bytes());
+        extension.push(0);
+        extension.extend(name_length.to_be_bytes());
+        extension.extend(name);
+
+        // version, random, no session id, one cipher suite and no compression
+        let mut hello = vec![3, 3];
+        hello.extend([0; 32]);
+        hello.extend([0, 0, 2, 0x13, 0x01, 1, 0]);
+        hello.extend(u16::try_from(extension.len()).unwrap().to_be_bytes());
+        hello.extend(extension);
+
+        let mut payload = vec![22, 3, 1];
+        payload.extend(u16::try_from(hello.len() + 4).unwrap().to_be_bytes());
+        payload.push(1);
+        payload.extend(&u32::try_from(hello.len()).unwrap().to_be_bytes()[1..]);
+        payload.extend(hello);
+
+        assert_eq!(
+            tls_server_name(&payload),
+            Some("www.example.com".to_string())
+        );
+        assert_eq!(tls_server_name(b"GET / HTTP/1.1\r\n"), None);
+    }
+
+    #[test]
+    fn pcapng() {
+        let block = |block_type: u32, body: &[u8]| {
+            let length = u32::try_from(12 + body.len()).unwrap();
+            let mut block = block_type.to_le_bytes().to_vec();
+            block.extend(length.to_le_bytes());
+            block.extend(body);
+            block.extend(length.to_le_bytes());
+            block
+        };
+
+        let frame = udp_frame(&dns_response());
+        let length = u32::try_from(frame.len()).unwrap();
+        let mut packet = vec![0; 12];
+        packet.extend(length.to_le_bytes());
+        packet.extend(length.to_le_bytes());
+        packet.extend(&frame);
+        packet.resize(packet.len().next_multiple_of(4), 0);
+
+        let mut section = vec![0x4d, 0x3c, 0x2b, 0x1a, 1, 0, 0, 0];
+        section.extend([0xff; 8]);
+        let mut interface = u16::try_from(LINKTYPE_ETHERNET)
+            .unwrap()
+            .to_le_bytes()
+            .to_vec();
+        interface.extend([0, 0, 0, 0, 0, 0]);
+
+        let mut data = block(0x0a0d_0d0a, &section);
+        data.extend(block(1, &interface));
+        data.extend(block(6, &packet));
+
+        let pcapng = import(&data).unwrap();
+        let pcap = import(&pcap(&[frame])).unwrap();
+        assert_eq!(pcapng.nodes.len(), pcap.nodes.len());
+        assert_eq!(pcapng.edges.len(), pcap.edges.len());
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt::{self, Display, Write};
+
+use chrono::Utc;
+
+use crate::annotation::{Confidence, Provenance};
+use crate::entity;
+use crate::error::Error;
+use crate::formats::image::{self, ImageFormat};
+use crate::registry::Registry;
+use crate::views::graph::{VisualGraph, VisualNode};
+
+/// The formats reports can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 2] = [ReportFormat::Html, ReportFormat::Markdown];
+
+    /// The file extension of the format.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+
+    /// The template used if the user didn't load their own.
+    #[must_use]
+    pub fn default_template(self) -> &'static str {
+        match self {
+            ReportFormat::Html => include_str!("templates/report.html"),
+            ReportFormat::Markdown => include_str!("templates/report.md"),
+        }
+    }
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportFormat::Html => write!(f, "HTML report"),
+            ReportFormat::Markdown => write!(f, "Markdown report"),
+        }
+    }
+}
+
+/// Render a report about a graph from a template.
+///
+/// Templates are HTML or Markdown files, so teams can use their own layout and
+/// branding. These placeholders are replaced with the content of the graph:
+///
+/// - `{{date}}`: the date the report was generated on
+/// - `{{entity_count}}` and `{{relationship_count}}`: the size of the graph
+/// - `{{image}}`: a picture of the graph, colored by the custom `types`
+/// - `{{entities}}`: one table per entity type with all fields of the entities
+///   and what the analysts noted about them
+/// - `{{relationships}}`: a table of all relationships
+/// - `{{provenance}}`: a table of the entities and relationships found by
+///   plugins, with the plugin that found them and when
+///
+/// Returns the files making up the report together with their names: the report
+/// itself named `name` and, for Markdown, the picture of the graph it refers to.
+/// HTML reports contain the picture, so they can be passed on as a single file.
+///
+/// # Errors
+///
+/// Returns an error if the picture of the graph can't be rendered.
+pub fn render(
+    graph: &VisualGraph,
+    types: &Registry,
+    zoom_factor: f32,
+    format: ReportFormat,
+    template: &str,
+    name: &str,
+) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let bounds = image::graph_bounds(graph, zoom_factor);
+    let mut files = Vec::new();
+    let image = match format {
+        ReportFormat::Html => {
+            let svg = image::export(graph, types, zoom_factor, bounds, ImageFormat::Svg)?;
+            String::from_utf8_lossy(&svg).into_owned()
+        }
+        ReportFormat::Markdown => {
+            let png = image::export(graph, types, zoom_factor, bounds, ImageFormat::Png)?;
+            let image_name = format!("{name}.png");
+            let reference = format!("![Graph]({image_name})");
+            files.push((image_name, png));
+            reference
+        }
+    };
+
+    let report = fill(
+        template,
+        &[
+            ("date", Utc::now().format("%Y-%m-%d").to_string()),
+            ("entity_count", graph.nodes.len().to_string()),
+            ("relationship_count", graph.edges.len().to_string()),
+            ("image", image),
+            ("entities", entities(graph, format)),
+            ("relationships", relationships(graph, format)),
+            ("provenance", provenance(graph, format)),
+        ],
+    );
+    files.insert(
+        0,
+        (
+            format!("{name}.{}", format.extension()),
+            report.into_bytes(),
+        ),
+    );
+
+    Ok(files)
+}
+
+/// Replace the placeholders of a template in a single pass, so placeholders in
+/// the content of the graph stay untouched.
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find("}}").and_then(|end| {
+            let key = &rest[2..end];
+            let (_, value) = values.iter().find(|(name, _)| *name == key)?;
+            Some((value, end + 2))
+        });
+        if let Some((value, length)) = value {
+            out.push_str(value);
+            rest = &rest[length..];
+        } else {
+            // not a placeholder we know, keep it as it is
+            out.push_str("{{");
+            rest = &rest[2..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The fields of an entity followed by its annotations, leaving out what
+/// wasn't noted.
+fn report_fields(node: &VisualNode) -> Vec<(&str, String)> {
+    let annotations = &node.annotations;
+    let mut fields = entity::fields(&node.node);
+    if !annotations.tags.is_empty() {
+        fields.push(("Tags", annotations.tag_text()));
+    }
+    if annotations.confidence != Confidence::Unknown {
+        fields.push(("Confidence", annotations.confidence.name().to_string()));
+    }
+    for (name, value) in &annotations.properties {
+        fields.push((name, value.clone()));
+    }
+    if !annotations.notes.is_empty() {
+        fields.push(("Notes", annotations.notes.clone()));
+    }
+    fields
+}
+
+/// One table per entity type, listing all fields and annotations of the
+/// entities.
+fn entities(graph: &VisualGraph, format: ReportFormat) -> String {
+    // group the nodes by type, keeping the order they were added in
+    let mut groups: Vec<(&str, Vec<&VisualNode>)> = Vec::new();
+    for node in &graph.nodes {
+        let name = entity::display_name(&node.node);
+        match groups.iter_mut().find(|(group, _)| *group == name) {
+            Some((_, group)) => group.push(node),
+            None => groups.push((name, vec![node])),
+        }
+    }
+
+    let mut out = String::new();
+    for (name, nodes) in groups {
+        // DNS entries have different fields per record type, so use all that appear
+        let mut columns: Vec<&str> = Vec::new();
+        for node in &nodes {
+            for (field, _) in report_fields(node) {
+                if !columns.contains(&field) {
+                    columns.push(field);
+                }
+            }
+        }
+
+        let rows = nodes
+            .iter()
+            .map(|node| {
+                let fields = report_fields(node);
+                let values = columns.iter().map(|column| {
+                    fields
+                        .iter()
+                        .find(|(field, _)| field == column)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default()
+                });
+                std::iter::once(node.id.to_string()).chain(values).collect()
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        let header: Vec<&str> = std::iter::once("#").chain(columns).collect();
+        match format {
+            ReportFormat::Html => {
+                let _ = writeln!(out, "<h3>{}</h3>", escape_html(name));
+            }
+            ReportFormat::Markdown => {
+                let _ = writeln!(out, "### {name}\n");
+            }
+        }
+        out.push_str(&table(format, &header, &rows));
+    }
+    out
+}
+
+/// An entity of the graph as it is named in tables.
+fn describe(graph: &VisualGraph, id: usize) -> String {
+    graph.node(id).map_or(id.to_string(), |node| {
+        format!("{} (#{id})", entity::label(&node.node))
+    })
+}
+
+fn relationships(graph: &VisualGraph, format: ReportFormat) -> String {
+    let describe = |id| describe(graph, id);
+    let rows: Vec<Vec<String>> = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            vec![
+                describe(edge.from),
+                edge.kind.name().to_string(),
+                describe(edge.to),
+            ]
+        })
+        .collect();
+    table(format, &["From", "Relationship", "To"], &rows)
+}
+
+/// Which plugin found which entities and relationships and when, in the order
+/// they were added.
+fn provenance(graph: &VisualGraph, format: ReportFormat) -> String {
+    let row = |found: String, provenance: &Provenance| {
+        vec![
+            found,
+            provenance.source.clone(),
+            provenance.time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        ]
+    };
+    let nodes = graph.nodes.iter().filter_map(|node| {
+        let provenance = node.provenance.as_ref()?;
+        Some(row(describe(graph, node.id), provenance))
+    });
+    let edges = graph.edges.iter().filter_map(|edge| {
+        let provenance = edge.provenance.as_ref()?;
+        let found = format!(
+            "{} {} {}",
+            describe(graph, edge.from),
+            edge.kind.name(),
+            describe(graph, edge.to)
+        );
+        Some(row(found, provenance))
+    });
+    let rows: Vec<Vec<String>> = nodes.chain(edges).collect();
+
+    if rows.is_empty() {
+        let nothing = "Nothing was found by plugins.";
+        return match format {
+            ReportFormat::Html => format!("<p>{nothing}</p>\n"),
+            ReportFormat::Markdown => format!("{nothing}\n"),
+        };
+    }
+    table(format, &["Found", "Plugin", "When"], &rows)
+}
+
+fn table(format: ReportFormat, header: &[&str], rows: &[Vec<String>]) -> String {
+    // writing to a string can't fail
+    let mut out = String::new();
+    match format {
+        ReportFormat::Html => {
+            out.push_str("<table>\n<tr>");
+            for cell in header {
+                let _ = write!(out, "<th>{}</th>", escape_html(cell));
+            }
+            out.push_str("</tr>\n");
+            for row in rows {
+                out.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(out, "<td>{}</td>", escape_html(cell));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</table>\n");
+        }
+        ReportFormat::Markdown => {
+            let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+            out.push_str(&line(header.iter().map(|c| escape_markdown(c)).collect()));
+            out.push_str(&line(header.iter().map(|_| "---".to_string()).collect()));
+            for row in rows {
+                out.push_str(&line(row.iter().map(|c| escape_markdown(c)).collect()));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Make text safe to use in a cell of a Markdown table.
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\n', '\r'], " ")
+}
@@ -0,0 +1,445 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{SecondsFormat, Utc};
+use schnuffel_types::graph::{Domain, EmailAddress, Node};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::annotation::{Annotations, Confidence};
+use crate::entity::Entity;
+use crate::error::Error;
+use crate::formats::attributes;
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// The namespace STIX uses to derive the ids of cyber observables.
+const OBSERVABLE_NAMESPACE: Uuid = Uuid::from_u128(0x00ab_edb4_aa42_466c_9c01_fed2_3315_a9b7);
+
+/// The type of the custom object used for entities STIX has no object for.
+const CUSTOM_ENTITY_TYPE: &str = "x-schnuffel-entity";
+
+/// The prefix of the custom properties of objects.
+const CUSTOM_PREFIX: &str = "x_schnuffel_";
+
+/// Write a graph as a STIX 2.1 bundle.
+///
+/// Entities become cyber observables or identities, edges become relationships.
+/// Entities without a STIX counterpart (DNS entries, phone numbers and generic
+/// entities) are written as custom `x-schnuffel-entity` objects so they aren't
+/// lost. Annotations are written as described in [`annotate`].
+///
+/// # Errors
+///
+/// Returns an error if the bundle can't be serialized.
+pub fn export(graph: &VisualGraph) -> Result<Vec<u8>, Error> {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let mut objects = Vec::new();
+    let mut written = HashSet::new();
+    let mut stix_ids = HashMap::new();
+    for node in &graph.nodes {
+        let mut object = stix_object(&node.node, &now);
+        annotate(&mut object, &node.annotations);
+        let stix_id = object["id"].as_str().unwrap_or_default().to_string();
+
+        // observables with the same value share an id, only write them once
+        if written.insert(stix_id.clone()) {
+            objects.push(object);
+        }
+        stix_ids.insert(node.id, stix_id);
+    }
+
+    for edge in &graph.edges {
+        let (Some(source), Some(target)) = (stix_ids.get(&edge.from), stix_ids.get(&edge.to))
+        else {
+            continue;
+        };
+        let relationship_type = relationship_type(&edge.kind);
+        let mut relationship = json!({
+            "type": "relationship",
+            "spec_version": "2.1",
+            "id": format!("relationship--{}", Uuid::new_v4()),
+            "created": now,
+            "modified": now,
+            "relationship_type": relationship_type,
+            "source_ref": source,
+            "target_ref": target,
+        });
+        // keep the name of custom kinds that had to be changed to be valid
+        if let EdgeKind::Custom(name) = &edge.kind {
+            if *name != relationship_type {
+                relationship[format!("{CUSTOM_PREFIX}kind")] = json!(name);
+            }
+        }
+        objects.push(relationship);
+    }
+
+    let bundle = json!({
+        "type": "bundle",
+        "id": format!("bundle--{}", Uuid::new_v4()),
+        "objects": objects,
+    });
+    Ok(serde_json::to_vec_pretty(&bundle)?)
+}
+
+/// Read the entities and relationships of a STIX 2.1 bundle.
+///
+/// Objects that don't describe an entity (like indicators or malware) are
+/// skipped, as are relationships between them. Objects with malformed values
+/// are skipped as well, see [`import_counting`].
+///
+/// # Errors
+///
+/// Returns an error if the data isn't a STIX bundle.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    import_counting(data).map(|(graph, _)| graph)
+}
+
+/// Read a STIX 2.1 bundle like [`import`], also returning how many objects
+/// were skipped because of malformed values, like an `ipv4-addr` holding a
+/// network instead of an address.
+///
+/// # Errors
+///
+/// Returns an error if the data isn't a STIX bundle.
+pub fn import_counting(data: &[u8]) -> Result<(VisualGraph, usize), Error> {
+    let bundle: Value = serde_json::from_slice(data)?;
+    let objects = bundle
+        .get("objects")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::InvalidFormat("the file is no STIX bundle".to_string()))?;
+
+    let mut graph = VisualGraph::empty();
+    let mut ids = HashMap::new();
+    let mut skipped = 0;
+    for object in objects {
+        let Ok(node) = node_from_object(object) else {
+            skipped += 1;
+            continue;
+        };
+        if let Some(node) = node {
+            let id = graph.add_node(node);
+            graph.annotate(id, annotations_from_object(object));
+            ids.insert(string(object, "id")?, id);
+        }
+    }
+
+    for object in objects {
+        if string(object, "type")? != "relationship" {
+            continue;
+        }
+        let endpoints = (
+            ids.get(string(object, "source_ref")?),
+            ids.get(string(object, "target_ref")?),
+        );
+        if let (Some(from), Some(to)) = endpoints {
+            let kind = match object.get("x_schnuffel_kind").and_then(Value::as_str) {
+                Some(name) => EdgeKind::Custom(name.to_string()),
+                None => match string(object, "relationship_type")? {
+                    "related-to" => EdgeKind::Related,
+                    other => EdgeKind::from_name(other),
+                },
+            };
+            graph.add_edge(*from, *to, kind);
+        }
+    }
+
+    Ok((graph, skipped))
+}
+
+fn stix_object(entity: &Entity, now: &str) -> Value {
+    let Entity::Node(node) = entity else {
+        return custom_object(entity, now);
+    };
+    match node {
+        Node::IP(ip) if ip.is_ipv4() => observable("ipv4-addr", &ip.to_string()),
+        Node::IP(ip) => observable("ipv6-addr", &ip.to_string()),
+        Node::Domain(domain) => observable("domain-name", &domain.domain),
+        Node::EmailAddress(email) => observable("email-addr", &email.email),
+        Node::Website { url } => observable("url", url.as_str()),
+        Node::Person(name) => identity(name, "individual", now),
+        Node::Organization(name) => identity(name, "organization", now),
+        Node::SocialMedia {
+            social_media_url,
+            account_url,
+        } => {
+            let id = observable_id("user-account", &json!({ "user_id": account_url.as_str() }));
+            json!({
+                "type": "user-account",
+                "spec_version": "2.1",
+                "id": id,
+                "user_id": account_url.as_str(),
+                "x_schnuffel_social_media_url": social_media_url.as_str(),
+            })
+        }
+        Node::DNSEntry { .. } | Node::PhoneNumber(_) => custom_object(entity, now),
+    }
+}
+
+/// An object for entities STIX has no object for, holding all their attributes.
+fn custom_object(entity: &Entity, now: &str) -> Value {
+    let attributes: Map<String, Value> = attributes::node_attributes(entity)
+        .into_iter()
+        .map(|(key, value)| (key, Value::String(value)))
+        .collect();
+    json!({
+        "type": CUSTOM_ENTITY_TYPE,
+        "spec_version": "2.1",
+        "id": format!("{CUSTOM_ENTITY_TYPE}--{}", Uuid::new_v4()),
+        "created": now,
+        "modified": now,
+        "x_schnuffel_attributes": attributes,
+    })
+}
+
+/// A cyber observable that is identified by its `value`.
+fn observable(kind: &str, value: &str) -> Value {
+    json!({
+        "type": kind,
+        "spec_version": "2.1",
+        "id": observable_id(kind, &json!({ "value": value })),
+        "value": value,
+    })
+}
+
+/// Derive the deterministic id of a cyber observable from its id contributing
+/// properties, as described in section 2.9 of the STIX 2.1 specification.
+fn observable_id(kind: &str, contributing: &Value) -> String {
+    let uuid = Uuid::new_v5(&OBSERVABLE_NAMESPACE, contributing.to_string().as_bytes());
+    format!("{kind}--{uuid}")
+}
+
+fn identity(name: &str, class: &str, now: &str) -> Value {
+    json!({
+        "type": "identity",
+        "spec_version": "2.1",
+        "id": format!("identity--{}", Uuid::new_v4()),
+        "created": now,
+        "modified": now,
+        "name": name,
+        "identity_class": class,
+    })
+}
+
+/// Add the annotations of an entity to its object.
+///
+/// Only domain objects like identities have `labels` and a `confidence` in
+/// STIX, so observables and custom objects get them as `x_schnuffel_labels`
+/// and `x_schnuffel_confidence` instead. Notes and properties are always
+/// custom properties.
+fn annotate(object: &mut Value, annotations: &Annotations) {
+    let prefix = if object["type"] == "identity" {
+        ""
+    } else {
+        CUSTOM_PREFIX
+    };
+    if !annotations.tags.is_empty() {
+        object[format!("{prefix}labels")] = json!(annotations.tags);
+    }
+    if let Some(confidence) = stix_confidence(annotations.confidence) {
+        object[format!("{prefix}confidence")] = json!(confidence);
+    }
+    if !annotations.notes.is_empty() {
+        object[format!("{CUSTOM_PREFIX}notes")] = json!(annotations.notes);
+    }
+    if !annotations.properties.is_empty() {
+        object[format!("{CUSTOM_PREFIX}properties")] = json!(annotations.properties);
+    }
+}
+
+/// Read the annotations written by [`annotate`], also taking the labels and
+/// confidence of objects written by other tools.
+fn annotations_from_object(object: &Value) -> Annotations {
+    let get = |name: &str| {
+        object
+            .get(name)
+            .or_else(|| object.get(format!("{CUSTOM_PREFIX}{name}")))
+    };
+    Annotations {
+        notes: get("notes")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        tags: get("labels")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|label| Some(label.as_str()?.to_string()))
+            .collect(),
+        confidence: get("confidence")
+            .and_then(Value::as_u64)
+            .map_or(Confidence::Unknown, confidence_from_stix),
+        properties: get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+            .collect(),
+    }
+}
+
+/// The confidence on the 0 to 100 scale of STIX, using the values of its
+/// None/Low/Med/High scale, `None` if it is unknown.
+fn stix_confidence(confidence: Confidence) -> Option<u8> {
+    match confidence {
+        Confidence::Unknown => None,
+        Confidence::Low => Some(15),
+        Confidence::Medium => Some(50),
+        Confidence::High => Some(85),
+        Confidence::Verified => Some(100),
+    }
+}
+
+fn confidence_from_stix(confidence: u64) -> Confidence {
+    match confidence {
+        0..=29 => Confidence::Low,
+        30..=69 => Confidence::Medium,
+        70..=99 => Confidence::High,
+        _ => Confidence::Verified,
+    }
+}
+
+/// The relationship type of an edge kind.
+///
+/// STIX only allows lowercase letters, digits and dashes, so the names of
+/// custom kinds are lowercased and everything else becomes a dash.
+fn relationship_type(kind: &EdgeKind) -> String {
+    if *kind == EdgeKind::Related {
+        return "related-to".to_string();
+    }
+    let mut name = String::new();
+    for c in kind.name().chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name = name.trim_end_matches('-');
+    if name.is_empty() {
+        "related-to".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn node_from_object(object: &Value) -> Result<Option<Entity>, Error> {
+    let node = match string(object, "type")? {
+        "ipv4-addr" | "ipv6-addr" => Node::IP(parse(object, "value")?),
+        "domain-name" => Node::Domain(Domain {
+            domain: string(object, "value")?.to_string(),
+        }),
+        "email-addr" => Node::EmailAddress(EmailAddress {
+            email: string(object, "value")?.to_string(),
+        }),
+        "url" => Node::Website {
+            url: parse(object, "value")?,
+        },
+        "identity" => {
+            let name = string(object, "name")?.to_string();
+            match object.get("identity_class").and_then(Value::as_str) {
+                Some("organization" | "group" | "class") => Node::Organization(name),
+                _ => Node::Person(name),
+            }
+        }
+        // only accounts exported by us know which network they belong to
+        "user-account" if object.get("x_schnuffel_social_media_url").is_some() => {
+            Node::SocialMedia {
+                social_media_url: parse(object, "x_schnuffel_social_media_url")?,
+                account_url: parse(object, "user_id")?,
+            }
+        }
+        CUSTOM_ENTITY_TYPE => {
+            let attributes: HashMap<String, String> = object
+                .get("x_schnuffel_attributes")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect();
+            return Ok(Some(attributes::node_from_attributes(&attributes)?));
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(node.into()))
+}
+
+fn string<'a>(object: &'a Value, key: &str) -> Result<&'a str, Error> {
+    object
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::InvalidFormat(format!("a STIX object has no {key}")))
+}
+
+fn parse<T>(object: &Value, key: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    string(object, key)?
+        .parse()
+        .map_err(|e| Error::InvalidFormat(format!("malformed STIX {key}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use schnuffel_types::graph::Node;
+    use serde_json::Value;
+
+    use super::{export, import, import_counting};
+    use crate::views::graph::{EdgeKind, VisualGraph};
+
+    #[test]
+    fn custom_kinds_are_valid_relationship_types() {
+        let mut graph = VisualGraph::empty();
+        let jane = graph.add_node(Node::Person("Jane Doe".to_string()));
+        let acme = graph.add_node(Node::Organization("ACME".to_string()));
+        graph.add_edge(
+            jane,
+            acme,
+            EdgeKind::Custom("Works at (since 2020)".to_string()),
+        );
+
+        let data = export(&graph).unwrap();
+        let bundle: Value = serde_json::from_slice(&data).unwrap();
+        let relationship = bundle["objects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|object| object["type"] == "relationship")
+            .unwrap();
+        assert_eq!(relationship["relationship_type"], "works-at-since-2020");
+
+        let read = import(&data).unwrap();
+        assert_eq!(read.edges.len(), 1);
+        assert_eq!(
+            read.edges[0].kind,
+            EdgeKind::Custom("Works at (since 2020)".to_string())
+        );
+    }
+
+    #[test]
+    fn malformed_objects_are_skipped() {
+        let bundle = br#"{
+            "type": "bundle",
+            "id": "bundle--6b0a1b6c-3c7e-4a8e-9a1d-2f6f1f0d6a11",
+            "objects": [
+                {
+                    "type": "ipv4-addr",
+                    "id": "ipv4-addr--1",
+                    "value": "198.51.100.0/24"
+                },
+                {
+                    "type": "domain-name",
+                    "id": "domain-name--2",
+                    "value": "example.com"
+                }
+            ]
+        }"#;
+
+        let (graph, skipped) = import_counting(bundle).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(graph.nodes.len(), 1);
+    }
+}
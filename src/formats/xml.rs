@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::error::Error;
+
+/// A minimal in memory XML element, good enough to pick the interesting parts out
+/// of the XML based formats we import.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    /// The name of the element without its namespace prefix.
+    pub name: String,
+    /// All attributes of the element, keyed by their full name.
+    pub attributes: HashMap<String, String>,
+    pub children: Vec<Element>,
+    /// All text directly inside the element.
+    pub text: String,
+}
+
+impl Element {
+    /// Get the value of an attribute.
+    #[must_use]
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Get the first direct child with the given name.
+    #[must_use]
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Iterate over all direct children with the given name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Collect all elements with the given name anywhere below this element.
+    #[must_use]
+    pub fn descendants(&self, name: &str) -> Vec<&Element> {
+        let mut found = Vec::new();
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            found.append(&mut child.descendants(name));
+        }
+        found
+    }
+}
+
+/// Parse an XML document into its root element.
+///
+/// # Errors
+///
+/// Returns an error if the document isn't well formed XML or is empty.
+pub fn parse(data: &[u8]) -> Result<Element, Error> {
+    // the text isn't trimmed, as whitespace around values like notes matters
+    let mut reader = Reader::from_reader(data);
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => stack.push(element_from_start(&start)?),
+            Event::Empty(start) => {
+                let element = element_from_start(&start)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::End(_) => {
+                let mut element = stack
+                    .pop()
+                    .ok_or_else(|| Error::InvalidFormat("unexpected closing tag".to_string()))?;
+                // only the indentation between the children of an element
+                if !element.children.is_empty() && element.text.trim().is_empty() {
+                    element.text.clear();
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    root.ok_or_else(|| Error::InvalidFormat("the document is empty".to_string()))
+}
+
+fn element_from_start(start: &BytesStart) -> Result<Element, Error> {
+    let mut attributes = HashMap::new();
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        attributes.insert(
+            String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+            attribute.unescape_value()?.into_owned(),
+        );
+    }
+
+    Ok(Element {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+        attributes,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,470 @@
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use schnuffel_types::graph::{DNSRecord, Domain, Node};
+
+use crate::entity::{Entity, GenericEntity};
+use crate::error::Error;
+use crate::formats::builder::GraphBuilder;
+use crate::views::graph::{EdgeKind, VisualGraph};
+
+/// The type of the generic entities for records of types [`DNSRecord`] has no
+/// variant for.
+pub const UNSUPPORTED_TYPE: &str = "DNS Record";
+
+/// The classes a record can have, which are skipped while parsing.
+const CLASSES: [&str; 4] = ["IN", "CH", "HS", "CS"];
+
+/// A resource record as written in the file, with all names absolute.
+struct Record {
+    line: usize,
+    owner: String,
+    kind: String,
+    data: Vec<String>,
+}
+
+/// Read the records of an RFC 1035 zone file, e.g. an AXFR dump, or of saved
+/// `dig` output.
+///
+/// A, AAAA, MX, TXT, SRV and CNAME records become DNS entries, connected to the
+/// domain they belong to and with resolves-to edges to the domains and IPs they
+/// point at. The nameserver of the entries is taken from the SOA record, the
+/// first NS record or the server `dig` asked, in that order.
+///
+/// Records of other types, like NS, PTR, SOA or CAA, are kept as generic
+/// entities, see [`unsupported`].
+///
+/// # Errors
+///
+/// Returns an error if the file contains no records or a record is malformed.
+pub fn import(data: &[u8]) -> Result<VisualGraph, Error> {
+    let text = String::from_utf8_lossy(data);
+    let records = records(&text)?;
+    if records.is_empty() {
+        return Err(Error::InvalidFormat(
+            "the file contains no DNS records".to_string(),
+        ));
+    }
+
+    let nameserver = nameserver(&records)
+        .or_else(|| dig_server(&text))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let mut builder = GraphBuilder::new();
+    for record in &records {
+        add(&mut builder, record, &nameserver)?;
+    }
+    Ok(builder.finish())
+}
+
+/// A summary of the records of an imported graph whose types aren't supported
+/// yet, e.g. `"2 NS, 1 SOA"`, or `None` if there are none.
+#[must_use]
+pub fn unsupported(graph: &VisualGraph) -> Option<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for node in &graph.nodes {
+        let Entity::Generic(generic) = &node.node else {
+            continue;
+        };
+        if generic.entity_type != UNSUPPORTED_TYPE {
+            continue;
+        }
+        let Some((_, record_type)) = generic.properties.iter().find(|(name, _)| name == "type")
+        else {
+            continue;
+        };
+        match counts.iter_mut().find(|(t, _)| t == record_type) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((record_type, 1)),
+        }
+    }
+
+    if counts.is_empty() {
+        return None;
+    }
+    let counts: Vec<String> = counts
+        .into_iter()
+        .map(|(record_type, count)| format!("{count} {record_type}"))
+        .collect();
+    Some(counts.join(", "))
+}
+
+/// The nameserver from the SOA record or else the first NS record.
+fn nameserver(records: &[Record]) -> Option<String> {
+    let soa = records.iter().find(|r| r.kind == "SOA");
+    let ns = records.iter().find(|r| r.kind == "NS");
+    soa.or(ns)?.data.first().map(|name| absolute(name, ""))
+}
+
+/// The server `dig` asked, from a line like
+/// `;; SERVER: 9.9.9.9#53(dns.quad9.net) (UDP)`.
+fn dig_server(text: &str) -> Option<String> {
+    let server = text
+        .lines()
+        .find_map(|line| line.strip_prefix(";; SERVER:"))?
+        .trim();
+    let name = server
+        .split_once('(')
+        .and_then(|(_, name)| name.split_once(')'))
+        .map(|(name, _)| name)
+        .filter(|name| !name.is_empty());
+    let address = server.split('#').next().unwrap_or(server);
+    Some(name.unwrap_or(address).to_string())
+}
+
+/// Make a name absolute and drop the trailing dot.
+fn absolute(name: &str, origin: &str) -> String {
+    if name == "@" {
+        origin.to_string()
+    } else if let Some(name) = name.strip_suffix('.') {
+        name.to_string()
+    } else if origin.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}.{origin}")
+    }
+}
+
+/// Parse all records of a zone file.
+fn records(text: &str) -> Result<Vec<Record>, Error> {
+    let mut records = Vec::new();
+    let mut origin = String::new();
+    let mut owner = String::new();
+
+    for (line, starts_with_owner, tokens) in logical_lines(text)? {
+        let invalid = |reason: &str| Error::InvalidFormat(format!("line {line}: {reason}"));
+        let mut tokens = tokens.into_iter().peekable();
+
+        match tokens.peek().map(String::as_str) {
+            Some("$ORIGIN") => {
+                tokens.next();
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| invalid("$ORIGIN without a name"))?;
+                origin = absolute(&name, &origin);
+                continue;
+            }
+            // the default TTL and included files don't matter for the graph
+            Some(directive) if directive.starts_with('$') => continue,
+            _ => {}
+        }
+
+        if starts_with_owner {
+            let name = tokens.next().ok_or_else(|| invalid("missing owner"))?;
+            owner = absolute(&name, &origin);
+        }
+        // the TTL and class may come in any order before the type
+        let record_type = loop {
+            let token = tokens
+                .next()
+                .ok_or_else(|| invalid("missing record type"))?;
+            // TTLs may have units, like 1h30m
+            let is_ttl = token.starts_with(|c: char| c.is_ascii_digit());
+            if !is_ttl && !CLASSES.contains(&token.to_ascii_uppercase().as_str()) {
+                break token.to_ascii_uppercase();
+            }
+        };
+
+        // names in the data are relative to the origin too
+        let names = name_fields(&record_type);
+        let data = tokens
+            .enumerate()
+            .map(|(i, token)| {
+                if names.contains(&i) {
+                    absolute(&token, &origin)
+                } else {
+                    token
+                }
+            })
+            .collect();
+        records.push(Record {
+            line,
+            owner: owner.clone(),
+            kind: record_type,
+            data,
+        });
+    }
+    Ok(records)
+}
+
+/// The positions of the data of a record type that are domain names.
+fn name_fields(record_type: &str) -> &'static [usize] {
+    match record_type {
+        "CNAME" | "NS" | "PTR" => &[0],
+        "MX" => &[1],
+        "SRV" => &[3],
+        "SOA" => &[0, 1],
+        _ => &[],
+    }
+}
+
+/// Split a zone file into logical lines, joining lines inside parentheses and
+/// dropping comments.
+///
+/// Returns the number of the first line, whether it starts with an owner name
+/// and its tokens, with the quotes of quoted strings removed.
+fn logical_lines(text: &str) -> Result<Vec<(usize, bool, Vec<String>)>, Error> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, bool, Vec<String>)> = None;
+    let mut depth = 0;
+
+    for (number, line) in text.lines().enumerate() {
+        let (first, starts_with_owner, tokens) =
+            current.get_or_insert_with(|| (number + 1, !line.starts_with([' ', '\t']), Vec::new()));
+        let invalid =
+            |reason: &dyn Display| Error::InvalidFormat(format!("line {}: {reason}", number + 1));
+
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                ';' => break,
+                '(' => depth += 1,
+                ')' => {
+                    if depth == 0 {
+                        return Err(invalid(&"unbalanced parenthesis"));
+                    }
+                    depth -= 1;
+                }
+                '"' => {
+                    let mut string = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => string.push(escaped(&mut chars)),
+                            Some(c) => string.push(c),
+                            None => return Err(invalid(&"unterminated string")),
+                        }
+                    }
+                    tokens.push(string);
+                }
+                c if c.is_whitespace() => {}
+                c => {
+                    let mut token = String::from(c);
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || matches!(c, ';' | '(' | ')' | '"') {
+                            break;
+                        }
+                        chars.next();
+                        token.push(c);
+                    }
+                    tokens.push(token);
+                }
+            }
+        }
+
+        if depth == 0 {
+            if !tokens.is_empty() {
+                lines.push((*first, *starts_with_owner, std::mem::take(tokens)));
+            }
+            current = None;
+        }
+    }
+
+    if depth > 0 {
+        return Err(Error::InvalidFormat(
+            "unbalanced parenthesis at the end of the file".to_string(),
+        ));
+    }
+    Ok(lines)
+}
+
+/// Read an escaped character after a backslash, either `\X` or `\DDD`.
+fn escaped(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> char {
+    let mut digits = String::new();
+    while digits.len() < 3 {
+        match chars.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                digits.push(*c);
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+    if digits.is_empty() {
+        chars.next().unwrap_or('\\')
+    } else {
+        digits
+            .parse::<u8>()
+            .map_or(char::REPLACEMENT_CHARACTER, char::from)
+    }
+}
+
+fn add(builder: &mut GraphBuilder, record: &Record, nameserver: &str) -> Result<(), Error> {
+    let invalid = |reason: &dyn Display| {
+        Error::InvalidFormat(format!(
+            "line {}: invalid {} record: {reason}",
+            record.line, record.kind
+        ))
+    };
+    let field = |index: usize| {
+        record
+            .data
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| invalid(&"missing data"))
+    };
+
+    // what the record points at
+    let (dns_record, target) = match record.kind.as_str() {
+        "A" => {
+            let addr: Ipv4Addr = field(0)?.parse().map_err(|e| invalid(&e))?;
+            (DNSRecord::A(addr), Some(Node::IP(IpAddr::V4(addr))))
+        }
+        "AAAA" => {
+            let addr: Ipv6Addr = field(0)?.parse().map_err(|e| invalid(&e))?;
+            (DNSRecord::AAAA(addr), Some(Node::IP(IpAddr::V6(addr))))
+        }
+        "MX" => {
+            let exchange = Domain {
+                domain: field(1)?.to_string(),
+            };
+            (
+                DNSRecord::MX(exchange.clone()),
+                Some(Node::Domain(exchange)),
+            )
+        }
+        "TXT" => (DNSRecord::TXT(record.data.concat()), None),
+        "CNAME" => {
+            let to = Domain {
+                domain: field(0)?.to_string(),
+            };
+            let cname = DNSRecord::CNAME {
+                from: record.owner.clone(),
+                to: to.clone(),
+            };
+            (cname, Some(Node::Domain(to)))
+        }
+        "SRV" => {
+            let to = field(3)?.to_string();
+            let srv = srv(
+                &record.owner,
+                field(2)?.parse().map_err(|e| invalid(&e))?,
+                &to,
+            );
+            (srv, Some(Node::Domain(Domain { domain: to })))
+        }
+        _ => {
+            add_unsupported(builder, record);
+            return Ok(());
+        }
+    };
+
+    add_entry(builder, &record.owner, nameserver, dns_record, target);
+    Ok(())
+}
+
+/// Add the DNS entry for a record, connected to the domain it belongs to and
+/// with a resolves-to edge to what it points at.
+pub fn add_entry(
+    builder: &mut GraphBuilder,
+    owner: &str,
+    nameserver: &str,
+    record: DNSRecord,
+    target: Option<Node>,
+) {
+    let owner = builder.domain(owner);
+    let entry = builder.node(Node::DNSEntry {
+        nameserver: Domain {
+            domain: nameserver.to_string(),
+        },
+        record,
+    });
+    builder.edge(owner, entry, EdgeKind::Custom("record".to_string()));
+    if let Some(target) = target {
+        let target = builder.node(target);
+        builder.edge(entry, target, EdgeKind::ResolvesTo);
+    }
+}
+
+/// Keep a record of a type [`DNSRecord`] has no variant for.
+fn add_unsupported(builder: &mut GraphBuilder, record: &Record) {
+    let data = record.data.join(" ");
+    let owner = builder.domain(&record.owner);
+    let entity = builder.node(Entity::Generic(GenericEntity {
+        entity_type: UNSUPPORTED_TYPE.to_string(),
+        value: format!("{} {} {data}", record.owner, record.kind),
+        properties: vec![
+            ("data".to_string(), data),
+            ("owner".to_string(), record.owner.clone()),
+            ("type".to_string(), record.kind.clone()),
+        ],
+    }));
+    builder.edge(owner, entity, EdgeKind::Custom("record".to_string()));
+}
+
+/// An SRV record, with the service and protocol taken from an owner name like
+/// `_sip._tcp.example.com`.
+#[must_use]
+pub fn srv(owner: &str, port: u16, to: &str) -> DNSRecord {
+    let mut labels = owner.splitn(3, '.');
+    let (service, protocol, from) = match (labels.next(), labels.next(), labels.next()) {
+        (Some(service), Some(protocol), Some(from))
+            if service.starts_with('_') && protocol.starts_with('_') =>
+        {
+            (&service[1..], &protocol[1..], from)
+        }
+        _ => ("", "", owner),
+    };
+    DNSRecord::SRV {
+        service: service.to_string(),
+        protocol: protocol.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        to_port: port,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use schnuffel_types::graph::{DNSRecord, Domain, Node};
+
+    use super::{import, records, unsupported};
+    use crate::entity::Entity;
+
+    const ZONE: &str = "\
+$ORIGIN example.com.
+$TTL 3600
+@       IN SOA ns1 hostmaster 2024010101 7200 3600 1209600 3600
+        IN NS  ns1
+www     IN A   192.0.2.1
+mail 1h IN MX  10 mx.example.net.
+";
+
+    #[test]
+    fn names_are_relative_to_the_origin() {
+        let records = records(ZONE).unwrap();
+        let owners: Vec<&str> = records.iter().map(|r| r.owner.as_str()).collect();
+        assert_eq!(
+            owners,
+            [
+                "example.com",
+                "example.com",
+                "www.example.com",
+                "mail.example.com"
+            ]
+        );
+        assert_eq!(records[1].data, ["ns1.example.com"]);
+        assert_eq!(records[3].data, ["10", "mx.example.net"]);
+    }
+
+    #[test]
+    fn records_become_entries() {
+        let graph = import(ZONE.as_bytes()).unwrap();
+        let entry = Entity::Node(Node::DNSEntry {
+            nameserver: Domain {
+                domain: "ns1.example.com".to_string(),
+            },
+            record: DNSRecord::A(Ipv4Addr::new(192, 0, 2, 1)),
+        });
+        let address = Entity::Node(Node::IP(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+        let www = Entity::Node(Node::Domain(Domain {
+            domain: "www.example.com".to_string(),
+        }));
+        for entity in [entry, address, www] {
+            assert!(graph.nodes.iter().any(|node| node.node == entity));
+        }
+        assert_eq!(unsupported(&graph), Some("1 SOA, 1 NS".to_string()));
+    }
+}
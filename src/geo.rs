@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use schnuffel_types::graph::Node;
+
+use crate::annotation::Annotations;
+use crate::entity::Entity;
+use crate::error::Error;
+use crate::views::graph::{EdgeKind, VisualGraph, VisualNode};
+
+// the properties geolocated IPs get
+pub const COUNTRY: &str = "country";
+pub const COUNTRY_CODE: &str = "country code";
+pub const CITY: &str = "city";
+pub const LATITUDE: &str = "latitude";
+pub const LONGITUDE: &str = "longitude";
+pub const ASN: &str = "asn";
+pub const ORGANIZATION: &str = "organization";
+
+/// The kind of the edges from IPs to the organizations owning them.
+pub const OWNER_EDGE: &str = "owned-by";
+
+/// The language of the names of countries and cities.
+const LANGUAGE: &str = "en";
+
+/// A database in the `MaxMind` DB format, like `GeoLite2-City.mmdb` or
+/// `GeoLite2-ASN.mmdb`, which are read from files the user supplies and never
+/// downloaded.
+#[derive(Debug, Clone)]
+pub struct GeoDatabase {
+    /// The type of the database from its metadata, e.g. `GeoLite2-City`.
+    pub name: String,
+    reader: Arc<Reader<Vec<u8>>>,
+}
+
+impl GeoDatabase {
+    /// Read a database from the content of its file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data isn't a `MaxMind` database.
+    pub fn read(data: Vec<u8>) -> Result<Self, Error> {
+        let reader = Reader::from_source(data)?;
+        Ok(Self {
+            name: reader.metadata.database_type.clone(),
+            reader: Arc::new(reader),
+        })
+    }
+}
+
+impl From<MaxMindDBError> for Error {
+    fn from(e: MaxMindDBError) -> Self {
+        Error::InvalidFormat(format!("invalid MaxMind database: {e}"))
+    }
+}
+
+/// Where an IP is and who it belongs to, as far as the databases know.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoInfo {
+    pub country: Option<String>,
+    /// The ISO 3166 code of the country, e.g. `DE`.
+    pub country_code: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// The number of the autonomous system announcing the IP.
+    pub asn: Option<u32>,
+    /// The organization operating the autonomous system.
+    pub organization: Option<String>,
+}
+
+impl GeoInfo {
+    /// Whether none of the databases knows the IP.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == GeoInfo::default()
+    }
+
+    /// The info as properties, leaving out what is unknown.
+    #[must_use]
+    pub fn properties(&self) -> Vec<(&'static str, String)> {
+        [
+            (COUNTRY, self.country.clone()),
+            (COUNTRY_CODE, self.country_code.clone()),
+            (CITY, self.city.clone()),
+            (LATITUDE, self.latitude.map(|l| l.to_string())),
+            (LONGITUDE, self.longitude.map(|l| l.to_string())),
+            (ASN, self.asn.map(|asn| format!("AS{asn}"))),
+            (ORGANIZATION, self.organization.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+/// Look an IP up in all databases. What a later database knows replaces what
+/// an earlier one knew.
+#[must_use]
+pub fn lookup(databases: &[GeoDatabase], ip: IpAddr) -> GeoInfo {
+    let mut info = GeoInfo::default();
+    for database in databases {
+        // every record type has only optional fields, so looking up the wrong
+        // one in a database just finds nothing
+        if let Ok(city) = database.reader.lookup::<geoip2::City>(ip) {
+            if let Some(country) = city.country {
+                info.country = name(country.names.as_ref()).or(info.country);
+                info.country_code = country.iso_code.map(str::to_string).or(info.country_code);
+            }
+            if let Some(city) = city.city {
+                info.city = name(city.names.as_ref()).or(info.city);
+            }
+            if let Some(location) = city.location {
+                info.latitude = location.latitude.or(info.latitude);
+                info.longitude = location.longitude.or(info.longitude);
+            }
+        }
+        if let Ok(asn) = database.reader.lookup::<geoip2::Asn>(ip) {
+            info.asn = asn.autonomous_system_number.or(info.asn);
+            info.organization = asn
+                .autonomous_system_organization
+                .map(str::to_string)
+                .or(info.organization);
+        }
+    }
+    info
+}
+
+fn name(names: Option<&BTreeMap<&str, &str>>) -> Option<String> {
+    names?.get(LANGUAGE).map(|name| (*name).to_string())
+}
+
+/// Geolocate the IPs of a graph, adding what the databases know as properties
+/// and connecting the IPs to the organizations owning them.
+///
+/// Returns how many IPs were found in the databases.
+pub fn enrich(graph: &mut VisualGraph, databases: &[GeoDatabase]) -> usize {
+    let mut found = VisualGraph::empty();
+    for node in &graph.nodes {
+        let Entity::Node(Node::IP(ip)) = &node.node else {
+            continue;
+        };
+        let info = lookup(databases, *ip);
+        if info.is_empty() {
+            continue;
+        }
+        let annotations = Annotations {
+            properties: info
+                .properties()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+            ..Annotations::default()
+        };
+        let id = found.add_node(node.node.clone());
+        found.annotate(id, annotations);
+        if let Some(organization) = info.organization {
+            let owner = found.add_node(Node::Organization(organization));
+            found.add_edge(id, owner, EdgeKind::Custom(OWNER_EDGE.to_string()));
+        }
+    }
+    let count = found
+        .nodes
+        .iter()
+        .filter(|n| matches!(n.node, Entity::Node(Node::IP(_))))
+        .count();
+    graph.update(found);
+    count
+}
+
+/// The latitude and longitude of a node, e.g. a geolocated IP or an entity of
+/// the custom `Location` type.
+#[must_use]
+pub fn coordinates(node: &VisualNode) -> Option<(f64, f64)> {
+    let coordinate = |name: &str| {
+        let value = node.annotations.properties.get(name).or_else(|| {
+            let Entity::Generic(generic) = &node.node else {
+                return None;
+            };
+            generic
+                .properties
+                .iter()
+                .find(|(property, _)| property == name)
+                .map(|(_, value)| value)
+        })?;
+        value.trim().parse::<f64>().ok()
+    };
+    Some((coordinate(LATITUDE)?, coordinate(LONGITUDE)?))
+}
@@ -1,13 +1,56 @@
-use iced::{mouse::ScrollDelta, Point};
+use iced::{keyboard::Modifiers, mouse::ScrollDelta, touch::Finger, Point, Size};
 
+use views::graph::analytics::AnalyticsMessage;
+use views::graph::annotations::AnnotationMessage;
+use views::graph::csv_import::CsvMessage;
+use views::graph::files::FileMessage;
+use views::graph::geo::GeoMessage;
+use views::graph::paste::PasteMessage;
+use views::graph::paths::PathMessage;
+use views::graph::query::QueryMessage;
+use views::graph::search::SearchMessage;
+use views::graph::types::TypesMessage;
+use views::map::MapMessage;
+use views::table::TableMessage;
+use views::timeline::TimelineMessage;
+use views::View;
+
+pub mod analysis;
+pub mod annotation;
+pub mod entity;
+pub mod error;
+pub mod extract;
+pub mod formats;
+pub mod geo;
 pub mod plugin;
+pub mod registry;
 pub mod views;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
     MouseClick(Point),
     MouseDrag(Point),
     MouseRelease,
     MouseScroll(ScrollDelta),
     MiddleMouseClick(Point),
+    ModifiersChanged(Modifiers),
+    FingerPressed(Finger, Point),
+    FingerMoved(Finger, Point),
+    FingerLifted(Finger),
+    CanvasResized(Size),
+    File(FileMessage),
+    Csv(CsvMessage),
+    Paste(PasteMessage),
+    MergeDuplicates,
+    Paths(PathMessage),
+    Analytics(AnalyticsMessage),
+    Query(QueryMessage),
+    Search(SearchMessage),
+    Annotation(AnnotationMessage),
+    Types(TypesMessage),
+    Geo(GeoMessage),
+    SwitchView(View),
+    Timeline(TimelineMessage),
+    Table(TableMessage),
+    Map(MapMessage),
 }
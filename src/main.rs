@@ -1,19 +1,24 @@
 use iced::executor;
 use iced::theme::Theme;
+use iced::widget::column;
 use iced::Settings;
 use iced::{Application, Command, Element};
 use schnuffel::views::graph::helpers::update_graph;
 use schnuffel::views::graph::GraphState;
+use schnuffel::views::map::{self, MapState, MapStateUpdate};
+use schnuffel::views::table::{self, TableState, TableStateUpdate};
+use schnuffel::views::timeline::{self, TimelineState, TimelineStateUpdate};
+use schnuffel::views::{tabs, View, ViewState};
 use schnuffel::Message;
 
-// all of our views
-enum View {
-    Graph(GraphState),
-}
-
 // the actual app
 struct App {
     view: View,
+    // the views share the investigation, which the graph view holds
+    graph: GraphState,
+    timeline: TimelineState,
+    table: TableState,
+    map: MapState,
 }
 
 // our app in the graph view
@@ -26,7 +31,11 @@ impl Application for App {
     fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
         (
             App {
-                view: View::Graph(GraphState::default()),
+                view: View::Graph,
+                graph: GraphState::default(),
+                timeline: TimelineState::default(),
+                table: TableState::default(),
+                map: MapState::default(),
             },
             Command::none(),
         )
@@ -37,18 +46,62 @@ impl Application for App {
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
-        match &mut self.view {
-            View::Graph(state) => {
-                update_graph(state, message);
+        match message {
+            Message::SwitchView(view) => {
+                match view {
+                    View::Graph => {}
+                    View::Timeline => self.timeline.update_state(TimelineStateUpdate {
+                        graph: self.graph.graph.clone(),
+                    }),
+                    View::Table => self.table.update_state(TableStateUpdate {
+                        graph: self.graph.graph.clone(),
+                    }),
+                    View::Map => self.map.update_state(MapStateUpdate {
+                        graph: self.graph.graph.clone(),
+                    }),
+                }
+                self.view = view;
+                Command::none()
+            }
+            // the brush of the timeline filters the graph view
+            Message::Timeline(message) => {
+                timeline::update(&mut self.timeline, message);
+                self.graph.set_time_range(self.timeline.brush);
+                Command::none()
             }
+            // the table changes the investigation, which it then lists again
+            Message::Table(message) => {
+                let changes_entities = message.changes_entities();
+                let command = table::update(&mut self.table, &mut self.graph, message);
+                if changes_entities {
+                    self.table.update_state(TableStateUpdate {
+                        graph: self.graph.graph.clone(),
+                    });
+                }
+                command
+            }
+            // the map selects entities of the investigation, which it then shows again
+            Message::Map(message) => {
+                map::update(&mut self.map, &mut self.graph, message);
+                if message.changes_entities() {
+                    self.map.update_state(MapStateUpdate {
+                        graph: self.graph.graph.clone(),
+                    });
+                }
+                Command::none()
+            }
+            message => update_graph(&mut self.graph, message),
         }
-        Command::none()
     }
 
     fn view(&self) -> Element<'_, Self::Message, Self::Theme, iced::Renderer> {
-        match &self.view {
-            View::Graph(state) => schnuffel::views::graph::view(state),
-        }
+        let content = match self.view {
+            View::Graph => schnuffel::views::graph::view(&self.graph),
+            View::Timeline => timeline::view(&self.timeline),
+            View::Table => table::view(&self.table, &self.graph),
+            View::Map => map::view(&self.map),
+        };
+        column!(tabs(self.view, self.graph.time_range), content).into()
     }
 
     fn theme(&self) -> Self::Theme {
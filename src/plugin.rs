@@ -6,6 +6,12 @@ use extism::{typed_plugin, Manifest, Plugin, Wasm};
 use schnuffel_types::graph::{Graph, Node};
 use schnuffel_types::plugin::{Input, Output};
 
+use crate::annotation::Provenance;
+use crate::entity::{self, Entity, EntityKind};
+use crate::error::Error;
+use crate::registry::Registry;
+use crate::views::graph::{EdgeKind, VisualGraph};
+
 // The type information that a plugin must adhere to.
 // TODO: let plugins return results once own error is written.
 typed_plugin!(SchnuffelPlugin {
@@ -98,6 +104,19 @@ impl<'a> Wrapper<RunningState> {
         let res = self.state.plugin.exec_on_node(input).unwrap();
         res.data
     }
+    /// Execute the plugin on an entity, which may be of a custom type.
+    ///
+    /// Returns `None` if the entity can't be passed to plugins, see
+    /// [`Registry::plugin_node`]. Plugins only return built-in nodes, see
+    /// [`to_visual_graph`] for how they return entities of custom types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the execution fails.
+    pub fn exec_on_entity(&mut self, entity: &Entity, types: &Registry) -> Option<Graph> {
+        let node = types.plugin_node(entity)?;
+        Some(self.exec_on_node(node))
+    }
     /// Execute the plugin on a given graph.
     ///
     /// # Panics
@@ -133,3 +152,117 @@ pub struct RunningState {
 impl State for NewState {}
 impl State for InitializedState {}
 impl State for RunningState {}
+
+/// The separator between the name and the value of a property set by a plugin.
+const PROPERTY_SEPARATOR: char = '=';
+
+/// The separator between the custom type and the value of an entity returned
+/// by a plugin.
+const TYPE_SEPARATOR: char = ':';
+
+/// The entities and relationships a plugin returned for an entity as a graph
+/// of the investigation, with the properties the plugin set, see
+/// [`entity_from_plugin`].
+///
+/// The node the entity was passed as becomes the entity again, so entities of
+/// custom types keep their type.
+#[must_use]
+pub fn to_visual_graph(graph: &Graph, input: &Entity, types: &Registry) -> VisualGraph {
+    let input_node = types.plugin_node(input);
+    let mut visual = VisualGraph::empty();
+    let ids: Vec<usize> = graph
+        .node_indices()
+        .map(|index| {
+            let node = &graph[index];
+            if input_node.as_ref() == Some(node) {
+                return visual.add_node(input.clone());
+            }
+            let (entity, properties) = entity_from_plugin(node.clone(), types);
+            let id = visual.add_node(entity);
+            for (name, value) in properties {
+                visual.set_property(id, &name, &value);
+            }
+            id
+        })
+        .collect();
+    for edge in graph.edge_indices() {
+        if let Some((from, to)) = graph.edge_endpoints(edge) {
+            visual.add_edge(ids[from.index()], ids[to.index()], EdgeKind::Related);
+        }
+    }
+    visual
+}
+
+/// Load the plugin at an URL and run it on entities, collecting what it found
+/// into one graph. Everything in it records the URL of the plugin and when it
+/// was found as its provenance.
+///
+/// # Errors
+///
+/// Returns an error if the plugin can't be loaded or fails on an entity.
+pub fn transform(url: &str, entities: Vec<Entity>, types: Registry) -> Result<VisualGraph, Error> {
+    let plugin_url = url.to_string();
+    // plugins still panic when they fail, which mustn't take the app down
+    std::thread::spawn(move || {
+        let mut plugin = Wrapper::load(&plugin_url).start();
+        let mut found = VisualGraph::empty();
+        for entity in &entities {
+            if let Some(graph) = plugin.exec_on_entity(entity, &types) {
+                let mut visual = to_visual_graph(&graph, entity, &types);
+                let provenance = Provenance::now(&plugin_url);
+                for node in &mut visual.nodes {
+                    node.provenance = Some(provenance.clone());
+                }
+                for edge in &mut visual.edges {
+                    edge.provenance = Some(provenance.clone());
+                }
+                found.update(visual);
+            }
+        }
+        found
+    })
+    .join()
+    .map_err(|_| Error::Plugin(format!("the plugin at {url} failed")))
+}
+
+/// The entity a node returned by a plugin stands for, together with the
+/// properties the plugin set on it.
+///
+/// Plugins can only return nodes, so they set properties by adding lines like
+/// `first seen=2024-01-01` to the text of a node, e.g. the name of a person or
+/// a domain. Nodes that can't hold such text, like IPs, can't get properties.
+///
+/// Entities of custom types are returned the same way, with the name of the
+/// type before their value, e.g. a person named `CryptoWallet:bc1q...` with a
+/// line `currency=BTC`. The lines are then the properties of the type. Nodes
+/// that don't fit the schema of their type stay as they are.
+fn entity_from_plugin(node: Node, types: &Registry) -> (Entity, Vec<(String, String)>) {
+    let entity = Entity::Node(node);
+    let label = entity::label(&entity);
+    let mut lines = label.lines();
+    let value = lines.next().unwrap_or_default();
+    let properties: Vec<(String, String)> = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(PROPERTY_SEPARATOR)?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+    let custom = value
+        .split_once(TYPE_SEPARATOR)
+        .and_then(|(name, value)| types.get(name.trim())?.create(value, &properties).ok());
+    if let Some(custom) = custom {
+        return (custom, Vec::new());
+    }
+    if properties.is_empty() {
+        return (entity, properties);
+    }
+    // the entity without the lines of the properties
+    let node = entity
+        .as_node()
+        .and_then(EntityKind::of)
+        .and_then(|kind| kind.create(value).ok());
+    match node {
+        Some(node) => (node.into(), properties),
+        None => (entity, Vec::new()),
+    }
+}
@@ -0,0 +1,389 @@
+use std::fmt::{self, Display};
+
+use iced::Color;
+use schnuffel_types::graph::Node;
+use serde_json::{json, Value};
+
+use crate::entity::{self, Entity, EntityKind, GenericEntity};
+use crate::error::Error;
+
+/// What kind of value a property of a custom entity type holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PropertyKind {
+    #[default]
+    Text,
+    Number,
+    Url,
+}
+
+impl PropertyKind {
+    pub const ALL: [PropertyKind; 3] =
+        [PropertyKind::Text, PropertyKind::Number, PropertyKind::Url];
+
+    /// The name of the kind, used in type definition files.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            PropertyKind::Text => "text",
+            PropertyKind::Number => "number",
+            PropertyKind::Url => "url",
+        }
+    }
+
+    /// Get the kind with the given name, ignoring case.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        PropertyKind::ALL
+            .into_iter()
+            .find(|k| k.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Whether a value is valid for the kind.
+    #[must_use]
+    pub fn accepts(self, value: &str) -> bool {
+        match self {
+            PropertyKind::Text => true,
+            PropertyKind::Number => value.trim().parse::<f64>().is_ok(),
+            PropertyKind::Url => url::Url::parse(value.trim()).is_ok(),
+        }
+    }
+}
+
+impl Display for PropertyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyKind::Text => write!(f, "Text"),
+            PropertyKind::Number => write!(f, "Number"),
+            PropertyKind::Url => write!(f, "URL"),
+        }
+    }
+}
+
+/// A property entities of a custom type can have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertySchema {
+    pub name: String,
+    pub kind: PropertyKind,
+    /// Whether every entity of the type needs the property.
+    pub required: bool,
+}
+
+/// A type of entity defined by the user, e.g. crypto wallets, for things the
+/// built-in [`Node`] variants don't cover.
+///
+/// Entities of the type are [`GenericEntity`]s whose `entity_type` is the
+/// name of the type, so every format that keeps generic entities keeps them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityType {
+    /// The name entities of the type are stored under, e.g. `CryptoWallet`.
+    pub name: String,
+    /// A short text, usually a single symbol, drawn on the entities.
+    pub icon: String,
+    pub color: Color,
+    pub properties: Vec<PropertySchema>,
+    /// The built-in kind the entities are passed to plugins as, which only
+    /// know the built-in types. `None` if plugins can't work with them.
+    pub plugin_kind: Option<EntityKind>,
+}
+
+impl EntityType {
+    /// Create an entity of this type, checking its properties against the
+    /// schema. Empty properties are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value or a required property is missing or a
+    /// property isn't valid for its kind.
+    pub fn create(&self, value: &str, properties: &[(String, String)]) -> Result<Entity, Error> {
+        let invalid =
+            |reason: String| Error::InvalidFormat(format!("invalid {}: {reason}", self.name));
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(invalid("the value is missing".to_string()));
+        }
+        let properties: Vec<(String, String)> = properties
+            .iter()
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        for schema in &self.properties {
+            match properties.iter().find(|(name, _)| *name == schema.name) {
+                Some((_, value)) if !schema.kind.accepts(value) => {
+                    return Err(invalid(format!("{} isn't a {}", schema.name, schema.kind)));
+                }
+                None if schema.required => {
+                    return Err(invalid(format!("{} is missing", schema.name)));
+                }
+                _ => {}
+            }
+        }
+        Ok(Entity::Generic(GenericEntity {
+            entity_type: self.name.clone(),
+            value: value.to_string(),
+            properties,
+        }))
+    }
+}
+
+/// The custom entity types known to an investigation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registry {
+    pub types: Vec<EntityType>,
+}
+
+impl Default for Registry {
+    /// The registry with the types most investigations need.
+    fn default() -> Self {
+        let property = |name: &str, kind, required| PropertySchema {
+            name: name.to_string(),
+            kind,
+            required,
+        };
+        let entity_type = |name: &str, icon: &str, color, properties, plugin_kind| EntityType {
+            name: name.to_string(),
+            icon: icon.to_string(),
+            color,
+            properties,
+            plugin_kind,
+        };
+        Self {
+            types: vec![
+                entity_type(
+                    "Username",
+                    "@",
+                    Color::from_rgb8(0x17, 0xbe, 0xcf),
+                    vec![property("platform", PropertyKind::Text, false)],
+                    Some(EntityKind::Person),
+                ),
+                entity_type(
+                    "CryptoWallet",
+                    "₿",
+                    Color::from_rgb8(0xf7, 0x93, 0x1a),
+                    vec![
+                        property("currency", PropertyKind::Text, true),
+                        property("balance", PropertyKind::Number, false),
+                    ],
+                    None,
+                ),
+                entity_type(
+                    "FileHash",
+                    "#",
+                    Color::from_rgb8(0x7f, 0x7f, 0x7f),
+                    vec![
+                        property("algorithm", PropertyKind::Text, true),
+                        property("file name", PropertyKind::Text, false),
+                    ],
+                    None,
+                ),
+                entity_type(
+                    "Certificate",
+                    "C",
+                    Color::from_rgb8(0x2c, 0xa0, 0x2c),
+                    vec![
+                        property("subject", PropertyKind::Text, false),
+                        property("issuer", PropertyKind::Text, false),
+                        property("not after", PropertyKind::Text, false),
+                    ],
+                    None,
+                ),
+                entity_type(
+                    "ASN",
+                    "AS",
+                    Color::from_rgb8(0x94, 0x67, 0xbd),
+                    vec![property("name", PropertyKind::Text, false)],
+                    None,
+                ),
+                entity_type(
+                    "Location",
+                    "L",
+                    Color::from_rgb8(0xd6, 0x27, 0x28),
+                    vec![
+                        property("latitude", PropertyKind::Number, false),
+                        property("longitude", PropertyKind::Number, false),
+                    ],
+                    None,
+                ),
+                entity_type(
+                    "Vehicle",
+                    "V",
+                    Color::from_rgb8(0x8c, 0x56, 0x4b),
+                    vec![
+                        property("license plate", PropertyKind::Text, false),
+                        property("model", PropertyKind::Text, false),
+                    ],
+                    None,
+                ),
+            ],
+        }
+    }
+}
+
+impl Registry {
+    /// The type of an entity, if it is a generic entity of a known type.
+    #[must_use]
+    pub fn type_of(&self, entity: &Entity) -> Option<&EntityType> {
+        let Entity::Generic(generic) = entity else {
+            return None;
+        };
+        self.get(&generic.entity_type)
+    }
+
+    /// The type with a name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&EntityType> {
+        self.types.iter().find(|t| t.name == name)
+    }
+
+    /// Add a type, replacing the one with the same name.
+    pub fn insert(&mut self, entity_type: EntityType) {
+        match self.types.iter_mut().find(|t| t.name == entity_type.name) {
+            Some(existing) => *existing = entity_type,
+            None => self.types.push(entity_type),
+        }
+    }
+
+    /// The name of the type of an entity as shown to the user, with the icon
+    /// of its custom type if it has one.
+    #[must_use]
+    pub fn display_name(&self, entity: &Entity) -> String {
+        match self.type_of(entity) {
+            Some(entity_type) if !entity_type.icon.is_empty() => {
+                format!("{} {}", entity_type.icon, entity_type.name)
+            }
+            _ => entity::display_name(entity).to_string(),
+        }
+    }
+
+    /// The node an entity is passed to plugins as.
+    ///
+    /// Built-in entities are passed as they are, entities of custom types as
+    /// their [`EntityType::plugin_kind`] created from their value.
+    #[must_use]
+    pub fn plugin_node(&self, entity: &Entity) -> Option<Node> {
+        match entity {
+            Entity::Node(node) => Some(node.clone()),
+            Entity::Generic(generic) => self
+                .type_of(entity)?
+                .plugin_kind?
+                .create(&generic.value)
+                .ok(),
+        }
+    }
+}
+
+/// Write a color as `#rrggbb`.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn color_to_hex(color: Color) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
+
+/// Read a color written as `#rrggbb`.
+#[must_use]
+pub fn color_from_hex(hex: &str) -> Option<Color> {
+    let hex = hex.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Read entity types from a JSON array of objects with a `name`, an `icon`, a
+/// `color` like `#f7931a`, `properties` with a `name`, a `kind` and whether
+/// they are `required`, and optionally the `plugin_kind` written like
+/// [`EntityKind`] is shown.
+///
+/// # Errors
+///
+/// Returns an error if the data isn't JSON, a type has no name or a color,
+/// property kind or plugin kind is unknown.
+pub fn read_types(data: &[u8]) -> Result<Vec<EntityType>, Error> {
+    let json: Value = serde_json::from_slice(data)?;
+    let invalid = |reason: &str| Error::InvalidFormat(format!("invalid entity type: {reason}"));
+    json.as_array()
+        .ok_or_else(|| invalid("expected a list of entity types"))?
+        .iter()
+        .map(|definition| {
+            let name = text(definition, "name").ok_or_else(|| invalid("a type has no name"))?;
+            let color = match text(definition, "color") {
+                Some(color) => color_from_hex(color).ok_or_else(|| invalid(color))?,
+                None => Color::BLACK,
+            };
+            let plugin_kind = match text(definition, "plugin_kind") {
+                Some(kind) => Some(
+                    EntityKind::ALL
+                        .into_iter()
+                        .find(|k| k.to_string() == kind)
+                        .ok_or_else(|| invalid(kind))?,
+                ),
+                None => None,
+            };
+            let properties = definition
+                .get("properties")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|property| {
+                    let kind = text(property, "kind").unwrap_or_default();
+                    Ok(PropertySchema {
+                        name: text(property, "name")
+                            .ok_or_else(|| invalid("a property has no name"))?
+                            .to_string(),
+                        kind: PropertyKind::from_name(kind).ok_or_else(|| invalid(kind))?,
+                        required: property
+                            .get("required")
+                            .and_then(Value::as_bool)
+                            .unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+            Ok(EntityType {
+                name: name.to_string(),
+                icon: text(definition, "icon").unwrap_or_default().to_string(),
+                color,
+                properties,
+                plugin_kind,
+            })
+        })
+        .collect()
+}
+
+fn text<'a>(value: &'a Value, name: &str) -> Option<&'a str> {
+    value.get(name).and_then(Value::as_str)
+}
+
+/// Write entity types as a JSON array, see [`read_types`].
+///
+/// # Errors
+///
+/// Returns an error if the JSON can't be written.
+pub fn write_types(types: &[EntityType]) -> Result<Vec<u8>, Error> {
+    let json: Vec<Value> = types
+        .iter()
+        .map(|t| {
+            let properties: Vec<Value> = t
+                .properties
+                .iter()
+                .map(|p| json!({ "name": p.name, "kind": p.kind.name(), "required": p.required }))
+                .collect();
+            let mut definition = json!({
+                "name": t.name,
+                "icon": t.icon,
+                "color": color_to_hex(t.color),
+                "properties": properties,
+            });
+            if let Some(kind) = t.plugin_kind {
+                definition["plugin_kind"] = json!(kind.to_string());
+            }
+            definition
+        })
+        .collect();
+    Ok(serde_json::to_vec_pretty(&json)?)
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use iced::widget::{button, column, pick_list, row, text, Column, Row};
+use iced::{Color, Command, Element, Length, Theme};
+
+use crate::analysis::centrality::{self, Metric, NodeMetrics};
+use crate::entity;
+use crate::views::graph::{constants, GraphState, VisualGraph};
+use crate::Message;
+
+/// The state of the panel showing how central the entities are.
+#[derive(Debug, Clone)]
+pub struct Analytics {
+    /// The measures of every node, `None` while they are computed.
+    pub metrics: Option<Vec<NodeMetrics>>,
+    /// The measure the table is sorted by.
+    pub sort_by: Metric,
+    pub descending: bool,
+    /// The measure the nodes are colored by on the canvas.
+    pub color_by: Option<Metric>,
+}
+
+impl Default for Analytics {
+    fn default() -> Self {
+        Self {
+            metrics: None,
+            sort_by: Metric::Degree,
+            descending: true,
+            color_by: None,
+        }
+    }
+}
+
+impl Analytics {
+    /// The color of every node by [`Analytics::color_by`]. Groups get colors
+    /// from a palette, other measures a color between the lowest and the
+    /// highest value.
+    #[must_use]
+    pub fn colors(&self) -> HashMap<usize, Color> {
+        let (Some(metric), Some(metrics)) = (self.color_by, &self.metrics) else {
+            return HashMap::new();
+        };
+        let (min, max) = range(metrics, metric);
+        metrics
+            .iter()
+            .map(|m| {
+                let value = metric.value(m);
+                let color = if metric.is_group() {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let group = value as usize;
+                    constants::GROUP_COLORS[group % constants::GROUP_COLORS.len()]
+                } else {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let share = normalize(value, min, max) as f32;
+                    mix(
+                        constants::LOW_METRIC_COLOR,
+                        constants::HIGHLIGHT_COLOR,
+                        share,
+                    )
+                };
+                (m.id, color)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AnalyticsMessage {
+    Open,
+    Computed(Vec<NodeMetrics>),
+    Refresh,
+    SortBy(Metric),
+    ColorBy(Metric),
+    ResetColors,
+    SizeBy(Metric),
+    NodeSelected(usize),
+    Close,
+}
+
+pub fn update(state: &mut GraphState, message: AnalyticsMessage) -> Command<Message> {
+    if let AnalyticsMessage::Open = message {
+        state.analytics = Some(Analytics::default());
+        return compute(state.graph.clone());
+    }
+    let Some(analytics) = &mut state.analytics else {
+        return Command::none();
+    };
+
+    match message {
+        // opening is handled above
+        AnalyticsMessage::Open => {}
+        AnalyticsMessage::Computed(metrics) => analytics.metrics = Some(metrics),
+        AnalyticsMessage::Refresh => {
+            analytics.metrics = None;
+            return compute(state.graph.clone());
+        }
+        AnalyticsMessage::SortBy(metric) => {
+            // sorting by the same measure again flips the order
+            analytics.descending = analytics.sort_by != metric || !analytics.descending;
+            analytics.sort_by = metric;
+        }
+        AnalyticsMessage::ColorBy(metric) => analytics.color_by = Some(metric),
+        AnalyticsMessage::ResetColors => analytics.color_by = None,
+        AnalyticsMessage::SizeBy(metric) => {
+            if let Some(metrics) = &analytics.metrics {
+                size_nodes(&mut state.graph, metrics, metric);
+            }
+        }
+        AnalyticsMessage::NodeSelected(id) => {
+            for node in &mut state.graph.nodes {
+                node.is_selected = node.id == id;
+            }
+        }
+        AnalyticsMessage::Close => state.analytics = None,
+    }
+    Command::none()
+}
+
+/// Compute the measures off the UI thread, as this takes a while for large
+/// graphs.
+fn compute(graph: VisualGraph) -> Command<Message> {
+    Command::perform(async move { centrality::analyze(&graph) }, |metrics| {
+        Message::Analytics(AnalyticsMessage::Computed(metrics))
+    })
+}
+
+/// Give the nodes with the highest values of a measure the largest radius.
+#[allow(clippy::cast_possible_truncation)]
+fn size_nodes(graph: &mut VisualGraph, metrics: &[NodeMetrics], metric: Metric) {
+    let (min, max) = range(metrics, metric);
+    let values: HashMap<usize, f64> = metrics.iter().map(|m| (m.id, metric.value(m))).collect();
+    for node in &mut graph.nodes {
+        if let Some(&value) = values.get(&node.id) {
+            let share = normalize(value, min, max) as f32;
+            node.radius = constants::MIN_NODE_RADIUS
+                + (constants::MAX_NODE_RADIUS - constants::MIN_NODE_RADIUS) * share;
+        }
+    }
+}
+
+fn range(metrics: &[NodeMetrics], metric: Metric) -> (f64, f64) {
+    metrics
+        .iter()
+        .map(|m| metric.value(m))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+/// Where a value lies between the minimum and the maximum, from 0 to 1.
+fn normalize(value: f64, min: f64, max: f64) -> f64 {
+    if max > min {
+        (value - min) / (max - min)
+    } else {
+        0.0
+    }
+}
+
+fn mix(a: Color, b: Color, share: f32) -> Color {
+    Color::from_rgb(
+        a.r + (b.r - a.r) * share,
+        a.g + (b.g - a.g) * share,
+        a.b + (b.b - a.b) * share,
+    )
+}
+
+#[must_use]
+pub fn view<'a>(
+    analytics: &'a Analytics,
+    state: &'a GraphState,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let controls = column!(
+        text("Centrality and communities"),
+        row!(
+            pick_list(&Metric::ALL[..], analytics.color_by, |m| {
+                Message::Analytics(AnalyticsMessage::ColorBy(m))
+            })
+            .placeholder("Color nodes by"),
+            button("Reset colors").on_press(Message::Analytics(AnalyticsMessage::ResetColors)),
+        )
+        .spacing(5),
+        pick_list(&Metric::ALL[..], None::<Metric>, |m| {
+            Message::Analytics(AnalyticsMessage::SizeBy(m))
+        })
+        .placeholder("Size nodes by"),
+        row!(
+            button("Refresh").on_press(Message::Analytics(AnalyticsMessage::Refresh)),
+            button("Close").on_press(Message::Analytics(AnalyticsMessage::Close)),
+        )
+        .spacing(5),
+    )
+    .spacing(5);
+
+    let table: Element<'_, Message, Theme, iced::Renderer> = match &analytics.metrics {
+        Some(metrics) => table(analytics, metrics, state).into(),
+        None => text("Computing…").into(),
+    };
+    column!(controls, table).spacing(10).padding(10)
+}
+
+/// The nodes sorted by the chosen measure, with a header whose buttons sort
+/// by the other measures.
+fn table<'a>(
+    analytics: &Analytics,
+    metrics: &'a [NodeMetrics],
+    state: &'a GraphState,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let mut header = vec![text("Entity").width(Length::FillPortion(3)).into()];
+    for metric in Metric::ALL {
+        let label = if metric != analytics.sort_by {
+            metric.to_string()
+        } else if analytics.descending {
+            format!("{metric} ▼")
+        } else {
+            format!("{metric} ▲")
+        };
+        header.push(
+            button(text(label).size(12))
+                .on_press(Message::Analytics(AnalyticsMessage::SortBy(metric)))
+                .width(Length::FillPortion(1))
+                .into(),
+        );
+    }
+
+    let mut sorted: Vec<&NodeMetrics> = metrics.iter().collect();
+    sorted.sort_by(|a, b| {
+        let order = analytics
+            .sort_by
+            .value(a)
+            .total_cmp(&analytics.sort_by.value(b));
+        if analytics.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+
+    let mut rows = vec![Row::with_children(header).spacing(2).into()];
+    for m in sorted.into_iter().take(constants::ANALYTICS_TABLE_ROWS) {
+        // the graph may have changed since the measures were computed
+        let Some(node) = state.graph.node(m.id) else {
+            continue;
+        };
+        let mut cells = vec![button(text(entity::label(&node.node)).size(12))
+            .on_press(Message::Analytics(AnalyticsMessage::NodeSelected(m.id)))
+            .width(Length::FillPortion(3))
+            .into()];
+        for metric in Metric::ALL {
+            let value = if metric.is_group() || metric == Metric::Degree {
+                format!("{}", metric.value(m))
+            } else {
+                format!("{:.4}", metric.value(m))
+            };
+            cells.push(text(value).size(12).width(Length::FillPortion(1)).into());
+        }
+        rows.push(Row::with_children(cells).spacing(2).into());
+    }
+    Column::from_vec(rows).spacing(2)
+}
@@ -0,0 +1,182 @@
+use iced::widget::{button, column, pick_list, row, text, text_editor, text_input, Column};
+use iced::{Alignment, Color, Element, Theme};
+
+use crate::annotation::Confidence;
+use crate::views::graph::{constants, GraphState, VisualGraph, VisualNode};
+use crate::Message;
+
+/// The state of the fields for annotating the selected entity in the info
+/// column.
+#[derive(Debug, Default)]
+pub struct AnnotationEditor {
+    /// The node being annotated, the first selected one.
+    pub id: Option<usize>,
+    pub notes: text_editor::Content,
+    /// The tags typed but not added yet.
+    pub tag: String,
+    pub property_name: String,
+    pub property_value: String,
+}
+
+impl AnnotationEditor {
+    /// Follow the selection, starting over with the notes of a newly selected
+    /// node or notes that were changed elsewhere, e.g. by an import.
+    pub fn sync(&mut self, graph: &VisualGraph) {
+        let selected = graph.nodes.iter().find(|n| n.is_selected);
+        let id = selected.map(|n| n.id);
+        let notes = selected.map_or("", |n| n.annotations.notes.as_str());
+        if id != self.id || notes != self.notes.text().trim_end() {
+            *self = AnnotationEditor {
+                id,
+                notes: text_editor::Content::with_text(notes),
+                ..AnnotationEditor::default()
+            };
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AnnotationMessage {
+    NotesEdited(text_editor::Action),
+    TagChanged(String),
+    AddTag,
+    RemoveTag(String),
+    ConfidenceSelected(Confidence),
+    PropertyNameChanged(String),
+    PropertyValueChanged(String),
+    SetProperty,
+    RemoveProperty(String),
+}
+
+pub fn update(state: &mut GraphState, message: AnnotationMessage) {
+    let editor = &mut state.annotation_editor;
+    let Some(node) = editor.id.and_then(|id| state.graph.node_mut(id)) else {
+        return;
+    };
+    let annotations = &mut node.annotations;
+
+    match message {
+        AnnotationMessage::NotesEdited(action) => {
+            let is_edit = action.is_edit();
+            editor.notes.perform(action);
+            if is_edit {
+                // the editor always ends the text with a line break
+                annotations.notes = editor.notes.text().trim_end().to_string();
+            }
+        }
+        AnnotationMessage::TagChanged(tag) => editor.tag = tag,
+        AnnotationMessage::AddTag => {
+            annotations.add_tags(&editor.tag);
+            editor.tag.clear();
+        }
+        AnnotationMessage::RemoveTag(tag) => annotations.tags.retain(|t| *t != tag),
+        AnnotationMessage::ConfidenceSelected(confidence) => annotations.confidence = confidence,
+        AnnotationMessage::PropertyNameChanged(name) => editor.property_name = name,
+        AnnotationMessage::PropertyValueChanged(value) => editor.property_value = value,
+        AnnotationMessage::SetProperty => {
+            let name = editor.property_name.trim();
+            if !name.is_empty() {
+                annotations
+                    .properties
+                    .insert(name.to_string(), editor.property_value.trim().to_string());
+                editor.property_name.clear();
+                editor.property_value.clear();
+            }
+        }
+        AnnotationMessage::RemoveProperty(name) => {
+            annotations.properties.remove(&name);
+        }
+    }
+}
+
+/// The color a tag is shown in, on the canvas as well as in the info column.
+#[must_use]
+pub fn tag_color(tag: &str) -> Color {
+    if let Some((_, color)) = constants::TAG_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+    {
+        return *color;
+    }
+    // the same tag always gets the same color
+    let hash = tag.to_lowercase().bytes().fold(0_usize, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(usize::from(b))
+    });
+    constants::GROUP_COLORS[hash % constants::GROUP_COLORS.len()]
+}
+
+/// The fields for the notes, tags, confidence and properties of a node.
+#[must_use]
+pub fn view<'a>(
+    editor: &'a AnnotationEditor,
+    node: &'a VisualNode,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let annotations = &node.annotations;
+    let tags: Vec<Element<'_, Message, Theme, iced::Renderer>> = annotations
+        .tags
+        .iter()
+        .map(|tag| {
+            row!(
+                text(tag).style(tag_color(tag)),
+                button("Remove").on_press(Message::Annotation(AnnotationMessage::RemoveTag(
+                    tag.clone()
+                ))),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into()
+        })
+        .collect();
+    let properties: Vec<Element<'_, Message, Theme, iced::Renderer>> = annotations
+        .properties
+        .iter()
+        .map(|(name, value)| {
+            row!(
+                text(format!("{name}: {value}")),
+                button("Remove").on_press(Message::Annotation(AnnotationMessage::RemoveProperty(
+                    name.clone()
+                ))),
+            )
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into()
+        })
+        .collect();
+
+    column!(
+        pick_list(&Confidence::ALL[..], Some(annotations.confidence), |c| {
+            Message::Annotation(AnnotationMessage::ConfidenceSelected(c))
+        }),
+        text("Notes"),
+        text_editor(&editor.notes)
+            .on_action(|action| Message::Annotation(AnnotationMessage::NotesEdited(action)))
+            .height(constants::NOTES_EDITOR_HEIGHT),
+        text("Tags"),
+        Column::from_vec(tags).spacing(5),
+        row!(
+            text_input("suspect, victim", &editor.tag)
+                .on_input(|t| Message::Annotation(AnnotationMessage::TagChanged(t)))
+                .on_submit(Message::Annotation(AnnotationMessage::AddTag)),
+            button("Add").on_press_maybe(
+                (!editor.tag.trim().is_empty())
+                    .then_some(Message::Annotation(AnnotationMessage::AddTag))
+            ),
+        )
+        .spacing(5),
+        text("Properties"),
+        Column::from_vec(properties).spacing(5),
+        row!(
+            text_input("Name", &editor.property_name)
+                .on_input(|n| Message::Annotation(AnnotationMessage::PropertyNameChanged(n))),
+            text_input("Value", &editor.property_value)
+                .on_input(|v| Message::Annotation(AnnotationMessage::PropertyValueChanged(v)))
+                .on_submit(Message::Annotation(AnnotationMessage::SetProperty)),
+            button("Set").on_press_maybe(
+                (!editor.property_name.trim().is_empty())
+                    .then_some(Message::Annotation(AnnotationMessage::SetProperty))
+            ),
+        )
+        .spacing(5),
+    )
+    .spacing(10)
+}
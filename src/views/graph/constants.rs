@@ -1,6 +1,72 @@
+use iced::Color;
+
 pub const MIN_ZOOM: f32 = 0.5;
 pub const MAX_ZOOM: f32 = 3.0;
 pub const ZOOM_MULTIPLIER: f32 = 0.5;
 pub const PAN_MULTIPLIER: f32 = 0.75;
-pub const NODE_ZOOM_POSITION_CHANGE: f32 = 10.0;
 pub const NODE_ZOOM_SCALING: f32 = 0.5;
+
+// sensitivity of the different scroll and gesture inputs
+/// Zoom change per pixel of a pixel based scroll (most trackpads).
+pub const PIXEL_ZOOM_SENSITIVITY: f32 = 0.01;
+/// How far the canvas moves per pixel of a two finger scroll.
+pub const SCROLL_PAN_MULTIPLIER: f32 = 1.0;
+/// Zoom change per pixel that two touching fingers move apart or together.
+pub const PINCH_ZOOM_SENSITIVITY: f32 = 0.005;
+
+/// The color of the nodes and edges connecting the selected entities.
+pub const HIGHLIGHT_COLOR: Color = Color::from_rgb(0.85, 0.2, 0.1);
+
+/// The color of the nodes with the lowest value of the measure they are
+/// colored by, the ones with the highest value get [`HIGHLIGHT_COLOR`].
+pub const LOW_METRIC_COLOR: Color = Color::from_rgb(0.2, 0.35, 0.7);
+/// The colors of the components or communities nodes are colored by, which
+/// repeat for more groups.
+pub const GROUP_COLORS: [Color; 8] = [
+    Color::from_rgb(0.12, 0.47, 0.71),
+    Color::from_rgb(1.0, 0.5, 0.05),
+    Color::from_rgb(0.17, 0.63, 0.17),
+    Color::from_rgb(0.84, 0.15, 0.16),
+    Color::from_rgb(0.58, 0.4, 0.74),
+    Color::from_rgb(0.55, 0.34, 0.29),
+    Color::from_rgb(0.89, 0.47, 0.76),
+    Color::from_rgb(0.5, 0.5, 0.5),
+];
+// the radii of nodes sized by a measure
+pub const MIN_NODE_RADIUS: f32 = 5.0;
+pub const MAX_NODE_RADIUS: f32 = 25.0;
+
+/// The opacity of the entities that don't match the search when they are
+/// dimmed.
+pub const DIMMED_ALPHA: f32 = 0.15;
+
+/// The width of the edges with the largest weights, before zooming.
+pub const MAX_EDGE_WIDTH: f32 = 5.0;
+
+// placement of nodes that are added without a position (e.g. by imports)
+pub const NEW_NODE_CENTER: f32 = 300.0;
+pub const NEW_NODE_SPACING: f32 = 40.0;
+/// The golden angle, which spreads nodes on the spiral evenly.
+pub const NEW_NODE_ANGLE: f32 = 2.399_963;
+
+/// How many of the entities a CSV import would create are listed in its preview.
+pub const CSV_PREVIEW_ENTITIES: usize = 20;
+/// The height of the text field entities are extracted from.
+pub const PASTE_EDITOR_HEIGHT: f32 = 200.0;
+/// How many of the most central entities are listed by the analytics panel.
+pub const ANALYTICS_TABLE_ROWS: usize = 100;
+pub const SEARCH_FIELD_WIDTH: f32 = 250.0;
+/// How many of the entities found by the search are listed.
+pub const SEARCH_RESULTS: usize = 200;
+
+/// The colors of tags analysts commonly use, other tags get one of
+/// [`GROUP_COLORS`].
+pub const TAG_COLORS: [(&str, Color); 3] = [
+    ("suspect", Color::from_rgb(0.84, 0.15, 0.16)),
+    ("victim", Color::from_rgb(1.0, 0.5, 0.05)),
+    ("benign", Color::from_rgb(0.17, 0.63, 0.17)),
+];
+/// The width of the ring around tagged nodes, in the color of their first tag.
+pub const TAG_RING_WIDTH: f32 = 3.0;
+/// The height of the field for the notes about an entity.
+pub const NOTES_EDITOR_HEIGHT: f32 = 120.0;
@@ -0,0 +1,212 @@
+use std::fmt::{self, Display};
+
+use iced::widget::{button, column, pick_list, row, text, text_input, Column};
+use iced::{Element, Theme};
+
+use crate::entity::{self, EntityKind};
+use crate::error::Error;
+use crate::formats::csv::{self, ColumnMapping, CsvTable, RelatedColumn};
+use crate::views::graph::{constants, EdgeKind, GraphState, VisualGraph};
+use crate::Message;
+
+/// The state of the wizard that turns the rows of a CSV file into entities.
+#[derive(Debug, Clone)]
+pub struct CsvImport {
+    pub table: CsvTable,
+    pub column: usize,
+    pub entity: EntityKind,
+    pub related_column: Option<usize>,
+    pub related_entity: EntityKind,
+    pub edge: String,
+    /// The entities that will be added to the graph, or why they can't be.
+    pub preview: Result<VisualGraph, Error>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CsvMessage {
+    ColumnSelected(CsvColumn),
+    EntitySelected(EntityKind),
+    RelatedColumnSelected(CsvColumn),
+    RelatedEntitySelected(EntityKind),
+    EdgeChanged(String),
+    Merge,
+    Cancel,
+}
+
+/// A column of the table as shown in the column pickers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvColumn {
+    /// `None` for the "no column" choice.
+    pub index: Option<usize>,
+    pub name: String,
+}
+
+impl Display for CsvColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl CsvImport {
+    #[must_use]
+    pub fn new(table: CsvTable) -> Self {
+        let mut import = Self {
+            table,
+            column: 0,
+            entity: EntityKind::Person,
+            related_column: None,
+            related_entity: EntityKind::Person,
+            edge: EdgeKind::Related.name().to_string(),
+            preview: Ok(VisualGraph::empty()),
+        };
+        import.refresh_preview();
+        import
+    }
+
+    /// The mapping currently configured by the user.
+    #[must_use]
+    pub fn mapping(&self) -> ColumnMapping {
+        ColumnMapping {
+            column: self.column,
+            entity: self.entity,
+            related: self.related_column.map(|column| RelatedColumn {
+                column,
+                entity: self.related_entity,
+                edge: EdgeKind::from_name(&self.edge),
+            }),
+        }
+    }
+
+    fn refresh_preview(&mut self) {
+        self.preview = csv::build(&self.table, &self.mapping());
+    }
+
+    fn columns(&self) -> Vec<CsvColumn> {
+        self.table
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, name)| CsvColumn {
+                index: Some(i),
+                name: name.clone(),
+            })
+            .collect()
+    }
+
+    fn column(&self, index: Option<usize>) -> CsvColumn {
+        let name = index.and_then(|i| self.table.headers.get(i));
+        CsvColumn {
+            index,
+            name: name.map_or("(none)".to_string(), Clone::clone),
+        }
+    }
+}
+
+pub fn update(state: &mut GraphState, message: CsvMessage) {
+    let Some(import) = &mut state.csv_import else {
+        return;
+    };
+
+    match message {
+        CsvMessage::ColumnSelected(column) => import.column = column.index.unwrap_or_default(),
+        CsvMessage::EntitySelected(entity) => import.entity = entity,
+        CsvMessage::RelatedColumnSelected(column) => import.related_column = column.index,
+        CsvMessage::RelatedEntitySelected(entity) => import.related_entity = entity,
+        CsvMessage::EdgeChanged(edge) => import.edge = edge,
+        CsvMessage::Merge => {
+            if let Ok(graph) = &import.preview {
+                state.graph.merge(graph.clone());
+            }
+            state.csv_import = None;
+            return;
+        }
+        CsvMessage::Cancel => {
+            state.csv_import = None;
+            return;
+        }
+    }
+    import.refresh_preview();
+}
+
+#[must_use]
+pub fn view(import: &CsvImport) -> Column<'_, Message, Theme, iced::Renderer> {
+    let mut related_columns = vec![import.column(None)];
+    related_columns.append(&mut import.columns());
+
+    let mapping = column!(
+        text("Import CSV"),
+        row!(
+            text("Column"),
+            pick_list(
+                import.columns(),
+                Some(import.column(Some(import.column))),
+                |c| Message::Csv(CsvMessage::ColumnSelected(c))
+            ),
+            text("contains"),
+            pick_list(&EntityKind::ALL[..], Some(import.entity), |e| {
+                Message::Csv(CsvMessage::EntitySelected(e))
+            }),
+        )
+        .spacing(5),
+        row!(
+            text("Related column"),
+            pick_list(
+                related_columns,
+                Some(import.column(import.related_column)),
+                |c| Message::Csv(CsvMessage::RelatedColumnSelected(c))
+            ),
+            text("contains"),
+            pick_list(&EntityKind::ALL[..], Some(import.related_entity), |e| {
+                Message::Csv(CsvMessage::RelatedEntitySelected(e))
+            }),
+        )
+        .spacing(5),
+        row!(
+            text("Edge type"),
+            text_input(EdgeKind::Related.name(), &import.edge)
+                .on_input(|e| Message::Csv(CsvMessage::EdgeChanged(e))),
+        )
+        .spacing(5),
+    )
+    .spacing(5);
+
+    let preview: Element<'_, Message, Theme, iced::Renderer> = match &import.preview {
+        Ok(graph) => {
+            let mut lines: Vec<Element<'_, Message, Theme, iced::Renderer>> = vec![text(format!(
+                "{} entities, {} relationships",
+                graph.nodes.len(),
+                graph.edges.len()
+            ))
+            .into()];
+            for node in graph.nodes.iter().take(constants::CSV_PREVIEW_ENTITIES) {
+                lines.push(
+                    text(format!(
+                        "{}: {}",
+                        entity::type_name(&node.node),
+                        entity::label(&node.node)
+                    ))
+                    .into(),
+                );
+            }
+            Column::from_vec(lines).into()
+        }
+        Err(e) => text(e.to_string()).into(),
+    };
+
+    column!(
+        mapping,
+        preview,
+        row!(
+            button("Merge into graph").on_press_maybe(
+                import
+                    .preview
+                    .is_ok()
+                    .then_some(Message::Csv(CsvMessage::Merge))
+            ),
+            button("Cancel").on_press(Message::Csv(CsvMessage::Cancel)),
+        )
+        .spacing(5),
+    )
+    .spacing(10)
+    .padding(10)
+}
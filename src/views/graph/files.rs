@@ -0,0 +1,380 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Column};
+use iced::{Alignment, Command, Point, Rectangle, Theme};
+use rfd::AsyncFileDialog;
+
+use crate::error::Error;
+use crate::formats::csv::{self, CsvTable};
+use crate::formats::dot::{self, DotConvention};
+use crate::formats::image::{self, ImageArea, ImageFormat};
+use crate::formats::jsonl::{self, TypeFilter};
+use crate::formats::pcap::{self, PcapWeight};
+use crate::formats::report::{self, ReportFormat};
+use crate::formats::{misp, stix, zone, Format};
+use crate::registry::Registry;
+use crate::views::graph::analytics::AnalyticsMessage;
+use crate::views::graph::csv_import::CsvImport;
+use crate::views::graph::geo::GeoMessage;
+use crate::views::graph::paste::PasteMessage;
+use crate::views::graph::paths::PathMessage;
+use crate::views::graph::query::QueryMessage;
+use crate::views::graph::search;
+use crate::views::graph::types::TypesMessage;
+use crate::views::graph::{GraphState, VisualGraph};
+use crate::Message;
+
+/// Messages for importing and exporting investigations.
+#[derive(Debug, Clone)]
+pub enum FileMessage {
+    FormatSelected(Format),
+    DotPrefixChanged(String),
+    TypeFilterSelected(TypeFilter),
+    PcapWeightSelected(PcapWeight),
+    Import,
+    /// The imported graph together with what the user should know about it,
+    /// e.g. how many records were skipped.
+    Imported(Format, Result<(VisualGraph, Option<String>), Error>),
+    Export,
+    ExportSelectionToggled(bool),
+    Exported(Result<(), Error>),
+    CsvImport,
+    CsvLoaded(Result<CsvTable, Error>),
+    CsvExport,
+    ImageAreaSelected(ImageArea),
+    ExportImage(ImageFormat),
+    ReportFormatSelected(ReportFormat),
+    LoadReportTemplate,
+    ReportTemplateLoaded(Result<String, Error>),
+    ExportReport,
+}
+
+/// The buttons for importing and exporting above the canvas.
+#[must_use]
+pub fn toolbar(state: &GraphState) -> Column<'_, Message, Theme, iced::Renderer> {
+    let mut exchange = row!(
+        pick_list(&Format::ALL[..], Some(state.format), |f| {
+            Message::File(FileMessage::FormatSelected(f))
+        }),
+        button("Import").on_press(Message::File(FileMessage::Import)),
+        button("Export").on_press_maybe(
+            state
+                .format
+                .exports()
+                .then_some(Message::File(FileMessage::Export))
+        ),
+        checkbox("Only selected", state.export_selection)
+            .on_toggle(|s| Message::File(FileMessage::ExportSelectionToggled(s))),
+        button("Import CSV").on_press(Message::File(FileMessage::CsvImport)),
+        button("Export CSV").on_press(Message::File(FileMessage::CsvExport)),
+        button("Paste text").on_press(Message::Paste(PasteMessage::Open)),
+        button("Merge duplicates").on_press(Message::MergeDuplicates),
+        button("Find connections").on_press(Message::Paths(PathMessage::Open)),
+        button("Analytics").on_press(Message::Analytics(AnalyticsMessage::Open)),
+        button("Query").on_press(Message::Query(QueryMessage::Open)),
+        button("Entity types").on_press(Message::Types(TypesMessage::Open)),
+        button("Load GeoIP databases").on_press(Message::Geo(GeoMessage::Load)),
+        button("Geolocate IPs").on_press_maybe(
+            (!state.geo_databases.is_empty()).then_some(Message::Geo(GeoMessage::Enrich))
+        ),
+    )
+    .spacing(10)
+    .align_items(Alignment::Center);
+    if state.format == Format::Dot {
+        exchange = exchange.push(
+            text_input("Attribute prefix", &state.dot_convention.prefix)
+                .on_input(|p| Message::File(FileMessage::DotPrefixChanged(p)))
+                .width(150),
+        );
+    }
+    if state.format == Format::JsonLines {
+        exchange = exchange.push(pick_list(
+            TypeFilter::options(&state.graph),
+            Some(state.type_filter.clone()),
+            |f| Message::File(FileMessage::TypeFilterSelected(f)),
+        ));
+    }
+    if state.format == Format::Pcap {
+        exchange = exchange.push(pick_list(
+            &PcapWeight::ALL[..],
+            Some(state.pcap_weight),
+            |w| Message::File(FileMessage::PcapWeightSelected(w)),
+        ));
+    }
+
+    let reporting = row!(
+        pick_list(&ImageArea::ALL[..], Some(state.image_area), |a| {
+            Message::File(FileMessage::ImageAreaSelected(a))
+        }),
+        button("Export SVG").on_press(Message::File(FileMessage::ExportImage(ImageFormat::Svg))),
+        button("Export PNG").on_press(Message::File(FileMessage::ExportImage(ImageFormat::Png))),
+        pick_list(&ReportFormat::ALL[..], Some(state.report_format), |f| {
+            Message::File(FileMessage::ReportFormatSelected(f))
+        }),
+        button("Load template").on_press(Message::File(FileMessage::LoadReportTemplate)),
+        button("Write report").on_press(Message::File(FileMessage::ExportReport)),
+        text(state.status.as_deref().unwrap_or_default()),
+    )
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    column!(exchange, reporting, search::bar(&state.search))
+        .spacing(5)
+        .padding(5)
+}
+
+pub fn update(state: &mut GraphState, message: FileMessage) -> Command<Message> {
+    match message {
+        FileMessage::FormatSelected(format) => state.format = format,
+        FileMessage::DotPrefixChanged(prefix) => state.dot_convention.prefix = prefix,
+        FileMessage::TypeFilterSelected(filter) => state.type_filter = filter,
+        FileMessage::PcapWeightSelected(weight) => state.pcap_weight = weight,
+        FileMessage::Import => {
+            let format = state.format;
+            let import = import_file(format, state.dot_convention.clone(), state.pcap_weight);
+            return Command::perform(import, move |result| {
+                Message::File(FileMessage::Imported(format, result))
+            });
+        }
+        FileMessage::Imported(format, result) => apply_import(state, format, result),
+        FileMessage::Export => {
+            let mut graph = if state.export_selection {
+                state.graph.selection()
+            } else {
+                state.graph.clone()
+            };
+            if state.format == Format::JsonLines {
+                graph = graph.filter(|n| state.type_filter.keeps(&n.node));
+            }
+            return Command::perform(export_file(state.format, graph), |result| {
+                Message::File(FileMessage::Exported(result))
+            });
+        }
+        FileMessage::ExportSelectionToggled(selection) => state.export_selection = selection,
+        FileMessage::Exported(result) => state.status = result.err().map(|e| e.to_string()),
+        FileMessage::CsvImport => {
+            return Command::perform(read_csv_file(), |result| {
+                Message::File(FileMessage::CsvLoaded(result))
+            });
+        }
+        FileMessage::CsvLoaded(result) => match result {
+            Ok(table) => {
+                state.csv_import = Some(CsvImport::new(table));
+                state.status = None;
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        FileMessage::CsvExport => {
+            return Command::perform(export_csv_files(state.graph.clone()), |result| {
+                Message::File(FileMessage::Exported(result))
+            });
+        }
+        FileMessage::ImageAreaSelected(area) => state.image_area = area,
+        FileMessage::ExportImage(format) => {
+            let area = match state.image_area {
+                ImageArea::Graph => image::graph_bounds(&state.graph, state.zoom_factor),
+                ImageArea::Viewport => Rectangle::new(Point::ORIGIN, state.viewport),
+            };
+            let data = image::export(
+                &state.graph,
+                &state.entity_types,
+                state.zoom_factor,
+                area,
+                format,
+            );
+            return Command::perform(save_image(format, data), |result| {
+                Message::File(FileMessage::Exported(result))
+            });
+        }
+        FileMessage::ReportFormatSelected(format) => {
+            // templates are written for one format
+            state.report_format = format;
+            state.report_template = None;
+        }
+        FileMessage::LoadReportTemplate => {
+            return Command::perform(load_template(state.report_format), |result| {
+                Message::File(FileMessage::ReportTemplateLoaded(result))
+            });
+        }
+        FileMessage::ReportTemplateLoaded(result) => match result {
+            Ok(template) => {
+                state.report_template = Some(template);
+                state.status = None;
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        FileMessage::ExportReport => {
+            let template = state
+                .report_template
+                .clone()
+                .unwrap_or_else(|| state.report_format.default_template().to_string());
+            let report = export_report(
+                state.graph.clone(),
+                state.entity_types.clone(),
+                state.zoom_factor,
+                state.report_format,
+                template,
+            );
+            return Command::perform(report, |result| {
+                Message::File(FileMessage::Exported(result))
+            });
+        }
+    }
+    Command::none()
+}
+
+/// Let the user pick a report template.
+async fn load_template(format: ReportFormat) -> Result<String, Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    String::from_utf8(file.read().await)
+        .map_err(|_| Error::InvalidFormat("the template isn't valid UTF-8".to_string()))
+}
+
+/// Let the user pick where to save a report and write it there.
+async fn export_report(
+    graph: VisualGraph,
+    types: Registry,
+    zoom_factor: f32,
+    format: ReportFormat,
+    template: String,
+) -> Result<(), Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(format!("report.{}", format.extension()))
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    let path = file.path();
+    let name = path
+        .file_stem()
+        .map_or("report".into(), |stem| stem.to_string_lossy());
+
+    for (name, data) in report::render(&graph, &types, zoom_factor, format, &template, &name)? {
+        std::fs::write(path.with_file_name(name), data)?;
+    }
+    Ok(())
+}
+
+/// Let the user pick a file and save a rendered image to it.
+async fn save_image(format: ImageFormat, data: Result<Vec<u8>, Error>) -> Result<(), Error> {
+    let data = data?;
+    let file = AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(format!("investigation.{}", format.extension()))
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    file.write(&data).await?;
+    Ok(())
+}
+
+/// Let the user pick a CSV file and read it.
+async fn read_csv_file() -> Result<CsvTable, Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter("CSV", &["csv"])
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    csv::read(&file.read().await)
+}
+
+/// Let the user pick a folder and write one CSV file per entity type into it.
+async fn export_csv_files(graph: VisualGraph) -> Result<(), Error> {
+    let files = csv::export(&graph)?;
+    let folder = AsyncFileDialog::new()
+        .pick_folder()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    for (name, data) in files {
+        std::fs::write(folder.path().join(name), data)?;
+    }
+    Ok(())
+}
+
+/// Put an imported graph into the view or show why importing failed.
+fn apply_import(
+    state: &mut GraphState,
+    format: Format,
+    result: Result<(VisualGraph, Option<String>), Error>,
+) {
+    match result {
+        Ok((graph, note)) => {
+            // records schnuffel can't represent yet are kept, but the user should know
+            state.status = if format == Format::Zone {
+                zone::unsupported(&graph).map(|records| {
+                    format!("kept unsupported records as generic entities: {records}")
+                })
+            } else {
+                note
+            };
+            if format.updates() {
+                state.graph.update(graph);
+            } else if format.merges() {
+                state.graph.merge(graph);
+            } else {
+                state.graph = graph;
+            }
+        }
+        Err(e) => state.status = Some(e.to_string()),
+    }
+}
+
+/// Let the user pick a file and import it, returning the graph and what the
+/// user should know about it.
+async fn import_file(
+    format: Format,
+    convention: DotConvention,
+    weight: PcapWeight,
+) -> Result<(VisualGraph, Option<String>), Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter(format.to_string(), format.extensions())
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    let graph = match format {
+        // streams can be too large to read at once
+        Format::JsonLines => jsonl::read(BufReader::new(File::open(file.path())?)),
+        Format::Dot => dot::import_with(&file.read().await, &convention),
+        Format::Pcap => pcap::import_with(&file.read().await, weight),
+        // real events often have some values schnuffel can't read
+        Format::Misp => {
+            let (graph, skipped) = misp::import_counting(&file.read().await)?;
+            let note = (skipped > 0).then(|| {
+                format!("skipped {skipped} MISP attributes and objects with malformed values")
+            });
+            return Ok((graph, note));
+        }
+        Format::Stix => {
+            let (graph, skipped) = stix::import_counting(&file.read().await)?;
+            let note = (skipped > 0)
+                .then(|| format!("skipped {skipped} STIX objects with malformed values"));
+            return Ok((graph, note));
+        }
+        _ => format.import(&file.read().await),
+    };
+    Ok((graph?, None))
+}
+
+/// Let the user pick a file and export the graph to it.
+///
+/// # Errors
+///
+/// Returns an error if no file was picked or writing it fails.
+pub async fn export_file(format: Format, graph: VisualGraph) -> Result<(), Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(format!("investigation.{}", format.extension()))
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    if format == Format::JsonLines {
+        return jsonl::write(&graph, BufWriter::new(File::create(file.path())?));
+    }
+    file.write(&format.export(&graph)?).await?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use iced::Command;
+use rfd::AsyncFileDialog;
+
+use crate::error::Error;
+use crate::geo::{self, GeoDatabase};
+use crate::views::graph::GraphState;
+use crate::Message;
+
+/// Messages for geolocating IPs with local databases.
+#[derive(Debug, Clone)]
+pub enum GeoMessage {
+    Load,
+    Loaded(Result<Vec<GeoDatabase>, Error>),
+    Enrich,
+}
+
+pub fn update(state: &mut GraphState, message: GeoMessage) -> Command<Message> {
+    match message {
+        GeoMessage::Load => {
+            return Command::perform(load_databases(), |result| {
+                Message::Geo(GeoMessage::Loaded(result))
+            });
+        }
+        GeoMessage::Loaded(result) => match result {
+            Ok(databases) => {
+                let names: Vec<&str> = databases.iter().map(|d| d.name.as_str()).collect();
+                state.status = Some(format!("loaded {}", names.join(", ")));
+                state.geo_databases = databases;
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        GeoMessage::Enrich => {
+            let found = geo::enrich(&mut state.graph, &state.geo_databases);
+            state.status = Some(format!("geolocated {found} IPs"));
+        }
+    }
+    Command::none()
+}
+
+/// Let the user pick `MaxMind` databases, e.g. a city and an ASN database, and
+/// read them.
+async fn load_databases() -> Result<Vec<GeoDatabase>, Error> {
+    let files = AsyncFileDialog::new()
+        .add_filter("MaxMind database", &["mmdb"])
+        .pick_files()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    let mut databases = Vec::new();
+    for file in files {
+        databases.push(GeoDatabase::read(file.read().await)?);
+    }
+    Ok(databases)
+}
@@ -1,27 +1,34 @@
-use crate::views::graph::{constants, GraphState, GraphStateUpdate};
+use std::collections::HashSet;
+
+use crate::entity;
+use crate::views::graph::{
+    analytics, annotations, constants, csv_import, files, geo, paste, paths, query, search, types,
+    GraphState, GraphStateUpdate,
+};
 use crate::views::ViewState;
 use crate::Message;
 use iced::mouse::ScrollDelta;
 use iced::widget::{column, text, Column};
-use iced::{Element, Theme};
-use schnuffel_types::graph::{DNSRecord, Node};
+use iced::{Command, Element, Point, Theme, Vector};
 
-pub fn update_graph(state: &mut GraphState, message: Message) {
+pub fn update_graph(state: &mut GraphState, message: Message) -> Command<Message> {
+    let mut command = Command::none();
+    // the search and the time range have to find the entities these add,
+    // remove or annotate
+    let changes_entities = matches!(
+        message,
+        Message::File(_)
+            | Message::Csv(_)
+            | Message::Paste(_)
+            | Message::MergeDuplicates
+            | Message::Annotation(_)
+            | Message::Types(_)
+            | Message::Geo(_)
+    );
     match message {
-        Message::MouseClick(position) => {
-            for node in &mut state.graph.nodes {
-                // clear selections
-                node.is_selected = false;
-
-                if (position.x - node.x).powf(2.0) + (position.y - node.y).powf(2.0)
-                    < (node.radius * state.zoom_factor).powf(2.0)
-                {
-                    node.is_dragged = true;
-                    node.is_selected = true;
-                }
-            }
-        }
+        Message::MouseClick(position) => click(state, position),
         Message::MouseDrag(position) => {
+            state.cursor_position = position;
             for node in &mut state.graph.nodes {
                 if node.is_dragged {
                     node.x = position.x;
@@ -45,23 +52,19 @@ pub fn update_graph(state: &mut GraphState, message: Message) {
             }
         }
         Message::MouseScroll(delta) => {
-            if let ScrollDelta::Lines { x: _, y } = delta {
-                state.zoom_factor += y * constants::ZOOM_MULTIPLIER;
-                state.zoom_factor = state
-                    .zoom_factor
-                    .clamp(constants::MIN_ZOOM, constants::MAX_ZOOM);
-
-                for node in &mut state.graph.nodes {
-                    // update the node positions to reflect the zoom
-                    if y < 0.0 && state.zoom_factor != constants::MIN_ZOOM {
-                        // zoom out
-                        node.x += state.zoom_factor * constants::NODE_ZOOM_POSITION_CHANGE;
-                        node.y += state.zoom_factor * constants::NODE_ZOOM_POSITION_CHANGE;
-                    } else if y > 0.0 && state.zoom_factor != constants::MAX_ZOOM {
-                        // zoom in
-                        node.x -= state.zoom_factor * constants::NODE_ZOOM_POSITION_CHANGE;
-                        node.y -= state.zoom_factor * constants::NODE_ZOOM_POSITION_CHANGE;
-                    }
+            let cursor = state.cursor_position;
+            match delta {
+                // mouse wheels always zoom
+                ScrollDelta::Lines { y, .. } => {
+                    zoom_at(state, cursor, y * constants::ZOOM_MULTIPLIER);
+                }
+                // ctrl + scroll on a trackpad (also sent by many trackpads for pinching)
+                ScrollDelta::Pixels { y, .. } if state.modifiers.control() => {
+                    zoom_at(state, cursor, y * constants::PIXEL_ZOOM_SENSITIVITY);
+                }
+                // two finger scroll on a trackpad
+                ScrollDelta::Pixels { x, y } => {
+                    pan_by(state, Vector::new(x, y) * constants::SCROLL_PAN_MULTIPLIER);
                 }
             }
         }
@@ -69,104 +72,155 @@ pub fn update_graph(state: &mut GraphState, message: Message) {
             state.is_panning = true;
             state.panning_start_point = position;
         }
-    };
+        Message::ModifiersChanged(modifiers) => state.modifiers = modifiers,
+        Message::CanvasResized(size) => state.viewport = size,
+        Message::FingerPressed(finger, position) => {
+            state.fingers.insert(finger, position);
+        }
+        Message::FingerMoved(finger, position) => {
+            let old_spread = finger_spread(state);
+            state.fingers.insert(finger, position);
+
+            // pinching needs exactly two fingers
+            if let (Some((old_distance, _)), Some((new_distance, center))) =
+                (old_spread, finger_spread(state))
+            {
+                zoom_at(
+                    state,
+                    center,
+                    (new_distance - old_distance) * constants::PINCH_ZOOM_SENSITIVITY,
+                );
+            }
+        }
+        Message::FingerLifted(finger) => {
+            state.fingers.remove(&finger);
+        }
+        Message::File(message) => command = files::update(state, message),
+        Message::Csv(message) => csv_import::update(state, message),
+        Message::Paste(message) => command = paste::update(state, message),
+        Message::MergeDuplicates => {
+            let merged = state.graph.merge_duplicates();
+            state.status = Some(format!("merged {merged} duplicate entities"));
+        }
+        Message::Paths(message) => paths::update(state, message),
+        Message::Analytics(message) => command = analytics::update(state, message),
+        Message::Query(message) => command = query::update(state, message),
+        Message::Search(message) => search::update(state, message),
+        Message::Annotation(message) => annotations::update(state, message),
+        Message::Types(message) => command = types::update(state, message),
+        Message::Geo(message) => command = geo::update(state, message),
+        // the app switches the views and updates the other views
+        Message::SwitchView(_) | Message::Timeline(_) | Message::Table(_) | Message::Map(_) => {}
+    }
+    if changes_entities {
+        refresh_filters(state);
+    }
+    state.annotation_editor.sync(&state.graph);
     state.update_state(GraphStateUpdate {
         graph: state.graph.clone(),
         zoom_factor: state.zoom_factor,
     });
+    command
+}
+
+/// Update what depends on the entities after another view changed them, e.g.
+/// the table.
+pub fn entities_changed(state: &mut GraphState) {
+    refresh_filters(state);
+    state.annotation_editor.sync(&state.graph);
+    state.graph_cache.clear();
+}
+
+/// Find the entities matching the search and the ones outside of the time
+/// range again.
+fn refresh_filters(state: &mut GraphState) {
+    if state.search.is_active() {
+        state.search.refresh(&state.graph);
+    }
+    if state.time_range.is_some() {
+        state.refresh_time_range();
+    }
+}
+
+/// Select the node at a position and start dragging it.
+fn click(state: &mut GraphState, position: Point) {
+    // shift adds to the selection, e.g. to find connections between nodes
+    let adds_to_selection = state.modifiers.shift();
+    // hidden nodes can't be clicked
+    let hidden: HashSet<usize> = state
+        .graph
+        .nodes
+        .iter()
+        .map(|node| node.id)
+        .filter(|id| state.is_hidden(*id))
+        .collect();
+    for node in &mut state.graph.nodes {
+        if !adds_to_selection {
+            node.is_selected = false;
+        }
+
+        if !hidden.contains(&node.id)
+            && (position.x - node.x).powf(2.0) + (position.y - node.y).powf(2.0)
+                < (node.radius * state.zoom_factor).powf(2.0)
+        {
+            node.is_dragged = true;
+            node.is_selected = true;
+        }
+    }
+}
+
+/// Zoom the graph around `center` so that the point under it stays in place.
+///
+/// `amount` is applied exponentially, which makes many small zoom steps (like the
+/// ones sent by trackpads) feel the same as a few large ones (like the ones sent by
+/// mouse wheels).
+fn zoom_at(state: &mut GraphState, center: Point, amount: f32) {
+    let old_zoom = state.zoom_factor;
+    state.zoom_factor = (old_zoom * amount.exp()).clamp(constants::MIN_ZOOM, constants::MAX_ZOOM);
+
+    let scale = state.zoom_factor / old_zoom;
+    for node in &mut state.graph.nodes {
+        node.x = center.x + (node.x - center.x) * scale;
+        node.y = center.y + (node.y - center.y) * scale;
+    }
+}
+
+/// Move every node of the graph by `delta`.
+pub fn pan_by(state: &mut GraphState, delta: Vector) {
+    for node in &mut state.graph.nodes {
+        node.x += delta.x;
+        node.y += delta.y;
+    }
+}
+
+/// The distance between and the center of the two fingers touching the canvas.
+///
+/// Returns `None` if not exactly two fingers touch the canvas.
+fn finger_spread(state: &GraphState) -> Option<(f32, Point)> {
+    let mut fingers = state.fingers.values();
+    match (fingers.next(), fingers.next(), fingers.next()) {
+        (Some(a), Some(b), None) => Some((
+            a.distance(*b),
+            Point::new(f32::midpoint(a.x, b.x), f32::midpoint(a.y, b.y)),
+        )),
+        _ => None,
+    }
 }
 
 pub fn build_info_column(state: &GraphState) -> Column<Message, Theme, iced::Renderer> {
     match state.graph.nodes.iter().find(|n| n.is_selected) {
-        Some(node) => match &node.node {
-            Node::SocialMedia {
-                social_media_url,
-                account_url,
-            } => {
-                column!(
-                    text("Type: Social Media"),
-                    text(format!("Network URL: {}", social_media_url.as_str())),
-                    text(format!("Account URL: {}", account_url.as_str())),
-                )
-            }
-            Node::IP(ip) => {
-                column!(text("Type: IP Address"), text(format!("IP: {}", ip)))
-            }
-            Node::Person(name) => {
-                column!(text("Type: Person"), text(format!("Name: {name}")))
+        Some(node) => {
+            let mut info: Vec<Element<'_, Message, Theme, iced::Renderer>> = vec![text(format!(
+                "Type: {}",
+                state.entity_types.display_name(&node.node)
+            ))
+            .into()];
+            for (name, value) in entity::fields(&node.node) {
+                info.push(text(format!("{name}: {value}")).into());
             }
-            Node::Domain(domain) => {
-                column!(
-                    text("Type: Domain"),
-                    text(format!("domain: {}", domain.domain))
-                )
-            }
-            Node::Website { url } => {
-                column!(text("Type: Website"), text(format!("URL: {url}")))
-            }
-            Node::DNSEntry { nameserver, record } => {
-                let mut entry: Vec<Element<'_, Message, Theme, iced::Renderer>> = vec![
-                    text("Type: DNS Entry").into(),
-                    text(format!("NS: {}", nameserver.domain)).into(),
-                ];
-                let mut record: Vec<Element<'_, Message, Theme, iced::Renderer>> = match record {
-                    DNSRecord::A(addr) => {
-                        vec![
-                            text("Record Type: A").into(),
-                            text(format!("Address: {addr}")).into(),
-                        ]
-                    }
-                    DNSRecord::MX(domain) => vec![
-                        text("Record Type: MX").into(),
-                        text(format!("Domain: {}", domain.domain)).into(),
-                    ],
-                    DNSRecord::TXT(txt) => {
-                        vec![
-                            text("Record Type: TXT").into(),
-                            text(format!("Text: {txt}")).into(),
-                        ]
-                    }
-                    DNSRecord::AAAA(addr) => {
-                        vec![
-                            text("Record Type: AAAA").into(),
-                            text(format!("Address: {addr}")).into(),
-                        ]
-                    }
-                    DNSRecord::SRV {
-                        service,
-                        protocol,
-                        from,
-                        to,
-                        to_port,
-                    } => vec![
-                        text("Record Type: SRV").into(),
-                        text(format!("Service: {service}")).into(),
-                        text(format!("Protocol: {protocol}")).into(),
-                        text(format!("From: {from}")).into(),
-                        text(format!("To: {to}")).into(),
-                        text(format!("To port: {to_port}")).into(),
-                    ],
-                    DNSRecord::CNAME { from, to } => vec![
-                        text("Record Type: CNAME").into(),
-                        text(format!("From: {from}")).into(),
-                        text(format!("To: {}", to.domain)).into(),
-                    ],
-                };
-                entry.append(&mut record);
-                Column::from_vec(entry)
-            }
-            Node::PhoneNumber(number) => column!(
-                text("Type: Phone Number"),
-                text(format!("Number: {}", number.number))
-            ),
-            Node::EmailAddress(email) => column!(
-                text("Type: Email Address"),
-                text(format!("Address: {}", email.email))
-            ),
-            Node::Organization(org) => {
-                column!(text("Type: Organization"), text(format!("Name: {org}")))
-            }
-        },
+            info.push(annotations::view(&state.annotation_editor, node).into());
+            Column::from_vec(info).spacing(5)
+        }
         None => column!().padding(10),
     }
 }
@@ -1,38 +1,95 @@
+pub mod analytics;
+pub mod annotations;
 pub mod constants;
+pub mod csv_import;
+pub mod files;
+pub mod geo;
 pub mod helpers;
+pub mod paste;
+pub mod paths;
+pub mod query;
+pub mod search;
+pub mod types;
 
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+
+use crate::analysis::timeline::{self, TimeRange};
+use crate::annotation::{Annotations, Provenance};
+use crate::entity::{self, Entity};
+use crate::formats::dot::DotConvention;
+use crate::formats::image::ImageArea;
+use crate::formats::jsonl::TypeFilter;
+use crate::formats::pcap::PcapWeight;
+use crate::formats::report::ReportFormat;
+use crate::formats::Format;
+use crate::geo::GeoDatabase;
+use crate::registry::Registry;
 use crate::Message;
-use iced::widget::row;
+use chrono::{DateTime, Utc};
+use iced::alignment::{Horizontal, Vertical};
+use iced::keyboard::{self, Modifiers};
+use iced::touch::{self, Finger};
 use iced::widget::scrollable::{Direction, Properties};
+use iced::widget::{column, row};
 use iced::{mouse, Theme};
 use iced::{
     widget::canvas::{
         self,
         stroke::{self, Stroke},
-        Cache, Canvas, Path, Program,
+        Cache, Canvas, Frame, Path, Program, Text,
     },
     Element,
 };
-use iced::{Color, Point, Rectangle};
+use iced::{Color, Point, Rectangle, Size};
 use schnuffel_types::graph::{DNSRecord, Domain, Node};
 
 use super::ViewState;
+use analytics::Analytics;
+use annotations::AnnotationEditor;
+use csv_import::CsvImport;
 use helpers::build_info_column;
+use paste::PasteImport;
+use paths::PathFinder;
+use query::{QueryPanel, SavedQuery};
+use search::Search;
+use types::TypesPanel;
 
 pub fn view(state: &GraphState) -> Element<'_, Message, Theme, iced::Renderer> {
-    iced::widget::responsive(move |size| {
+    let toolbar = files::toolbar(state);
+
+    let content = iced::widget::responsive(move |size| {
         row!(
             Canvas::new(state)
                 .width((size.width / 3.0) * 2.0) // 2/3 of the space belong to the canvas
                 .height(size.height),
-            iced::widget::scrollable(build_info_column(state))
-                .width(size.width / 3.0) // 1/3 of the space belongs to the node info
-                .height(size.height)
-                .direction(Direction::Vertical(Properties::default()))
+            iced::widget::scrollable(if let Some(import) = &state.csv_import {
+                csv_import::view(import)
+            } else if let Some(import) = &state.paste_import {
+                paste::view(import)
+            } else if let Some(finder) = &state.path_finder {
+                paths::view(finder, state)
+            } else if let Some(panel) = &state.query_panel {
+                query::view(panel, state)
+            } else if let Some(analytics) = &state.analytics {
+                analytics::view(analytics, state)
+            } else if let Some(panel) = &state.types_panel {
+                types::view(panel, state)
+            } else {
+                column!(
+                    search::results(&state.search, &state.graph),
+                    build_info_column(state)
+                )
+            })
+            .width(size.width / 3.0) // 1/3 of the space belongs to the node info
+            .height(size.height)
+            .direction(Direction::Vertical(Properties::default()))
         )
         .into()
-    })
-    .into()
+    });
+
+    column!(toolbar, content).into()
 }
 
 #[derive(Debug)]
@@ -42,6 +99,58 @@ pub struct GraphState {
     pub zoom_factor: f32,
     pub is_panning: bool,
     pub panning_start_point: Point,
+    pub cursor_position: Point,
+    pub modifiers: Modifiers,
+    pub fingers: HashMap<Finger, Point>,
+    /// The format used when importing or exporting.
+    pub format: Format,
+    /// How nodes of imported DOT files become entities.
+    pub dot_convention: DotConvention,
+    /// Which entities are exported as JSON Lines.
+    pub type_filter: TypeFilter,
+    /// What the edges of imported packet captures are weighted by.
+    pub pcap_weight: PcapWeight,
+    /// Whether only the selected nodes are exported.
+    pub export_selection: bool,
+    /// Which part of the canvas is exported as an image.
+    pub image_area: ImageArea,
+    /// The size of the canvas.
+    pub viewport: Size,
+    /// The format reports are written in.
+    pub report_format: ReportFormat,
+    /// The template loaded by the user, `None` to use the default one.
+    pub report_template: Option<String>,
+    /// The CSV import wizard, if it is open.
+    pub csv_import: Option<CsvImport>,
+    /// The dialog for finding entities in text, if it is open.
+    pub paste_import: Option<PasteImport>,
+    /// The panel for finding connections between entities, if it is open.
+    pub path_finder: Option<PathFinder>,
+    /// The panel showing how central the entities are, if it is open.
+    pub analytics: Option<Analytics>,
+    /// The panel for querying the investigation, if it is open.
+    pub query_panel: Option<QueryPanel>,
+    /// The queries the user saved for reuse.
+    pub saved_queries: Vec<SavedQuery>,
+    /// The ids of the nodes shown on the canvas, `None` to show all.
+    pub visible: Option<HashSet<usize>>,
+    /// The search bar and the entities it found.
+    pub search: Search,
+    /// The fields for annotating the selected entity.
+    pub annotation_editor: AnnotationEditor,
+    /// The custom entity types of the investigation.
+    pub entity_types: Registry,
+    /// The panel for defining entity types, if it is open.
+    pub types_panel: Option<TypesPanel>,
+    /// The time range brushed on the timeline, `None` to show all times.
+    pub time_range: Option<TimeRange>,
+    /// The ids of the nodes whose timestamps are all outside of the time range.
+    pub out_of_range: HashSet<usize>,
+    /// The databases IPs are geolocated with.
+    pub geo_databases: Vec<GeoDatabase>,
+    /// What happened on the last action that needs explaining, e.g. a failed
+    /// import.
+    pub status: Option<String>,
 }
 
 impl Default for GraphState {
@@ -52,6 +161,108 @@ impl Default for GraphState {
             zoom_factor: 1.0,
             is_panning: false,
             panning_start_point: Point::default(),
+            cursor_position: Point::default(),
+            modifiers: Modifiers::default(),
+            fingers: HashMap::new(),
+            format: Format::GraphML,
+            dot_convention: DotConvention::default(),
+            type_filter: TypeFilter::All,
+            pcap_weight: PcapWeight::default(),
+            export_selection: false,
+            image_area: ImageArea::Graph,
+            viewport: Size::ZERO,
+            report_format: ReportFormat::Html,
+            report_template: None,
+            csv_import: None,
+            paste_import: None,
+            path_finder: None,
+            analytics: None,
+            query_panel: None,
+            saved_queries: Vec::new(),
+            visible: None,
+            search: Search::default(),
+            annotation_editor: AnnotationEditor::default(),
+            entity_types: Registry::default(),
+            types_panel: None,
+            time_range: None,
+            out_of_range: HashSet::new(),
+            geo_databases: Vec::new(),
+            status: None,
+        }
+    }
+}
+
+impl GraphState {
+    /// Whether a node isn't drawn, because it isn't a result of the query or
+    /// the search that filters the canvas, or it happened outside of the time
+    /// range.
+    #[must_use]
+    pub fn is_hidden(&self, id: usize) -> bool {
+        !query::is_visible(self.visible.as_ref(), id)
+            || self.search.hides(id)
+            || self.out_of_range.contains(&id)
+    }
+
+    /// Whether an edge isn't drawn, because one of its nodes is hidden or it
+    /// was observed outside of the time range.
+    #[must_use]
+    pub fn is_edge_hidden(&self, edge: &VisualEdge) -> bool {
+        self.is_hidden(edge.from)
+            || self.is_hidden(edge.to)
+            || self
+                .time_range
+                .is_some_and(|range| timeline::is_edge_out_of_range(edge, range))
+    }
+
+    /// Only show what happened in a time range, `None` to show all times.
+    pub fn set_time_range(&mut self, range: Option<TimeRange>) {
+        self.time_range = range;
+        self.refresh_time_range();
+    }
+
+    /// Find the nodes outside of the time range again, e.g. after entities
+    /// were added.
+    pub fn refresh_time_range(&mut self) {
+        self.out_of_range = self
+            .time_range
+            .map(|range| timeline::out_of_range(&self.graph, range))
+            .unwrap_or_default();
+        self.graph_cache.clear();
+    }
+
+    /// Draw the icon of the custom type and the ring in the color of the first
+    /// tag of a node on top of its circle.
+    fn draw_marks(&self, frame: &mut Frame, node: &VisualNode, circle: &Path, alpha: f32) {
+        if let Some(entity_type) = self
+            .entity_types
+            .type_of(&node.node)
+            .filter(|t| !t.icon.is_empty())
+        {
+            frame.fill_text(Text {
+                content: entity_type.icon.clone(),
+                position: Point::new(node.x, node.y),
+                color: Color {
+                    a: alpha,
+                    ..Color::WHITE
+                },
+                size: (node.radius * self.zoom_factor * constants::NODE_ZOOM_SCALING).into(),
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Center,
+                ..Text::default()
+            });
+        }
+
+        if let Some(tag) = node.annotations.tags.first() {
+            let mut tag_color = annotations::tag_color(tag);
+            tag_color.a = alpha;
+            frame.stroke(
+                circle,
+                Stroke {
+                    style: stroke::Style::Solid(tag_color),
+                    width: constants::TAG_RING_WIDTH * self.zoom_factor,
+                    ..Stroke::default()
+                },
+            );
         }
     }
 }
@@ -73,25 +284,62 @@ impl ViewState for GraphState {
 
 #[derive(Debug, Clone)]
 pub struct VisualNode {
-    pub node: Node,
+    pub node: Entity,
     pub id: usize,
     pub x: f32,
     pub y: f32,
     pub radius: f32,
     pub is_dragged: bool,
     pub is_selected: bool,
+    /// What the analysts noted about the entity.
+    pub annotations: Annotations,
+    /// The plugin that found the entity, `None` if it was added otherwise.
+    pub provenance: Option<Provenance>,
 }
 
 impl Default for VisualNode {
     fn default() -> Self {
         Self {
-            node: Node::Person("Foo Bar".to_string()),
+            node: Node::Person("Foo Bar".to_string()).into(),
             id: 0,
             x: 0.0,
             y: 0.0,
             radius: 10.0,
             is_dragged: false,
             is_selected: false,
+            annotations: Annotations::default(),
+            provenance: None,
+        }
+    }
+}
+
+/// What the relationship between two nodes means.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    #[default]
+    Related,
+    ResolvesTo,
+    Custom(String),
+}
+
+impl EdgeKind {
+    /// The name of the kind, used as the edge label and in exports.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            EdgeKind::Related => "related",
+            EdgeKind::ResolvesTo => "resolves-to",
+            EdgeKind::Custom(name) => name,
+        }
+    }
+
+    /// Get the kind with the given name, treating unknown names as custom kinds.
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "related" => EdgeKind::Related,
+            "resolves-to" => EdgeKind::ResolvesTo,
+            custom => EdgeKind::Custom(custom.to_string()),
         }
     }
 }
@@ -100,18 +348,374 @@ impl Default for VisualNode {
 pub struct VisualEdge {
     pub from: usize,
     pub to: usize,
+    pub kind: EdgeKind,
+    /// How strong the relationship is, e.g. how many packets were exchanged.
+    pub weight: Option<f64>,
+    /// When the relationship was observed, e.g. when an email was sent.
+    pub time: Option<DateTime<Utc>>,
+    /// The plugin that found the relationship, `None` if it was added otherwise.
+    pub provenance: Option<Provenance>,
 }
 
+/// The entities of an investigation and their relationships.
+///
+/// Nodes are only added and removed through the methods of the graph, which
+/// keep the index of the entities up to date.
 #[derive(Debug, Clone)]
 pub struct VisualGraph {
     pub nodes: Vec<VisualNode>,
     pub edges: Vec<VisualEdge>,
+    /// The position in `nodes` of the node of every entity by its
+    /// [`entity::identity_key`], which includes the type of the entity.
+    index: HashMap<String, usize>,
+    /// The position in `nodes` of the node with every id.
+    positions: HashMap<usize, usize>,
+    /// The id the next added node gets.
+    next_id: usize,
+}
+
+impl VisualGraph {
+    /// A graph of nodes and the edges between them.
+    #[must_use]
+    pub fn new(nodes: Vec<VisualNode>, edges: Vec<VisualEdge>) -> Self {
+        let mut graph = Self {
+            nodes,
+            edges,
+            index: HashMap::new(),
+            positions: HashMap::new(),
+            next_id: 0,
+        };
+        graph.reindex();
+        graph
+    }
+
+    /// A graph without any nodes.
+    #[must_use]
+    pub fn empty() -> Self {
+        Self::new(Vec::new(), Vec::new())
+    }
+
+    /// Index the nodes again after they moved in `nodes`. The first node of
+    /// every entity is the one that is found.
+    fn reindex(&mut self) {
+        self.index.clear();
+        self.positions.clear();
+        for (position, node) in self.nodes.iter().enumerate() {
+            self.index
+                .entry(entity::identity_key(&node.node))
+                .or_insert(position);
+            self.positions.insert(node.id, position);
+            self.next_id = self.next_id.max(node.id + 1);
+        }
+    }
+
+    /// Get the node with the given id.
+    #[must_use]
+    pub fn node(&self, id: usize) -> Option<&VisualNode> {
+        self.nodes.get(*self.positions.get(&id)?)
+    }
+
+    /// Get the node with the given id to change it.
+    pub fn node_mut(&mut self, id: usize) -> Option<&mut VisualNode> {
+        self.nodes.get_mut(*self.positions.get(&id)?)
+    }
+
+    /// The id that isn't used by any node yet.
+    #[must_use]
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
+
+    /// A position for a new node that doesn't overlap the existing ones.
+    ///
+    /// New nodes are placed on a spiral, so adding many nodes at once still gives a
+    /// readable picture.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn free_position(&self) -> Point {
+        let n = self.nodes.len() as f32;
+        let angle = n * constants::NEW_NODE_ANGLE;
+        let distance = constants::NEW_NODE_SPACING * n.sqrt();
+        Point::new(
+            constants::NEW_NODE_CENTER + distance * angle.cos(),
+            constants::NEW_NODE_CENTER + distance * angle.sin(),
+        )
+    }
+
+    /// The id of the node describing the same entity, see [`entity::is_same`].
+    #[must_use]
+    pub fn find(&self, entity: &Entity) -> Option<usize> {
+        self.index
+            .get(&entity::identity_key(entity))
+            .map(|&position| self.nodes[position].id)
+    }
+
+    /// Add a node at a free position and return its id.
+    ///
+    /// Every entity is only added once: if the graph has it already, the id of
+    /// that node is returned instead, see [`entity::identity_key`].
+    pub fn add_node(&mut self, node: impl Into<Entity>) -> usize {
+        let node = node.into();
+        if let Some(id) = self.find(&node) {
+            return id;
+        }
+        let id = self.next_id;
+        let position = self.free_position();
+        self.push_node(VisualNode {
+            node,
+            id,
+            x: position.x,
+            y: position.y,
+            ..Default::default()
+        });
+        id
+    }
+
+    /// Add a node as it is, keeping its id and position even if the graph has
+    /// its entity already, e.g. while reading a file whose duplicates are
+    /// combined by [`VisualGraph::merge_duplicates`] afterwards.
+    pub fn push_node(&mut self, node: VisualNode) {
+        self.index
+            .entry(entity::identity_key(&node.node))
+            .or_insert(self.nodes.len());
+        self.positions.insert(node.id, self.nodes.len());
+        self.next_id = self.next_id.max(node.id + 1);
+        self.nodes.push(node);
+    }
+
+    /// Replace the entity of a node, e.g. after the user edited it.
+    pub fn set_entity(&mut self, id: usize, entity: Entity) {
+        if let Some(node) = self.node_mut(id) {
+            node.node = entity;
+            self.reindex();
+        }
+    }
+
+    /// Add annotations to the ones a node has already.
+    pub fn annotate(&mut self, id: usize, annotations: Annotations) {
+        if let Some(node) = self.node_mut(id) {
+            node.annotations.merge(annotations);
+        }
+    }
+
+    /// Set a property of a node, e.g. one a plugin found out, replacing the
+    /// value it had before.
+    pub fn set_property(&mut self, id: usize, name: &str, value: &str) {
+        if let Some(node) = self.node_mut(id) {
+            node.annotations
+                .properties
+                .insert(name.to_string(), value.to_string());
+        }
+    }
+
+    /// Connect two nodes.
+    pub fn add_edge(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        self.edges.push(VisualEdge {
+            from,
+            to,
+            kind,
+            weight: None,
+            time: None,
+            provenance: None,
+        });
+    }
+
+    /// Remove nodes together with their edges.
+    pub fn remove_nodes<S: BuildHasher>(&mut self, ids: &HashSet<usize, S>) {
+        self.nodes.retain(|n| !ids.contains(&n.id));
+        self.edges
+            .retain(|e| !ids.contains(&e.from) && !ids.contains(&e.to));
+        self.reindex();
+    }
+
+    /// The selected nodes and the edges between them.
+    #[must_use]
+    pub fn selection(&self) -> VisualGraph {
+        self.filter(|n| n.is_selected)
+    }
+
+    /// The nodes to keep and the edges between them.
+    #[must_use]
+    pub fn filter(&self, keep: impl Fn(&VisualNode) -> bool) -> VisualGraph {
+        let nodes: Vec<VisualNode> = self.nodes.iter().filter(|n| keep(n)).cloned().collect();
+        let ids: HashSet<usize> = nodes.iter().map(|n| n.id).collect();
+        let edges = self
+            .edges
+            .iter()
+            .filter(|e| ids.contains(&e.from) && ids.contains(&e.to))
+            .cloned()
+            .collect();
+        VisualGraph::new(nodes, edges)
+    }
+
+    /// Add the edges of another graph, whose node ids are mapped to the ones
+    /// in this graph. Edges between nodes that are connected by the same kind
+    /// of edge already only give it their weight and time.
+    fn insert_edges(&mut self, edges: Vec<VisualEdge>, ids: &HashMap<usize, usize>) {
+        let mut indices = HashMap::new();
+        for (index, edge) in self.edges.iter().enumerate() {
+            indices
+                .entry((edge.from, edge.to, edge.kind.clone()))
+                .or_insert(index);
+        }
+        for edge in edges {
+            let (Some(&from), Some(&to)) = (ids.get(&edge.from), ids.get(&edge.to)) else {
+                continue;
+            };
+            match indices.entry((from, to, edge.kind.clone())) {
+                Entry::Occupied(entry) => {
+                    let existing = &mut self.edges[*entry.get()];
+                    existing.weight = edge.weight.or(existing.weight);
+                    existing.time = edge.time.or(existing.time);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(self.edges.len());
+                    self.edges.push(VisualEdge { from, to, ..edge });
+                }
+            }
+        }
+    }
+
+    /// Add all nodes and edges of another graph to this one.
+    ///
+    /// The added nodes get new ids and are moved right of the existing nodes, so
+    /// the two graphs don't overlap. Entities the graph has already aren't added
+    /// again, their edges and annotations are added to the existing nodes
+    /// instead.
+    pub fn merge(&mut self, other: VisualGraph) {
+        let right_edge = self.nodes.iter().map(|n| n.x + n.radius).reduce(f32::max);
+        let other_left_edge = other.nodes.iter().map(|n| n.x - n.radius).reduce(f32::min);
+        let offset = match (right_edge, other_left_edge) {
+            (Some(right), Some(left)) => right - left + constants::NEW_NODE_SPACING,
+            _ => 0.0,
+        };
+
+        let mut ids = HashMap::new();
+        for mut node in other.nodes {
+            if let Some(&position) = self.index.get(&entity::identity_key(&node.node)) {
+                let existing = &mut self.nodes[position];
+                ids.insert(node.id, existing.id);
+                existing.annotations.merge(node.annotations);
+                continue;
+            }
+            let id = self.next_id;
+            ids.insert(node.id, id);
+            node.id = id;
+            node.x += offset;
+            self.push_node(node);
+        }
+        self.insert_edges(other.edges, &ids);
+    }
+
+    /// Add the nodes and edges of another graph that aren't in this one yet.
+    ///
+    /// Nodes that are already in this graph keep their position but take the
+    /// fields of the other graph, as they are usually more recent, and combine
+    /// their annotations. They keep their provenance though, like existing
+    /// edges do. New nodes are placed at free positions.
+    pub fn update(&mut self, other: VisualGraph) {
+        let mut ids = HashMap::new();
+        for node in other.nodes {
+            let id = if let Some(&position) = self.index.get(&entity::identity_key(&node.node)) {
+                let existing = &mut self.nodes[position];
+                existing.node = node.node;
+                existing.annotations.merge(node.annotations);
+                existing.id
+            } else {
+                let id = self.next_id;
+                let position = self.free_position();
+                self.push_node(VisualNode {
+                    node: node.node,
+                    id,
+                    x: position.x,
+                    y: position.y,
+                    annotations: node.annotations,
+                    provenance: node.provenance,
+                    ..Default::default()
+                });
+                id
+            };
+            ids.insert(node.id, id);
+        }
+        self.insert_edges(other.edges, &ids);
+    }
+
+    /// Combine the nodes describing the same entity, e.g. from files written
+    /// before entities were compared by their [`entity::identity_key`].
+    ///
+    /// The first node of every entity is kept and takes over the edges and
+    /// annotations of its duplicates. Generic entities also take over their
+    /// properties. Edges that become the same are combined and their weights
+    /// added up.
+    ///
+    /// Returns how many nodes were removed.
+    pub fn merge_duplicates(&mut self) -> usize {
+        // the index of the kept node of every entity
+        let mut kept: HashMap<String, usize> = HashMap::new();
+        // the ids of the removed nodes and the nodes they were merged into
+        let mut merged_into = HashMap::new();
+        let mut nodes: Vec<VisualNode> = Vec::with_capacity(self.nodes.len());
+        for node in std::mem::take(&mut self.nodes) {
+            match kept.entry(entity::identity_key(&node.node)) {
+                Entry::Occupied(entry) => {
+                    let first = &mut nodes[*entry.get()];
+                    merged_into.insert(node.id, first.id);
+                    first.is_selected |= node.is_selected;
+                    first.annotations.merge(node.annotations);
+                    first.provenance = first.provenance.take().or(node.provenance);
+                    if let (Entity::Generic(first), Entity::Generic(duplicate)) =
+                        (&mut first.node, node.node)
+                    {
+                        for property in duplicate.properties {
+                            if !first.properties.contains(&property) {
+                                first.properties.push(property);
+                            }
+                        }
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(nodes.len());
+                    nodes.push(node);
+                }
+            }
+        }
+        self.nodes = nodes;
+
+        let mut indices = HashMap::new();
+        let mut edges: Vec<VisualEdge> = Vec::with_capacity(self.edges.len());
+        for mut edge in std::mem::take(&mut self.edges) {
+            let was_loop = edge.from == edge.to;
+            edge.from = merged_into.get(&edge.from).copied().unwrap_or(edge.from);
+            edge.to = merged_into.get(&edge.to).copied().unwrap_or(edge.to);
+            // an edge between two duplicates would connect the node to itself
+            if edge.from == edge.to && !was_loop {
+                continue;
+            }
+            match indices.entry((edge.from, edge.to, edge.kind.clone())) {
+                Entry::Occupied(entry) => {
+                    let existing: &mut VisualEdge = &mut edges[*entry.get()];
+                    existing.weight = match (existing.weight, edge.weight) {
+                        (Some(a), Some(b)) => Some(a + b),
+                        (a, b) => a.or(b),
+                    };
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(edges.len());
+                    edges.push(edge);
+                }
+            }
+        }
+        self.edges = edges;
+        self.reindex();
+
+        merged_into.len()
+    }
 }
 
 impl Default for VisualGraph {
     fn default() -> Self {
-        Self {
-            nodes: vec![
+        Self::new(
+            vec![
                 VisualNode {
                     id: 0,
                     x: 10.0,
@@ -142,12 +746,24 @@ impl Default for VisualGraph {
                             to: "ftp.example.com".to_string(),
                             to_port: 21,
                         },
-                    },
+                    }
+                    .into(),
                     ..Default::default()
                 },
             ],
-            edges: vec![VisualEdge { from: 0, to: 1 }, VisualEdge { from: 1, to: 2 }],
-        }
+            vec![
+                VisualEdge {
+                    from: 0,
+                    to: 1,
+                    ..Default::default()
+                },
+                VisualEdge {
+                    from: 1,
+                    to: 2,
+                    ..Default::default()
+                },
+            ],
+        )
     }
 }
 
@@ -163,30 +779,82 @@ impl Program<Message> for GraphState {
         _cursor: mouse::Cursor,
     ) -> Vec<<iced_renderer::Renderer as canvas::Renderer>::Geometry> {
         // draw the graph
+        let connection = self
+            .path_finder
+            .as_ref()
+            .and_then(|finder| finder.result.as_ref());
+        let query_result = self
+            .query_panel
+            .as_ref()
+            .and_then(|panel| panel.result.as_ref());
+        let colors = self
+            .analytics
+            .as_ref()
+            .map(Analytics::colors)
+            .unwrap_or_default();
         let graph = self.graph_cache.draw(renderer, bounds.size(), |frame| {
             // draw all nodes
             for node in &self.graph.nodes {
+                if self.is_hidden(node.id) {
+                    continue;
+                }
                 let to_draw = Path::circle(
                     Point::new(node.x, node.y),
                     node.radius * self.zoom_factor * constants::NODE_ZOOM_SCALING,
                 );
-                frame.fill(&to_draw, Color::BLACK);
+                let is_highlighted = connection.is_some_and(|c| c.nodes.contains(&node.id))
+                    || query_result.is_some_and(|r| r.nodes.contains(&node.id));
+                // built-in entities have no type of their own and stay black
+                let entity_type = self.entity_types.type_of(&node.node);
+                let mut color = if is_highlighted {
+                    constants::HIGHLIGHT_COLOR
+                } else {
+                    colors
+                        .get(&node.id)
+                        .copied()
+                        .or(entity_type.map(|t| t.color))
+                        .unwrap_or(Color::BLACK)
+                };
+                if self.search.dims(node.id) {
+                    color.a = constants::DIMMED_ALPHA;
+                }
+                frame.fill(&to_draw, color);
+
+                self.draw_marks(frame, node, &to_draw, color.a);
             }
 
             // draw all edges
             for edge in &self.graph.edges {
-                let from_node = self.graph.nodes.iter().find(|n| n.id == edge.from).unwrap();
-                let to_node = self.graph.nodes.iter().find(|n| n.id == edge.to).unwrap();
+                if self.is_edge_hidden(edge) {
+                    continue;
+                }
+                let from_node = self.graph.node(edge.from).unwrap();
+                let to_node = self.graph.node(edge.to).unwrap();
                 let to_draw = Path::line(
                     Point::new(from_node.x, from_node.y),
                     Point::new(to_node.x, to_node.y),
                 );
 
+                // weights grow quickly, e.g. with byte counts, so they are scaled down
+                #[allow(clippy::cast_possible_truncation)]
+                let width = edge.weight.map_or(1.0, |weight| {
+                    (1.0 + weight.max(1.0).log10() as f32).min(constants::MAX_EDGE_WIDTH)
+                });
+                let is_highlighted = connection.is_some_and(|c| c.has_edge(edge))
+                    || query_result.is_some_and(|r| r.has_edge(edge.from, edge.to));
+                let mut color = if is_highlighted {
+                    constants::HIGHLIGHT_COLOR
+                } else {
+                    Color::BLACK
+                };
+                if self.search.dims(edge.from) || self.search.dims(edge.to) {
+                    color.a = constants::DIMMED_ALPHA;
+                }
                 frame.stroke(
                     &to_draw,
                     Stroke {
-                        style: stroke::Style::Solid(Color::BLACK),
-                        width: 1.0 * self.zoom_factor,
+                        style: stroke::Style::Solid(color),
+                        width: width * self.zoom_factor,
                         ..Stroke::default()
                     },
                 );
@@ -199,12 +867,22 @@ impl Program<Message> for GraphState {
         &self,
         _state: &mut Self::State,
         event: canvas::Event,
-        _bounds: Rectangle,
+        bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> (canvas::event::Status, Option<Message>) {
         // is returned if we dont do anything with the event
         let uncaptured = (canvas::event::Status::Ignored, None);
 
+        // the size of the canvas is needed to export the viewport as an image
+        if let canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) = event {
+            if bounds.size() != self.viewport {
+                return (
+                    canvas::event::Status::Ignored,
+                    Some(Message::CanvasResized(bounds.size())),
+                );
+            }
+        }
+
         // send update messages to the app
         match event {
             canvas::Event::Mouse(event) => match event {
@@ -247,7 +925,27 @@ impl Program<Message> for GraphState {
                 ),
                 _ => uncaptured,
             },
-            _ => uncaptured,
+            // pinch to zoom on touch screens
+            canvas::Event::Touch(event) => match event {
+                touch::Event::FingerPressed { id, position } => (
+                    canvas::event::Status::Captured,
+                    Some(Message::FingerPressed(id, position)),
+                ),
+                touch::Event::FingerMoved { id, position } => (
+                    canvas::event::Status::Captured,
+                    Some(Message::FingerMoved(id, position)),
+                ),
+                touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. } => (
+                    canvas::event::Status::Captured,
+                    Some(Message::FingerLifted(id)),
+                ),
+            },
+            // needed to differentiate between zooming (ctrl + scroll) and panning
+            canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => (
+                canvas::event::Status::Ignored,
+                Some(Message::ModifiersChanged(modifiers)),
+            ),
+            canvas::Event::Keyboard(_) => uncaptured,
         }
     }
 }
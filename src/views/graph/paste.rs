@@ -0,0 +1,152 @@
+use iced::widget::{button, checkbox, column, row, text, text_editor, Column};
+use iced::{Command, Element, Theme};
+use rfd::AsyncFileDialog;
+use schnuffel_types::graph::Node;
+
+use crate::entity::{self, Entity};
+use crate::error::Error;
+use crate::extract;
+use crate::views::graph::{constants, GraphState, VisualGraph};
+use crate::Message;
+
+/// The state of the dialog that finds entities in pasted text.
+#[derive(Debug, Default)]
+pub struct PasteImport {
+    pub content: text_editor::Content,
+    /// The entities found in the text and whether the user wants to add them.
+    pub found: Vec<(Node, bool)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PasteMessage {
+    Open,
+    Edit(text_editor::Action),
+    LoadFile,
+    Loaded(Result<String, Error>),
+    Toggled(usize, bool),
+    AllToggled(bool),
+    Add,
+    Cancel,
+}
+
+impl PasteImport {
+    /// Look for entities in the text again, keeping the choices of the user for
+    /// the entities that were found before.
+    fn refresh(&mut self) {
+        let previous = std::mem::take(&mut self.found);
+        self.found = extract::extract(&self.content.text())
+            .into_iter()
+            .map(|node| {
+                let selected = previous
+                    .iter()
+                    .find(|(n, _)| *n == node)
+                    .is_none_or(|(_, selected)| *selected);
+                (node, selected)
+            })
+            .collect();
+    }
+}
+
+pub fn update(state: &mut GraphState, message: PasteMessage) -> Command<Message> {
+    if let PasteMessage::Open = message {
+        state.paste_import = Some(PasteImport::default());
+        return Command::none();
+    }
+    let Some(import) = &mut state.paste_import else {
+        return Command::none();
+    };
+
+    match message {
+        // opening is handled above
+        PasteMessage::Open => {}
+        PasteMessage::Edit(action) => {
+            let is_edit = action.is_edit();
+            import.content.perform(action);
+            if is_edit {
+                import.refresh();
+            }
+        }
+        PasteMessage::LoadFile => {
+            return Command::perform(read_text_file(), |result| {
+                Message::Paste(PasteMessage::Loaded(result))
+            });
+        }
+        PasteMessage::Loaded(result) => match result {
+            Ok(text) => {
+                import.content = text_editor::Content::with_text(&text);
+                import.refresh();
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        PasteMessage::Toggled(index, selected) => {
+            if let Some((_, s)) = import.found.get_mut(index) {
+                *s = selected;
+            }
+        }
+        PasteMessage::AllToggled(selected) => {
+            for (_, s) in &mut import.found {
+                *s = selected;
+            }
+        }
+        PasteMessage::Add => {
+            let mut graph = VisualGraph::empty();
+            for (node, _) in import.found.iter().filter(|(_, selected)| *selected) {
+                graph.add_node(node.clone());
+            }
+            // entities that are already in the graph aren't added again
+            state.graph.update(graph);
+            state.paste_import = None;
+        }
+        PasteMessage::Cancel => state.paste_import = None,
+    }
+    Command::none()
+}
+
+/// Let the user pick a text file, e.g. a saved report, and read it.
+async fn read_text_file() -> Result<String, Error> {
+    let file = AsyncFileDialog::new()
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    Ok(String::from_utf8_lossy(&file.read().await).into_owned())
+}
+
+#[must_use]
+pub fn view(import: &PasteImport) -> Column<'_, Message, Theme, iced::Renderer> {
+    let selected = import.found.iter().filter(|(_, s)| *s).count();
+
+    let mut found: Vec<Element<'_, Message, Theme, iced::Renderer>> = vec![checkbox(
+        format!("{} entities found", import.found.len()),
+        !import.found.is_empty() && selected == import.found.len(),
+    )
+    .on_toggle(|s| Message::Paste(PasteMessage::AllToggled(s)))
+    .into()];
+    for (i, (node, is_selected)) in import.found.iter().enumerate() {
+        let node = Entity::Node(node.clone());
+        found.push(
+            checkbox(
+                format!("{}: {}", entity::display_name(&node), entity::label(&node)),
+                *is_selected,
+            )
+            .on_toggle(move |s| Message::Paste(PasteMessage::Toggled(i, s)))
+            .into(),
+        );
+    }
+
+    column!(
+        text("Paste text"),
+        text_editor(&import.content)
+            .on_action(|a| Message::Paste(PasteMessage::Edit(a)))
+            .height(constants::PASTE_EDITOR_HEIGHT),
+        button("Load text file").on_press(Message::Paste(PasteMessage::LoadFile)),
+        Column::from_vec(found).spacing(5),
+        row!(
+            button("Add to graph")
+                .on_press_maybe((selected > 0).then_some(Message::Paste(PasteMessage::Add))),
+            button("Cancel").on_press(Message::Paste(PasteMessage::Cancel)),
+        )
+        .spacing(5),
+    )
+    .spacing(10)
+    .padding(10)
+}
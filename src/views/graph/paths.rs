@@ -0,0 +1,134 @@
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Column};
+use iced::{Element, Theme};
+
+use crate::analysis::paths::{self, Connection, PathMode, PathOptions};
+use crate::views::graph::{EdgeKind, GraphState};
+use crate::Message;
+
+/// The state of the panel that finds connections between the selected
+/// entities.
+#[derive(Debug, Clone, Default)]
+pub struct PathFinder {
+    pub options: PathOptions,
+    /// The text of the maximum length field, which may not be a number yet.
+    pub max_length: String,
+    /// The text of the hub degree field, empty to pass through all nodes.
+    pub max_degree: String,
+    /// The connection that was found last, which is highlighted on the canvas.
+    pub result: Option<Connection>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PathMessage {
+    Open,
+    ModeSelected(PathMode),
+    MaxLengthChanged(String),
+    MaxDegreeChanged(String),
+    KindToggled(EdgeKind, bool),
+    Find,
+    Close,
+}
+
+pub fn update(state: &mut GraphState, message: PathMessage) {
+    if let PathMessage::Open = message {
+        let options = PathOptions::default();
+        state.path_finder = Some(PathFinder {
+            max_length: options.max_length.to_string(),
+            options,
+            ..PathFinder::default()
+        });
+        return;
+    }
+    let Some(finder) = &mut state.path_finder else {
+        return;
+    };
+
+    match message {
+        // opening is handled above
+        PathMessage::Open => {}
+        PathMessage::ModeSelected(mode) => finder.options.mode = mode,
+        PathMessage::MaxLengthChanged(length) => {
+            if let Ok(max_length) = length.parse() {
+                finder.options.max_length = max_length;
+            }
+            finder.max_length = length;
+        }
+        PathMessage::MaxDegreeChanged(degree) => {
+            finder.options.max_degree = degree.parse().ok();
+            finder.max_degree = degree;
+        }
+        PathMessage::KindToggled(kind, followed) => {
+            finder.options.ignored_kinds.retain(|k| *k != kind);
+            if !followed {
+                finder.options.ignored_kinds.push(kind);
+            }
+        }
+        PathMessage::Find => {
+            let selected: Vec<usize> = state
+                .graph
+                .nodes
+                .iter()
+                .filter(|n| n.is_selected)
+                .map(|n| n.id)
+                .collect();
+            let connection = paths::connect(&state.graph, &selected, &finder.options);
+            state.status = Some(if selected.len() < 2 {
+                "select at least two entities with shift + click".to_string()
+            } else if connection.paths == 0 {
+                "the selected entities aren't connected".to_string()
+            } else {
+                format!("found {} paths", connection.paths)
+            });
+            finder.result = Some(connection);
+        }
+        PathMessage::Close => state.path_finder = None,
+    }
+}
+
+#[must_use]
+pub fn view<'a>(
+    finder: &'a PathFinder,
+    state: &'a GraphState,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let mut kinds: Vec<&EdgeKind> = state.graph.edges.iter().map(|e| &e.kind).collect();
+    kinds.sort_unstable_by_key(|kind| kind.name());
+    kinds.dedup();
+    let kinds: Vec<Element<'_, Message, Theme, iced::Renderer>> = kinds
+        .into_iter()
+        .map(|kind| {
+            let followed = !finder.options.ignored_kinds.contains(kind);
+            checkbox(kind.name(), followed)
+                .on_toggle(|f| Message::Paths(PathMessage::KindToggled(kind.clone(), f)))
+                .into()
+        })
+        .collect();
+
+    let mut options = column!(
+        text("Find connections between the selected entities"),
+        pick_list(&PathMode::ALL[..], Some(finder.options.mode), |m| {
+            Message::Paths(PathMessage::ModeSelected(m))
+        }),
+    )
+    .spacing(5);
+    if finder.options.mode == PathMode::AllSimple {
+        options = options.push(
+            text_input("Maximum length", &finder.max_length)
+                .on_input(|l| Message::Paths(PathMessage::MaxLengthChanged(l))),
+        );
+    }
+
+    column!(
+        options,
+        text_input("Skip entities with more edges than", &finder.max_degree)
+            .on_input(|d| Message::Paths(PathMessage::MaxDegreeChanged(d))),
+        text("Follow edges of kind"),
+        Column::from_vec(kinds).spacing(5),
+        row!(
+            button("Find").on_press(Message::Paths(PathMessage::Find)),
+            button("Close").on_press(Message::Paths(PathMessage::Close)),
+        )
+        .spacing(5),
+    )
+    .spacing(10)
+    .padding(10)
+}
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+
+use iced::widget::{button, column, row, text, text_input, Column};
+use iced::{Command, Element, Theme};
+use rfd::AsyncFileDialog;
+use serde_json::{json, Value};
+
+use crate::analysis::query::{Query, QueryResult};
+use crate::error::Error;
+use crate::views::graph::GraphState;
+use crate::Message;
+
+/// A query the user saved under a name to run it again later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedQuery {
+    pub name: String,
+    pub query: String,
+}
+
+/// The state of the panel for querying the investigation.
+#[derive(Debug, Clone, Default)]
+pub struct QueryPanel {
+    pub query: String,
+    /// The name the query is saved under.
+    pub name: String,
+    /// The result of the last query, which is highlighted on the canvas.
+    pub result: Option<QueryResult>,
+}
+
+#[derive(Debug, Clone)]
+pub enum QueryMessage {
+    Open,
+    QueryChanged(String),
+    NameChanged(String),
+    Run,
+    Select,
+    ShowOnly,
+    ShowAll,
+    Save,
+    Load(usize),
+    Delete(usize),
+    Import,
+    Imported(Result<Vec<SavedQuery>, Error>),
+    Export,
+    Exported(Result<(), Error>),
+    Close,
+}
+
+pub fn update(state: &mut GraphState, message: QueryMessage) -> Command<Message> {
+    match message {
+        QueryMessage::Open => state.query_panel = Some(QueryPanel::default()),
+        QueryMessage::ShowAll => state.visible = None,
+        QueryMessage::Delete(index) => {
+            if index < state.saved_queries.len() {
+                state.saved_queries.remove(index);
+            }
+        }
+        QueryMessage::Import => {
+            return Command::perform(import_queries(), |result| {
+                Message::Query(QueryMessage::Imported(result))
+            });
+        }
+        QueryMessage::Imported(result) => match result {
+            Ok(queries) => {
+                for query in queries {
+                    state.saved_queries.retain(|q| q.name != query.name);
+                    state.saved_queries.push(query);
+                }
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        QueryMessage::Export => {
+            return Command::perform(export_queries(state.saved_queries.clone()), |result| {
+                Message::Query(QueryMessage::Exported(result))
+            });
+        }
+        QueryMessage::Exported(result) => state.status = result.err().map(|e| e.to_string()),
+        QueryMessage::Close => state.query_panel = None,
+        message => update_panel(state, message),
+    }
+    Command::none()
+}
+
+/// Handle the messages that need the panel to be open.
+fn update_panel(state: &mut GraphState, message: QueryMessage) {
+    let Some(panel) = &mut state.query_panel else {
+        return;
+    };
+    match message {
+        QueryMessage::QueryChanged(query) => panel.query = query,
+        QueryMessage::NameChanged(name) => panel.name = name,
+        QueryMessage::Run => match panel.query.parse::<Query>() {
+            Ok(query) => {
+                let result = query.run(&state.graph);
+                state.status = Some(format!("{} entities match", result.nodes.len()));
+                panel.result = Some(result);
+            }
+            Err(e) => {
+                state.status = Some(e.to_string());
+                panel.result = None;
+            }
+        },
+        QueryMessage::Select => {
+            if let Some(result) = &panel.result {
+                for node in &mut state.graph.nodes {
+                    node.is_selected = result.nodes.contains(&node.id);
+                }
+            }
+        }
+        QueryMessage::ShowOnly => {
+            state.visible = panel.result.as_ref().map(|r| r.nodes.clone());
+        }
+        QueryMessage::Save => {
+            let name = panel.name.trim().to_string();
+            state.saved_queries.retain(|q| q.name != name);
+            state.saved_queries.push(SavedQuery {
+                name,
+                query: panel.query.clone(),
+            });
+        }
+        QueryMessage::Load(index) => {
+            if let Some(saved) = state.saved_queries.get(index) {
+                panel.name.clone_from(&saved.name);
+                panel.query.clone_from(&saved.query);
+            }
+        }
+        // these don't need the panel
+        _ => {}
+    }
+}
+
+#[must_use]
+pub fn view<'a>(
+    panel: &'a QueryPanel,
+    state: &'a GraphState,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let has_result = panel.result.is_some();
+    let saved: Vec<Element<'_, Message, Theme, iced::Renderer>> = state
+        .saved_queries
+        .iter()
+        .enumerate()
+        .map(|(i, saved)| {
+            row!(
+                button(text(&saved.name)).on_press(Message::Query(QueryMessage::Load(i))),
+                button("Delete").on_press(Message::Query(QueryMessage::Delete(i))),
+            )
+            .spacing(5)
+            .into()
+        })
+        .collect();
+
+    column!(
+        text("Query the investigation"),
+        text_input("(e:EmailAddress)--(Domain) RETURN e", &panel.query)
+            .on_input(|q| Message::Query(QueryMessage::QueryChanged(q)))
+            .on_submit(Message::Query(QueryMessage::Run)),
+        row!(
+            button("Run").on_press(Message::Query(QueryMessage::Run)),
+            button("Select results")
+                .on_press_maybe(has_result.then_some(Message::Query(QueryMessage::Select))),
+            button("Show only results")
+                .on_press_maybe(has_result.then_some(Message::Query(QueryMessage::ShowOnly))),
+            button("Show all").on_press_maybe(
+                state
+                    .visible
+                    .is_some()
+                    .then_some(Message::Query(QueryMessage::ShowAll))
+            ),
+        )
+        .spacing(5),
+        row!(
+            text_input("Name", &panel.name)
+                .on_input(|n| Message::Query(QueryMessage::NameChanged(n))),
+            button("Save").on_press_maybe(
+                (!panel.name.trim().is_empty() && !panel.query.trim().is_empty())
+                    .then_some(Message::Query(QueryMessage::Save))
+            ),
+        )
+        .spacing(5),
+        text("Saved queries"),
+        Column::from_vec(saved).spacing(5),
+        row!(
+            button("Load queries").on_press(Message::Query(QueryMessage::Import)),
+            button("Save queries").on_press(Message::Query(QueryMessage::Export)),
+            button("Close").on_press(Message::Query(QueryMessage::Close)),
+        )
+        .spacing(5),
+    )
+    .spacing(10)
+    .padding(10)
+}
+
+/// Whether a node is shown on the canvas.
+#[must_use]
+pub fn is_visible<S: BuildHasher>(visible: Option<&HashSet<usize, S>>, id: usize) -> bool {
+    visible.is_none_or(|visible| visible.contains(&id))
+}
+
+/// Read saved queries from a JSON array of objects with a name and a query.
+///
+/// # Errors
+///
+/// Returns an error if the data isn't JSON or has no such array.
+pub fn read_queries(data: &[u8]) -> Result<Vec<SavedQuery>, Error> {
+    let json: Value = serde_json::from_slice(data)?;
+    let invalid = || Error::InvalidFormat("expected a list of saved queries".to_string());
+    json.as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|saved| {
+            let field = |name| saved.get(name).and_then(Value::as_str).map(str::to_string);
+            Ok(SavedQuery {
+                name: field("name").ok_or_else(invalid)?,
+                query: field("query").ok_or_else(invalid)?,
+            })
+        })
+        .collect()
+}
+
+/// Write saved queries as a JSON array.
+///
+/// # Errors
+///
+/// Returns an error if the JSON can't be written.
+pub fn write_queries(queries: &[SavedQuery]) -> Result<Vec<u8>, Error> {
+    let json: Vec<Value> = queries
+        .iter()
+        .map(|q| json!({ "name": q.name, "query": q.query }))
+        .collect();
+    Ok(serde_json::to_vec_pretty(&json)?)
+}
+
+/// Let the user pick a file of saved queries and read it.
+async fn import_queries() -> Result<Vec<SavedQuery>, Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter("Saved queries", &["json"])
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    read_queries(&file.read().await)
+}
+
+/// Let the user pick where to save the saved queries and write them there.
+async fn export_queries(queries: Vec<SavedQuery>) -> Result<(), Error> {
+    let data = write_queries(&queries)?;
+    let file = AsyncFileDialog::new()
+        .add_filter("Saved queries", &["json"])
+        .set_file_name("queries.json")
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    file.write(&data).await?;
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Column, Row};
+use iced::{Alignment, Element, Point, Theme};
+use regex::RegexBuilder;
+
+use crate::entity;
+use crate::views::graph::{constants, helpers, GraphState, VisualGraph, VisualNode};
+use crate::Message;
+
+/// What happens to the entities that don't match the search on the canvas.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    /// They are shown as usual, the matches are only listed.
+    #[default]
+    Show,
+    Dim,
+    Hide,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 3] = [FilterMode::Show, FilterMode::Dim, FilterMode::Hide];
+}
+
+impl Display for FilterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterMode::Show => write!(f, "Show all"),
+            FilterMode::Dim => write!(f, "Dim others"),
+            FilterMode::Hide => write!(f, "Hide others"),
+        }
+    }
+}
+
+/// The state of the search bar.
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+    pub text: String,
+    /// Whether the text is a regular expression instead of plain text.
+    pub is_regex: bool,
+    pub mode: FilterMode,
+    /// The types of entities that are left out, by their type name.
+    pub excluded_types: Vec<String>,
+    /// The ids of the matching nodes, in the order of the graph.
+    pub results: Vec<usize>,
+    /// The matching nodes, for looking them up while drawing.
+    pub matches: HashSet<usize>,
+    /// Why the search failed, e.g. a broken regular expression.
+    pub error: Option<String>,
+}
+
+impl Search {
+    /// Whether the user is searching, so non-matching entities are filtered.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty() || !self.excluded_types.is_empty()
+    }
+
+    /// Whether a node matches the search, which is true for all nodes if
+    /// there is no search.
+    #[must_use]
+    pub fn is_match(&self, id: usize) -> bool {
+        !self.is_active() || self.matches.contains(&id)
+    }
+
+    /// Whether a node isn't drawn because it doesn't match.
+    #[must_use]
+    pub fn hides(&self, id: usize) -> bool {
+        self.mode == FilterMode::Hide && !self.is_match(id)
+    }
+
+    /// Whether a node is drawn faintly because it doesn't match.
+    #[must_use]
+    pub fn dims(&self, id: usize) -> bool {
+        self.mode == FilterMode::Dim && !self.is_match(id)
+    }
+
+    /// Find the matching entities of a graph again, e.g. after the search
+    /// text or the graph changed.
+    pub fn refresh(&mut self, graph: &VisualGraph) {
+        self.error = None;
+        let is_match: Box<dyn Fn(&str) -> bool> = if self.is_regex {
+            match RegexBuilder::new(&self.text).case_insensitive(true).build() {
+                Ok(regex) => Box::new(move |value| regex.is_match(value)),
+                Err(e) => {
+                    self.error = Some(format!("invalid regular expression: {e}"));
+                    Box::new(|_| false)
+                }
+            }
+        } else {
+            let text = self.text.to_lowercase();
+            Box::new(move |value| value.to_lowercase().contains(&text))
+        };
+
+        self.results = graph
+            .nodes
+            .iter()
+            .filter(|node| {
+                !self
+                    .excluded_types
+                    .iter()
+                    .any(|t| t == entity::type_name(&node.node))
+                    && searchable_values(node).iter().any(|value| is_match(value))
+            })
+            .map(|node| node.id)
+            .collect();
+        self.matches = self.results.iter().copied().collect();
+    }
+}
+
+/// Everything about an entity that can be searched for, including what the
+/// analysts noted about it.
+fn searchable_values(node: &VisualNode) -> Vec<String> {
+    let entity = &node.node;
+    let mut values = vec![
+        entity::label(entity),
+        entity::display_name(entity).to_string(),
+    ];
+    values.extend(entity::fields(entity).into_iter().map(|(_, value)| value));
+    values.extend(node.annotations.texts());
+    values
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchMessage {
+    TextChanged(String),
+    RegexToggled(bool),
+    ModeSelected(FilterMode),
+    TypeToggled(String, bool),
+    ResultClicked(usize),
+    Clear,
+}
+
+pub fn update(state: &mut GraphState, message: SearchMessage) {
+    let search = &mut state.search;
+    match message {
+        SearchMessage::TextChanged(text) => search.text = text,
+        SearchMessage::RegexToggled(is_regex) => search.is_regex = is_regex,
+        SearchMessage::ModeSelected(mode) => search.mode = mode,
+        SearchMessage::TypeToggled(entity_type, is_included) => {
+            search.excluded_types.retain(|t| *t != entity_type);
+            if !is_included {
+                search.excluded_types.push(entity_type);
+            }
+        }
+        SearchMessage::ResultClicked(id) => {
+            jump_to(state, id);
+            return;
+        }
+        SearchMessage::Clear => *search = Search::default(),
+    }
+    search.refresh(&state.graph);
+}
+
+/// Select a node and move the canvas so it is in the middle.
+fn jump_to(state: &mut GraphState, id: usize) {
+    let Some(node) = state.graph.node(id) else {
+        return;
+    };
+    let center = Point::new(state.viewport.width / 2.0, state.viewport.height / 2.0);
+    let delta = center - Point::new(node.x, node.y);
+    helpers::pan_by(state, delta);
+    for node in &mut state.graph.nodes {
+        node.is_selected = node.id == id;
+    }
+}
+
+/// The search field and its options above the canvas.
+#[must_use]
+pub fn bar(search: &Search) -> Row<'_, Message, Theme, iced::Renderer> {
+    let placeholder = if search.is_regex {
+        "Search with a regular expression"
+    } else {
+        "Search"
+    };
+    row!(
+        text_input(placeholder, &search.text)
+            .on_input(|t| Message::Search(SearchMessage::TextChanged(t)))
+            .width(constants::SEARCH_FIELD_WIDTH),
+        checkbox("Regex", search.is_regex)
+            .on_toggle(|r| Message::Search(SearchMessage::RegexToggled(r))),
+        pick_list(&FilterMode::ALL[..], Some(search.mode), |m| {
+            Message::Search(SearchMessage::ModeSelected(m))
+        }),
+        button("Clear").on_press_maybe(
+            search
+                .is_active()
+                .then_some(Message::Search(SearchMessage::Clear))
+        ),
+        text(search.error.as_deref().unwrap_or_default()),
+    )
+    .spacing(10)
+    .align_items(Alignment::Center)
+}
+
+/// The types to search and, while searching, the matching entities, which
+/// are selected when clicked.
+#[must_use]
+pub fn results<'a>(
+    search: &'a Search,
+    graph: &'a VisualGraph,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let mut types: Vec<&str> = graph
+        .nodes
+        .iter()
+        .map(|n| entity::type_name(&n.node))
+        .chain(search.excluded_types.iter().map(String::as_str))
+        .collect();
+    types.sort_unstable();
+    types.dedup();
+    let types: Vec<Element<'_, Message, Theme, iced::Renderer>> = types
+        .into_iter()
+        .map(|entity_type| {
+            let is_included = !search.excluded_types.iter().any(|t| t == entity_type);
+            checkbox(entity_type, is_included)
+                .on_toggle(move |i| {
+                    Message::Search(SearchMessage::TypeToggled(entity_type.to_string(), i))
+                })
+                .into()
+        })
+        .collect();
+
+    let found: Vec<Element<'_, Message, Theme, iced::Renderer>> = search
+        .results
+        .iter()
+        .take(constants::SEARCH_RESULTS)
+        .filter_map(|&id| graph.node(id))
+        .map(|node| {
+            button(text(format!(
+                "{}: {}",
+                entity::display_name(&node.node),
+                entity::label(&node.node)
+            )))
+            .on_press(Message::Search(SearchMessage::ResultClicked(node.id)))
+            .into()
+        })
+        .collect();
+
+    let mut results = column!(text("Search in types"), Column::from_vec(types).spacing(5))
+        .spacing(10)
+        .padding(10);
+    if search.is_active() {
+        results = results.push(text(format!("{} matches", search.results.len())));
+        results = results.push(Column::from_vec(found).spacing(5));
+    }
+    results
+}
@@ -0,0 +1,312 @@
+use iced::widget::{button, column, pick_list, row, text, text_input, Column};
+use iced::{Command, Element, Theme};
+use rfd::AsyncFileDialog;
+
+use crate::error::Error;
+use crate::registry::{self, EntityType, PropertyKind, PropertySchema};
+use crate::views::graph::GraphState;
+use crate::Message;
+
+/// The state of the panel for defining entity types and creating entities of
+/// them.
+#[derive(Debug, Clone, Default)]
+pub struct TypesPanel {
+    /// The type of the entity to create.
+    pub entity_type: Option<String>,
+    pub value: String,
+    /// The values of the properties of the type, in the order of its schema.
+    pub properties: Vec<String>,
+    /// The name of the type to define.
+    pub name: String,
+    pub icon: String,
+    /// The color of the type to define, like `#f7931a`.
+    pub color: String,
+    /// The properties of the type to define, like `currency!, balance: number`.
+    pub schema: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum TypesMessage {
+    Open,
+    TypeSelected(String),
+    ValueChanged(String),
+    PropertyChanged(usize, String),
+    Create,
+    NameChanged(String),
+    IconChanged(String),
+    ColorChanged(String),
+    SchemaChanged(String),
+    Define,
+    Remove(String),
+    Import,
+    Imported(Result<Vec<EntityType>, Error>),
+    Export,
+    Exported(Result<(), Error>),
+    Close,
+}
+
+pub fn update(state: &mut GraphState, message: TypesMessage) -> Command<Message> {
+    match message {
+        TypesMessage::Open => state.types_panel = Some(TypesPanel::default()),
+        TypesMessage::Remove(name) => state.entity_types.types.retain(|t| t.name != name),
+        TypesMessage::Import => {
+            return Command::perform(import_types(), |result| {
+                Message::Types(TypesMessage::Imported(result))
+            });
+        }
+        TypesMessage::Imported(result) => match result {
+            Ok(types) => {
+                for entity_type in types {
+                    state.entity_types.insert(entity_type);
+                }
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        TypesMessage::Export => {
+            return Command::perform(export_types(state.entity_types.types.clone()), |result| {
+                Message::Types(TypesMessage::Exported(result))
+            });
+        }
+        TypesMessage::Exported(result) => state.status = result.err().map(|e| e.to_string()),
+        TypesMessage::Close => state.types_panel = None,
+        message => update_panel(state, message),
+    }
+    Command::none()
+}
+
+/// Handle the messages that need the panel to be open.
+fn update_panel(state: &mut GraphState, message: TypesMessage) {
+    let Some(panel) = &mut state.types_panel else {
+        return;
+    };
+    match message {
+        TypesMessage::TypeSelected(name) => {
+            let count = state
+                .entity_types
+                .get(&name)
+                .map_or(0, |t| t.properties.len());
+            panel.properties = vec![String::new(); count];
+            panel.entity_type = Some(name);
+        }
+        TypesMessage::ValueChanged(value) => panel.value = value,
+        TypesMessage::PropertyChanged(i, value) => {
+            if let Some(property) = panel.properties.get_mut(i) {
+                *property = value;
+            }
+        }
+        TypesMessage::Create => {
+            let Some(entity_type) = panel
+                .entity_type
+                .as_ref()
+                .and_then(|name| state.entity_types.get(name))
+            else {
+                return;
+            };
+            let properties: Vec<(String, String)> = entity_type
+                .properties
+                .iter()
+                .zip(&panel.properties)
+                .map(|(schema, value)| (schema.name.clone(), value.clone()))
+                .collect();
+            match entity_type.create(&panel.value, &properties) {
+                Ok(entity) => {
+                    let id = state.graph.add_node(entity);
+                    for node in &mut state.graph.nodes {
+                        node.is_selected = node.id == id;
+                    }
+                    panel.value.clear();
+                    for property in &mut panel.properties {
+                        property.clear();
+                    }
+                    state.status = None;
+                }
+                Err(e) => state.status = Some(e.to_string()),
+            }
+        }
+        TypesMessage::NameChanged(name) => panel.name = name,
+        TypesMessage::IconChanged(icon) => panel.icon = icon,
+        TypesMessage::ColorChanged(color) => panel.color = color,
+        TypesMessage::SchemaChanged(schema) => panel.schema = schema,
+        TypesMessage::Define => match define(panel) {
+            Ok(entity_type) => {
+                state.entity_types.insert(entity_type);
+                *panel = TypesPanel::default();
+                state.status = None;
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        // these don't need the panel
+        _ => {}
+    }
+}
+
+/// The type described by the fields of the panel.
+fn define(panel: &TypesPanel) -> Result<EntityType, Error> {
+    let color = if panel.color.trim().is_empty() {
+        iced::Color::BLACK
+    } else {
+        registry::color_from_hex(&panel.color).ok_or_else(|| {
+            Error::InvalidFormat(format!("{} isn't a color like #f7931a", panel.color))
+        })?
+    };
+    Ok(EntityType {
+        name: panel.name.trim().to_string(),
+        icon: panel.icon.trim().to_string(),
+        color,
+        properties: parse_schema(&panel.schema)?,
+        plugin_kind: None,
+    })
+}
+
+/// Read properties written like `currency!, balance: number`, where `!` marks
+/// required properties and properties without a kind hold text.
+fn parse_schema(schema: &str) -> Result<Vec<PropertySchema>, Error> {
+    schema
+        .split(',')
+        .map(str::trim)
+        .filter(|property| !property.is_empty())
+        .map(|property| {
+            let (name, kind) = property.split_once(':').unwrap_or((property, "text"));
+            let kind = kind.trim();
+            let required = name.trim().ends_with('!') || kind.ends_with('!');
+            let kind = kind.trim_end_matches('!');
+            Ok(PropertySchema {
+                name: name.trim().trim_end_matches('!').trim().to_string(),
+                kind: PropertyKind::from_name(kind)
+                    .ok_or_else(|| Error::InvalidFormat(format!("unknown property kind {kind}")))?,
+                required,
+            })
+        })
+        .collect()
+}
+
+#[must_use]
+pub fn view<'a>(
+    panel: &'a TypesPanel,
+    state: &'a GraphState,
+) -> Column<'a, Message, Theme, iced::Renderer> {
+    let names: Vec<String> = state
+        .entity_types
+        .types
+        .iter()
+        .map(|t| t.name.clone())
+        .collect();
+    let selected = panel
+        .entity_type
+        .as_ref()
+        .and_then(|name| state.entity_types.get(name));
+
+    let mut create = column!(
+        text("Create an entity"),
+        pick_list(names, panel.entity_type.clone(), |name| {
+            Message::Types(TypesMessage::TypeSelected(name))
+        })
+        .placeholder("Type"),
+        text_input("Value", &panel.value)
+            .on_input(|v| Message::Types(TypesMessage::ValueChanged(v))),
+    )
+    .spacing(5);
+    if let Some(entity_type) = selected {
+        for (i, (schema, value)) in entity_type
+            .properties
+            .iter()
+            .zip(&panel.properties)
+            .enumerate()
+        {
+            let placeholder = if schema.required {
+                format!("{} ({}, required)", schema.name, schema.kind)
+            } else {
+                format!("{} ({})", schema.name, schema.kind)
+            };
+            create = create.push(
+                text_input(&placeholder, value)
+                    .on_input(move |v| Message::Types(TypesMessage::PropertyChanged(i, v))),
+            );
+        }
+    }
+    create = create.push(
+        button("Add to graph").on_press_maybe(
+            (selected.is_some() && !panel.value.trim().is_empty())
+                .then_some(Message::Types(TypesMessage::Create)),
+        ),
+    );
+
+    let types: Vec<Element<'_, Message, Theme, iced::Renderer>> = state
+        .entity_types
+        .types
+        .iter()
+        .map(|t| {
+            let properties: Vec<String> = t
+                .properties
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{}{}: {}",
+                        p.name,
+                        if p.required { "!" } else { "" },
+                        p.kind
+                    )
+                })
+                .collect();
+            row!(
+                text(format!("{} {}", t.icon, t.name)).style(t.color),
+                text(properties.join(", ")),
+                button("Remove").on_press(Message::Types(TypesMessage::Remove(t.name.clone()))),
+            )
+            .spacing(5)
+            .into()
+        })
+        .collect();
+
+    column!(
+        create,
+        text("Entity types"),
+        Column::from_vec(types).spacing(5),
+        text("Define a type"),
+        row!(
+            text_input("Name", &panel.name)
+                .on_input(|n| Message::Types(TypesMessage::NameChanged(n))),
+            text_input("Icon", &panel.icon)
+                .on_input(|i| Message::Types(TypesMessage::IconChanged(i))),
+            text_input("#f7931a", &panel.color)
+                .on_input(|c| Message::Types(TypesMessage::ColorChanged(c))),
+        )
+        .spacing(5),
+        text_input("currency!, balance: number", &panel.schema)
+            .on_input(|s| Message::Types(TypesMessage::SchemaChanged(s))),
+        button("Define").on_press_maybe(
+            (!panel.name.trim().is_empty()).then_some(Message::Types(TypesMessage::Define))
+        ),
+        row!(
+            button("Load types").on_press(Message::Types(TypesMessage::Import)),
+            button("Save types").on_press(Message::Types(TypesMessage::Export)),
+            button("Close").on_press(Message::Types(TypesMessage::Close)),
+        )
+        .spacing(5),
+    )
+    .spacing(10)
+    .padding(10)
+}
+
+/// Let the user pick a file of entity types and read it.
+async fn import_types() -> Result<Vec<EntityType>, Error> {
+    let file = AsyncFileDialog::new()
+        .add_filter("Entity types", &["json"])
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    registry::read_types(&file.read().await)
+}
+
+/// Let the user pick where to save the entity types and write them there.
+async fn export_types(types: Vec<EntityType>) -> Result<(), Error> {
+    let data = registry::write_types(&types)?;
+    let file = AsyncFileDialog::new()
+        .add_filter("Entity types", &["json"])
+        .set_file_name("entity-types.json")
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+    file.write(&data).await?;
+    Ok(())
+}
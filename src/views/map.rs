@@ -0,0 +1,419 @@
+use iced::alignment::Vertical;
+use iced::keyboard::{self, Modifiers};
+use iced::mouse::{self, ScrollDelta};
+use iced::widget::canvas::{self, fill, Cache, Canvas, Fill, Path, Program, Stroke, Text};
+use iced::widget::{button, column, row, text};
+use iced::{Alignment, Color, Element, Length, Point, Rectangle, Size, Theme};
+
+use crate::entity;
+use crate::geo;
+use crate::views::graph::constants::{DIMMED_ALPHA, GROUP_COLORS, HIGHLIGHT_COLOR};
+use crate::views::graph::helpers::entities_changed;
+use crate::views::graph::{GraphState, VisualGraph};
+use crate::views::ViewState;
+use crate::Message;
+
+/// The distance between the lines of latitude and longitude, in degrees.
+const GRATICULE_STEP: f64 = 30.0;
+const OCEAN_COLOR: Color = Color::from_rgb(0.9, 0.95, 1.0);
+const LAND_COLOR: Color = Color::from_rgb(0.93, 0.92, 0.87);
+/// The outline of the land masses, see the file for its format.
+const WORLD: &str = include_str!("world.txt");
+const POINT_RADIUS: f32 = 5.0;
+const SELECTED_POINT_RADIUS: f32 = 8.0;
+/// How far from a point a click still selects it, in pixels.
+const CLICK_DISTANCE: f32 = 10.0;
+const MIN_ZOOM: f64 = 1.0;
+const MAX_ZOOM: f64 = 64.0;
+/// Zoom change per line of a mouse wheel.
+const LINE_ZOOM: f64 = 0.3;
+/// Zoom change per pixel of a pixel based scroll (most trackpads).
+const PIXEL_ZOOM: f64 = 0.01;
+
+/// An entity with known coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapPoint {
+    pub id: usize,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub label: String,
+    pub is_selected: bool,
+}
+
+/// The geolocated entities of the investigation on a map of the world.
+///
+/// Selecting entities here selects them on the canvas and the other way round.
+#[derive(Debug)]
+pub struct MapState {
+    pub cache: Cache,
+    pub points: Vec<MapPoint>,
+    /// The longitude and latitude in the middle of the map.
+    pub center: (f64, f64),
+    pub zoom: f64,
+}
+
+impl Default for MapState {
+    fn default() -> Self {
+        Self {
+            cache: Cache::default(),
+            points: Vec::new(),
+            center: (0.0, 0.0),
+            zoom: MIN_ZOOM,
+        }
+    }
+}
+
+pub struct MapStateUpdate {
+    pub graph: VisualGraph,
+}
+
+impl ViewState for MapState {
+    type UpdateType = MapStateUpdate;
+
+    fn update_state(&mut self, new: MapStateUpdate) {
+        self.points = new
+            .graph
+            .nodes
+            .iter()
+            .filter_map(|node| {
+                let (latitude, longitude) = geo::coordinates(node)?;
+                Some(MapPoint {
+                    id: node.id,
+                    latitude,
+                    longitude,
+                    label: entity::label(&node.node),
+                    is_selected: node.is_selected,
+                })
+            })
+            .collect();
+        self.cache.clear();
+    }
+}
+
+impl MapState {
+    /// Pixels per degree.
+    fn scale(&self, size: Size) -> f64 {
+        f64::from((size.width / 360.0).min(size.height / 180.0)) * self.zoom
+    }
+
+    /// The position of coordinates on a map of a size.
+    #[allow(clippy::cast_possible_truncation)]
+    fn project(&self, size: Size, latitude: f64, longitude: f64) -> Point {
+        let scale = self.scale(size);
+        Point::new(
+            size.width / 2.0 + ((longitude - self.center.0) * scale) as f32,
+            size.height / 2.0 - ((latitude - self.center.1) * scale) as f32,
+        )
+    }
+
+    /// The longitude and latitude at a position on a map of a size.
+    fn unproject(&self, size: Size, position: Point) -> (f64, f64) {
+        let scale = self.scale(size);
+        (
+            self.center.0 + f64::from(position.x - size.width / 2.0) / scale,
+            self.center.1 - f64::from(position.y - size.height / 2.0) / scale,
+        )
+    }
+
+    /// The point closest to a position, if a click there selects it.
+    fn point_at(&self, size: Size, position: Point) -> Option<usize> {
+        self.points
+            .iter()
+            .map(|p| {
+                let distance = self
+                    .project(size, p.latitude, p.longitude)
+                    .distance(position);
+                (p.id, distance)
+            })
+            .filter(|(_, distance)| *distance <= CLICK_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| id)
+    }
+
+    /// Draw the oceans, the land and the lines of latitude and longitude.
+    fn draw_graticule(&self, frame: &mut canvas::Frame) {
+        let size = frame.size();
+        let top_left = self.project(size, 90.0, -180.0);
+        let bottom_right = self.project(size, -90.0, 180.0);
+        frame.fill_rectangle(
+            top_left,
+            Size::new(bottom_right.x - top_left.x, bottom_right.y - top_left.y),
+            OCEAN_COLOR,
+        );
+        let land = Path::new(|builder| {
+            for ring in world_outline() {
+                let mut points = ring
+                    .into_iter()
+                    .map(|(longitude, latitude)| self.project(size, latitude, longitude));
+                if let Some(first) = points.next() {
+                    builder.move_to(first);
+                    for point in points {
+                        builder.line_to(point);
+                    }
+                    builder.close();
+                }
+            }
+        });
+        // lakes are rings inside the land
+        frame.fill(
+            &land,
+            Fill {
+                style: canvas::Style::Solid(LAND_COLOR),
+                rule: fill::Rule::EvenOdd,
+            },
+        );
+        let line = Stroke::default().with_color(Color {
+            a: DIMMED_ALPHA,
+            ..Color::BLACK
+        });
+        let mut degrees = -180.0;
+        while degrees <= 180.0 {
+            let from = self.project(size, 90.0, degrees);
+            let to = self.project(size, -90.0, degrees);
+            frame.stroke(&Path::line(from, to), line.clone());
+            if degrees.abs() <= 90.0 {
+                let from = self.project(size, degrees, -180.0);
+                let to = self.project(size, degrees, 180.0);
+                frame.stroke(&Path::line(from, to), line.clone());
+            }
+            degrees += GRATICULE_STEP;
+        }
+    }
+}
+
+/// The rings of the outline of the land masses, as longitudes and latitudes.
+fn world_outline() -> impl Iterator<Item = Vec<(f64, f64)>> {
+    WORLD
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.split_whitespace()
+                .filter_map(|point| {
+                    let (longitude, latitude) = point.split_once(',')?;
+                    Some((longitude.parse().ok()?, latitude.parse().ok()?))
+                })
+                .collect()
+        })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MapMessage {
+    /// Select the entity at a point, or clear the selection. Adding keeps the
+    /// entities that are selected already.
+    Select { id: Option<usize>, add: bool },
+    /// Zoom around a longitude and latitude by a factor.
+    Zoom {
+        longitude: f64,
+        latitude: f64,
+        factor: f64,
+    },
+    /// Move the center of the map by a longitude and latitude.
+    Pan(f64, f64),
+    /// Show the whole world.
+    Reset,
+}
+
+impl MapMessage {
+    /// Whether the message changes which entities are selected, after which
+    /// the map has to be updated with the changed graph.
+    #[must_use]
+    pub fn changes_entities(&self) -> bool {
+        matches!(self, MapMessage::Select { .. })
+    }
+}
+
+/// Handle a message of the map, selecting entities in the investigation held
+/// by the graph view. The map has to be updated with the changed graph
+/// afterwards if [`MapMessage::changes_entities`].
+pub fn update(map: &mut MapState, state: &mut GraphState, message: MapMessage) {
+    match message {
+        MapMessage::Select { id, add } => {
+            for node in &mut state.graph.nodes {
+                if Some(node.id) == id {
+                    node.is_selected = !(add && node.is_selected);
+                } else if !add {
+                    node.is_selected = false;
+                }
+            }
+            entities_changed(state);
+        }
+        MapMessage::Zoom {
+            longitude,
+            latitude,
+            factor,
+        } => {
+            let zoom = (map.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+            // the coordinates under the mouse stay in place
+            let scale = map.zoom / zoom;
+            map.center = (
+                longitude + (map.center.0 - longitude) * scale,
+                latitude + (map.center.1 - latitude) * scale,
+            );
+            map.zoom = zoom;
+        }
+        MapMessage::Pan(longitude, latitude) => {
+            map.center = (
+                (map.center.0 + longitude).clamp(-180.0, 180.0),
+                (map.center.1 + latitude).clamp(-90.0, 90.0),
+            );
+        }
+        MapMessage::Reset => {
+            map.center = (0.0, 0.0);
+            map.zoom = MIN_ZOOM;
+        }
+    }
+    map.cache.clear();
+}
+
+/// How the mouse is used on the map.
+#[derive(Debug, Default)]
+pub struct MapInteraction {
+    /// Where the mouse was last while it is dragging the map.
+    drag: Option<Point>,
+    /// Whether the mouse moved since it was pressed, which makes the press a
+    /// drag instead of a click.
+    moved: bool,
+    modifiers: Modifiers,
+}
+
+impl Program<Message> for MapState {
+    type State = MapInteraction;
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<<iced::Renderer as canvas::Renderer>::Geometry> {
+        let map = self.cache.draw(renderer, bounds.size(), |frame| {
+            self.draw_graticule(frame);
+            // selected points are drawn last, so they are on top
+            let (selected, others): (Vec<&MapPoint>, Vec<&MapPoint>) =
+                self.points.iter().partition(|p| p.is_selected);
+            for point in others {
+                let position = self.project(frame.size(), point.latitude, point.longitude);
+                frame.fill(&Path::circle(position, POINT_RADIUS), GROUP_COLORS[0]);
+            }
+            for point in selected {
+                let position = self.project(frame.size(), point.latitude, point.longitude);
+                frame.fill(
+                    &Path::circle(position, SELECTED_POINT_RADIUS),
+                    HIGHLIGHT_COLOR,
+                );
+                frame.fill_text(Text {
+                    content: point.label.clone(),
+                    position: Point::new(position.x + SELECTED_POINT_RADIUS * 1.5, position.y),
+                    vertical_alignment: Vertical::Center,
+                    ..Text::default()
+                });
+            }
+        });
+        vec![map]
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        let uncaptured = (canvas::event::Status::Ignored, None);
+        if let canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+            state.modifiers = modifiers;
+            return uncaptured;
+        }
+        let Some(position) = cursor.position_in(bounds) else {
+            // dragging stops when the mouse leaves the map
+            state.drag = None;
+            return uncaptured;
+        };
+        let size = bounds.size();
+        let message = match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                state.drag = Some(position);
+                state.moved = false;
+                return (canvas::event::Status::Captured, None);
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let Some(last) = state.drag.replace(position) else {
+                    return uncaptured;
+                };
+                state.moved = true;
+                let scale = self.scale(size);
+                MapMessage::Pan(
+                    f64::from(last.x - position.x) / scale,
+                    f64::from(position.y - last.y) / scale,
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.drag.take().is_none() || state.moved {
+                    return (canvas::event::Status::Captured, None);
+                }
+                // shift adds to the selection, like on the canvas of the graph
+                MapMessage::Select {
+                    id: self.point_at(size, position),
+                    add: state.modifiers.shift(),
+                }
+            }
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let (longitude, latitude) = self.unproject(size, position);
+                let amount = match delta {
+                    ScrollDelta::Lines { y, .. } => f64::from(y) * LINE_ZOOM,
+                    ScrollDelta::Pixels { y, .. } => f64::from(y) * PIXEL_ZOOM,
+                };
+                MapMessage::Zoom {
+                    longitude,
+                    latitude,
+                    factor: amount.exp(),
+                }
+            }
+            _ => return uncaptured,
+        };
+        (canvas::event::Status::Captured, Some(Message::Map(message)))
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.drag.is_some() && state.moved {
+            mouse::Interaction::Grabbing
+        } else if cursor.is_over(bounds) {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+pub fn view(map: &MapState) -> Element<'_, Message, Theme, iced::Renderer> {
+    let info = if map.points.is_empty() {
+        "No entity has coordinates yet. Load GeoIP databases and geolocate the IPs in the \
+         graph view."
+            .to_string()
+    } else {
+        format!(
+            "{} geolocated entities, {} selected. Drag to move, scroll to zoom, shift + click \
+             to select several.",
+            map.points.len(),
+            map.points.iter().filter(|p| p.is_selected).count()
+        )
+    };
+    column!(
+        row!(
+            button("Show the world").on_press(Message::Map(MapMessage::Reset)),
+            text(info),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center),
+        Canvas::new(map).width(Length::Fill).height(Length::Fill),
+    )
+    .spacing(10)
+    .padding(10)
+    .into()
+}
@@ -1,7 +1,54 @@
 pub mod graph;
+pub mod map;
+pub mod table;
+pub mod timeline;
+
+use iced::widget::{button, row, text, Row};
+use iced::Theme;
+
+use crate::analysis::timeline::TimeRange;
+use crate::Message;
 
 pub trait ViewState: Default {
     type UpdateType;
 
     fn update_state(&mut self, new: Self::UpdateType);
 }
+
+/// The views of the investigation the user can switch between.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum View {
+    #[default]
+    Graph,
+    Timeline,
+    Table,
+    Map,
+}
+
+/// The buttons switching between the views, together with the time range
+/// that filters the graph view.
+#[must_use]
+pub fn tabs(current: View, time_range: Option<TimeRange>) -> Row<'static, Message, Theme> {
+    let tab = |label, view| {
+        button(label).on_press_maybe((view != current).then_some(Message::SwitchView(view)))
+    };
+    let mut tabs = row!(
+        tab("Graph", View::Graph),
+        tab("Timeline", View::Timeline),
+        tab("Table", View::Table),
+        tab("Map", View::Map),
+    )
+    .spacing(5);
+    if let Some(range) = time_range {
+        tabs = tabs.push(text(format!(
+            "Showing {} to {}",
+            range.start.format("%Y-%m-%d %H:%M"),
+            range.end.format("%Y-%m-%d %H:%M")
+        )));
+        tabs = tabs.push(
+            button("Show all times")
+                .on_press(Message::Timeline(timeline::TimelineMessage::ClearBrush)),
+        );
+    }
+    tabs.padding(5)
+}
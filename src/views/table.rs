@@ -0,0 +1,546 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use iced::widget::{
+    button, checkbox, column, pick_list, row, scrollable, text, text_input, Column, Row,
+};
+use iced::{theme, Alignment, Command, Element, Length, Theme};
+
+use crate::annotation::Annotations;
+use crate::entity::{self, Entity};
+use crate::error::Error;
+use crate::formats::attributes;
+use crate::plugin;
+use crate::views::graph::files;
+use crate::views::graph::helpers::entities_changed;
+use crate::views::graph::{GraphState, VisualGraph, VisualNode};
+use crate::views::ViewState;
+use crate::Message;
+
+/// The choice in the type list that lists entities of every type.
+const ALL_TYPES: &str = "All types";
+/// The columns of entities of every type.
+const SHARED_COLUMNS: [&str; 4] = ["type", "label", "tags", "confidence"];
+/// The columns of annotations, after the fields of the listed type.
+const ANNOTATION_COLUMNS: [&str; 2] = ["tags", "confidence"];
+/// How many entities are listed at once.
+const PAGE_ROWS: usize = 100;
+
+/// The cell that is being edited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub id: usize,
+    pub column: String,
+    pub value: String,
+}
+
+/// The entities of the investigation as a table, for reviewing and changing
+/// many of them at once.
+///
+/// Selecting entities here selects them on the canvas and the other way round.
+#[derive(Debug, Clone)]
+pub struct TableState {
+    pub graph: VisualGraph,
+    /// The [`entity::type_name`] of the listed entities, `None` to list all.
+    pub entity_type: Option<String>,
+    /// The columns of the listed entities, which are their attributes when
+    /// only one type is listed.
+    pub columns: Vec<String>,
+    /// The column the entities are sorted by and whether they are sorted in
+    /// descending order.
+    pub sort: Option<(String, bool)>,
+    /// The ids of the listed entities, in the order they are listed.
+    pub rows: Vec<usize>,
+    pub page: usize,
+    pub edit: Option<Edit>,
+    /// The tag to add to the selected entities.
+    pub tag: String,
+    /// The URL of the plugin to run on the selected entities.
+    pub plugin_url: String,
+}
+
+impl Default for TableState {
+    fn default() -> Self {
+        Self {
+            graph: VisualGraph::empty(),
+            entity_type: None,
+            columns: Vec::new(),
+            sort: None,
+            rows: Vec::new(),
+            page: 0,
+            edit: None,
+            tag: String::new(),
+            plugin_url: String::new(),
+        }
+    }
+}
+
+pub struct TableStateUpdate {
+    pub graph: VisualGraph,
+}
+
+impl ViewState for TableState {
+    type UpdateType = TableStateUpdate;
+
+    fn update_state(&mut self, new: TableStateUpdate) {
+        self.graph = new.graph;
+        // the last entity of a type may have been deleted
+        if self.entity_type.as_ref().is_some_and(|t| {
+            !self
+                .graph
+                .nodes
+                .iter()
+                .any(|n| entity::type_name(&n.node) == t)
+        }) {
+            self.entity_type = None;
+        }
+        self.refresh();
+    }
+}
+
+impl TableState {
+    /// List the entities of the chosen type again, in the chosen order.
+    fn refresh(&mut self) {
+        let listed: Vec<&VisualNode> = self
+            .graph
+            .nodes
+            .iter()
+            .filter(|n| {
+                self.entity_type
+                    .as_ref()
+                    .is_none_or(|t| entity::type_name(&n.node) == t)
+            })
+            .collect();
+
+        self.columns = if self.entity_type.is_some() {
+            let mut columns: Vec<String> = Vec::new();
+            for node in &listed {
+                for (name, _) in attributes::node_attributes(&node.node) {
+                    if name != "type" && !columns.contains(&name) {
+                        columns.push(name);
+                    }
+                }
+            }
+            columns.extend(ANNOTATION_COLUMNS.map(String::from));
+            columns
+        } else {
+            SHARED_COLUMNS.map(String::from).to_vec()
+        };
+
+        let mut rows: Vec<(usize, String)> = listed
+            .iter()
+            .map(|node| {
+                let key = self
+                    .sort
+                    .as_ref()
+                    .map(|(column, _)| cell(node, column))
+                    .unwrap_or_default();
+                (node.id, key)
+            })
+            .collect();
+        if let Some((_, descending)) = self.sort {
+            rows.sort_by(|(_, a), (_, b)| {
+                let order = compare_cells(a, b);
+                if descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        self.rows = rows.into_iter().map(|(id, _)| id).collect();
+        self.page = self.page.min(self.pages().saturating_sub(1));
+    }
+
+    fn pages(&self) -> usize {
+        self.rows.len().div_ceil(PAGE_ROWS)
+    }
+
+    /// The ids of the entities on the current page.
+    fn page_rows(&self) -> &[usize] {
+        let start = (self.page * PAGE_ROWS).min(self.rows.len());
+        let end = (start + PAGE_ROWS).min(self.rows.len());
+        &self.rows[start..end]
+    }
+}
+
+/// The text of a cell, see [`TableState::columns`].
+fn cell(node: &VisualNode, column: &str) -> String {
+    match column {
+        "type" => entity::display_name(&node.node).to_string(),
+        "label" => entity::label(&node.node),
+        "tags" => node.annotations.tag_text(),
+        "confidence" => node.annotations.confidence.to_string(),
+        attribute => attributes::node_attributes(&node.node)
+            .into_iter()
+            .find(|(name, _)| name == attribute)
+            .map(|(_, value)| value)
+            .unwrap_or_default(),
+    }
+}
+
+/// Compare cells as numbers if both are numbers, e.g. ports, and as text
+/// ignoring case otherwise.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// Whether a column can be edited in the table. The type and the label are
+/// made of other fields and confidences are picked in the graph view.
+fn is_editable(column: &str) -> bool {
+    !matches!(column, "type" | "label" | "confidence")
+}
+
+/// The entity with one attribute changed.
+fn edited_entity(entity: &Entity, column: &str, value: &str) -> Result<Entity, Error> {
+    let mut fields: HashMap<String, String> =
+        attributes::node_attributes(entity).into_iter().collect();
+    fields.insert(column.to_string(), value.trim().to_string());
+    attributes::node_from_attributes(&fields)
+}
+
+#[derive(Debug, Clone)]
+pub enum TableMessage {
+    TypeSelected(String),
+    Sort(String),
+    Page(usize),
+    Toggle(usize, bool),
+    /// Select or deselect all listed entities.
+    ToggleAll(bool),
+    Edit(usize, String),
+    EditChanged(String),
+    CommitEdit,
+    CancelEdit,
+    TagChanged(String),
+    AddTag,
+    Delete,
+    PluginUrlChanged(String),
+    Transform,
+    Transformed(Result<VisualGraph, Error>),
+    Export,
+    Exported(Result<(), Error>),
+}
+
+impl TableMessage {
+    /// Whether the message changes the entities or which of them are selected,
+    /// after which the table has to be updated with the changed graph.
+    #[must_use]
+    pub fn changes_entities(&self) -> bool {
+        matches!(
+            self,
+            TableMessage::Toggle(..)
+                | TableMessage::ToggleAll(_)
+                | TableMessage::CommitEdit
+                | TableMessage::AddTag
+                | TableMessage::Delete
+                | TableMessage::Transformed(_)
+        )
+    }
+}
+
+/// Handle a message of the table, changing the investigation held by the graph
+/// view. The table has to be updated with the changed graph afterwards if
+/// [`TableMessage::changes_entities`].
+pub fn update(
+    table: &mut TableState,
+    state: &mut GraphState,
+    message: TableMessage,
+) -> Command<Message> {
+    let changes_entities = message.changes_entities();
+    match message {
+        TableMessage::TypeSelected(entity_type) => {
+            table.entity_type = (entity_type != ALL_TYPES).then_some(entity_type);
+            table.sort = None;
+            table.page = 0;
+            table.edit = None;
+            table.refresh();
+        }
+        TableMessage::Sort(column) => {
+            // sorting by the same column again reverses the order
+            let descending = table
+                .sort
+                .as_ref()
+                .is_some_and(|(sorted, descending)| *sorted == column && !descending);
+            table.sort = Some((column, descending));
+            table.refresh();
+        }
+        TableMessage::Page(page) => table.page = page.min(table.pages().saturating_sub(1)),
+        TableMessage::Toggle(id, selected) => {
+            if let Some(node) = state.graph.node_mut(id) {
+                node.is_selected = selected;
+            }
+        }
+        TableMessage::ToggleAll(selected) => {
+            let listed: HashSet<usize> = table.rows.iter().copied().collect();
+            for node in &mut state.graph.nodes {
+                if listed.contains(&node.id) {
+                    node.is_selected = selected;
+                }
+            }
+        }
+        TableMessage::Edit(id, column) => {
+            let value = state.graph.node(id).map(|n| cell(n, &column));
+            table.edit = value.map(|value| Edit { id, column, value });
+        }
+        TableMessage::EditChanged(value) => {
+            if let Some(edit) = &mut table.edit {
+                edit.value = value;
+            }
+        }
+        TableMessage::CommitEdit => {
+            if let Some(edit) = table.edit.take() {
+                commit(state, &edit);
+            }
+        }
+        TableMessage::CancelEdit => table.edit = None,
+        message => return update_bulk(table, state, message),
+    }
+    if changes_entities {
+        entities_changed(state);
+    }
+    Command::none()
+}
+
+/// Handle the actions on all selected entities.
+fn update_bulk(
+    table: &mut TableState,
+    state: &mut GraphState,
+    message: TableMessage,
+) -> Command<Message> {
+    let changes_entities = message.changes_entities();
+    match message {
+        TableMessage::TagChanged(tag) => table.tag = tag,
+        TableMessage::AddTag => {
+            for id in selected(state) {
+                let mut annotations = Annotations::default();
+                annotations.add_tags(&table.tag);
+                state.graph.annotate(id, annotations);
+            }
+            table.tag.clear();
+        }
+        TableMessage::Delete => {
+            let selected = selected(state);
+            state.graph.remove_nodes(&selected);
+            state.status = Some(format!("deleted {} entities", selected.len()));
+        }
+        TableMessage::PluginUrlChanged(url) => table.plugin_url = url,
+        TableMessage::Transform => {
+            let url = table.plugin_url.trim().to_string();
+            let entities: Vec<Entity> = state
+                .graph
+                .nodes
+                .iter()
+                .filter(|n| n.is_selected)
+                .map(|n| n.node.clone())
+                .collect();
+            let types = state.entity_types.clone();
+            return Command::perform(
+                async move { plugin::transform(&url, entities, types) },
+                |result| Message::Table(TableMessage::Transformed(result)),
+            );
+        }
+        TableMessage::Transformed(result) => match result {
+            Ok(graph) => {
+                state.status = Some(format!("the plugin found {} entities", graph.nodes.len()));
+                state.graph.update(graph);
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        },
+        TableMessage::Export => {
+            return Command::perform(
+                files::export_file(state.format, state.graph.selection()),
+                |result| Message::Table(TableMessage::Exported(result)),
+            );
+        }
+        TableMessage::Exported(result) => state.status = result.err().map(|e| e.to_string()),
+        // the other messages are handled by update
+        _ => {}
+    }
+    if changes_entities {
+        entities_changed(state);
+    }
+    Command::none()
+}
+
+/// The ids of the selected entities.
+fn selected(state: &GraphState) -> HashSet<usize> {
+    state
+        .graph
+        .nodes
+        .iter()
+        .filter(|n| n.is_selected)
+        .map(|n| n.id)
+        .collect()
+}
+
+/// Change a cell of an entity, keeping the entity as it is if the new value
+/// doesn't fit.
+fn commit(state: &mut GraphState, edit: &Edit) {
+    let Some(node) = state.graph.node_mut(edit.id) else {
+        return;
+    };
+    if edit.column == "tags" {
+        node.annotations.tags.clear();
+        node.annotations.add_tags(&edit.value);
+    } else {
+        match edited_entity(&node.node, &edit.column, &edit.value) {
+            Ok(entity) => {
+                state.graph.set_entity(edit.id, entity);
+                state.status = None;
+            }
+            Err(e) => state.status = Some(e.to_string()),
+        }
+    }
+}
+
+#[must_use]
+pub fn view<'a>(
+    table: &'a TableState,
+    state: &'a GraphState,
+) -> Element<'a, Message, Theme, iced::Renderer> {
+    let mut types: Vec<String> = table
+        .graph
+        .nodes
+        .iter()
+        .map(|n| entity::type_name(&n.node).to_string())
+        .collect();
+    types.sort();
+    types.dedup();
+    types.insert(0, ALL_TYPES.to_string());
+    let selected = table.graph.nodes.iter().filter(|n| n.is_selected).count();
+
+    let listing = row!(
+        pick_list(
+            types,
+            Some(
+                table
+                    .entity_type
+                    .clone()
+                    .unwrap_or_else(|| ALL_TYPES.to_string())
+            ),
+            |t| Message::Table(TableMessage::TypeSelected(t)),
+        ),
+        button("Select all").on_press(Message::Table(TableMessage::ToggleAll(true))),
+        button("Select none").on_press(Message::Table(TableMessage::ToggleAll(false))),
+        button("Previous").on_press_maybe(
+            (table.page > 0).then(|| Message::Table(TableMessage::Page(table.page - 1)))
+        ),
+        text(format!(
+            "Page {} of {}, {} entities",
+            table.page + 1,
+            table.pages().max(1),
+            table.rows.len()
+        )),
+        button("Next").on_press_maybe(
+            (table.page + 1 < table.pages())
+                .then(|| Message::Table(TableMessage::Page(table.page + 1)))
+        ),
+    )
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let has_selection = selected > 0;
+    let bulk = row!(
+        text(format!("{selected} selected")),
+        text_input("Tag", &table.tag)
+            .on_input(|t| Message::Table(TableMessage::TagChanged(t)))
+            .on_submit(Message::Table(TableMessage::AddTag))
+            .width(150),
+        button("Tag").on_press_maybe(
+            (has_selection && !table.tag.trim().is_empty())
+                .then_some(Message::Table(TableMessage::AddTag))
+        ),
+        button("Delete")
+            .on_press_maybe(has_selection.then_some(Message::Table(TableMessage::Delete))),
+        text_input("Plugin URL", &table.plugin_url)
+            .on_input(|u| Message::Table(TableMessage::PluginUrlChanged(u)))
+            .width(250),
+        button("Run transform").on_press_maybe(
+            (has_selection && !table.plugin_url.trim().is_empty())
+                .then_some(Message::Table(TableMessage::Transform))
+        ),
+        button(text(format!("Export as {}", state.format))).on_press_maybe(
+            (has_selection && state.format.exports())
+                .then_some(Message::Table(TableMessage::Export))
+        ),
+        text(state.status.as_deref().unwrap_or_default()),
+    )
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let rows: Vec<Element<'_, Message, Theme, iced::Renderer>> = table
+        .page_rows()
+        .iter()
+        .filter_map(|id| table.graph.node(*id))
+        .map(|node| table_row(table, node).into())
+        .collect();
+
+    column!(
+        listing,
+        bulk,
+        header(table),
+        scrollable(Column::from_vec(rows).spacing(2)).height(Length::Fill),
+    )
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+/// The names of the columns, which sort the entities when clicked.
+fn header(table: &TableState) -> Row<'_, Message, Theme, iced::Renderer> {
+    let mut header = row!(text("").width(30)).spacing(5);
+    for column in &table.columns {
+        let marker = match &table.sort {
+            Some((sorted, true)) if sorted == column => " ▼",
+            Some((sorted, false)) if sorted == column => " ▲",
+            _ => "",
+        };
+        header = header.push(
+            button(text(format!("{column}{marker}")))
+                .style(theme::Button::Secondary)
+                .on_press(Message::Table(TableMessage::Sort(column.clone())))
+                .width(Length::FillPortion(1)),
+        );
+    }
+    header
+}
+
+fn table_row<'a>(
+    table: &'a TableState,
+    node: &'a VisualNode,
+) -> Row<'a, Message, Theme, iced::Renderer> {
+    let id = node.id;
+    let mut cells = row!(checkbox("", node.is_selected)
+        .on_toggle(move |s| Message::Table(TableMessage::Toggle(id, s)))
+        .width(30))
+    .spacing(5)
+    .align_items(Alignment::Center);
+    for column in &table.columns {
+        let editing = table
+            .edit
+            .as_ref()
+            .filter(|edit| edit.id == id && edit.column == *column);
+        let cell: Element<'_, Message, Theme, iced::Renderer> = if let Some(edit) = editing {
+            row!(
+                text_input(column, &edit.value)
+                    .on_input(|v| Message::Table(TableMessage::EditChanged(v)))
+                    .on_submit(Message::Table(TableMessage::CommitEdit)),
+                button("Cancel").on_press(Message::Table(TableMessage::CancelEdit)),
+            )
+            .spacing(5)
+            .into()
+        } else if is_editable(column) {
+            button(text(cell(node, column)))
+                .style(theme::Button::Text)
+                .on_press(Message::Table(TableMessage::Edit(id, column.clone())))
+                .into()
+        } else {
+            text(cell(node, column)).into()
+        };
+        cells = cells.push(iced::widget::container(cell).width(Length::FillPortion(1)));
+    }
+    cells
+}
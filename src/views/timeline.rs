@@ -0,0 +1,464 @@
+use chrono::Duration;
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse::{self, ScrollDelta};
+use iced::widget::canvas::{self, Cache, Canvas, Frame, Path, Program, Stroke, Text};
+use iced::widget::{button, column, row, scrollable, text, Column};
+use iced::{Color, Element, Length, Point, Rectangle, Size, Theme};
+
+use crate::analysis::timeline::{self, Event, Subject, TimeRange};
+use crate::entity;
+use crate::views::graph::constants::{DIMMED_ALPHA, GROUP_COLORS, HIGHLIGHT_COLOR};
+use crate::views::graph::VisualGraph;
+use crate::views::ViewState;
+use crate::Message;
+
+/// The lane of the events of edges, below the lanes of the entity types.
+const RELATIONSHIP_LANE: &str = "Relationships";
+const LANE_HEIGHT: f32 = 30.0;
+/// The width of the names of the lanes left of the timeline.
+const LANE_LABEL_WIDTH: f32 = 140.0;
+const AXIS_HEIGHT: f32 = 24.0;
+const EVENT_RADIUS: f32 = 4.0;
+/// How far the mouse has to move for a drag to brush a range instead of
+/// clearing it.
+const MIN_BRUSH_WIDTH: f32 = 3.0;
+/// How many labeled ticks the axis has at most.
+const MAX_TICKS: i64 = 10;
+/// The steps between ticks, in seconds, with the format of their labels.
+const TICK_STEPS: [(i64, &str); 9] = [
+    (60, "%H:%M"),
+    (15 * 60, "%H:%M"),
+    (60 * 60, "%m-%d %H:%M"),
+    (6 * 60 * 60, "%m-%d %H:%M"),
+    (24 * 60 * 60, "%Y-%m-%d"),
+    (7 * 24 * 60 * 60, "%Y-%m-%d"),
+    (30 * 24 * 60 * 60, "%Y-%m"),
+    (365 * 24 * 60 * 60, "%Y"),
+    (10 * 365 * 24 * 60 * 60, "%Y"),
+];
+/// Zoom change per line of a mouse wheel.
+const LINE_ZOOM: f64 = 0.2;
+/// Zoom change per pixel of a pixel based scroll (most trackpads).
+const PIXEL_ZOOM: f64 = 0.01;
+/// How many times the span of all events the window can be zoomed out to.
+const MAX_ZOOM_OUT: i32 = 10;
+/// How many of the events in the brushed range are listed below the timeline.
+const LISTED_EVENTS: usize = 200;
+
+/// The entities and edges of the investigation on a timeline, where a time
+/// range can be brushed to filter the graph view.
+#[derive(Debug)]
+pub struct TimelineState {
+    pub cache: Cache,
+    pub graph: VisualGraph,
+    /// Everything in the graph with a known time, the earliest first.
+    pub events: Vec<Event>,
+    /// The entity types that have events, followed by [`RELATIONSHIP_LANE`]
+    /// if edges have times.
+    pub lanes: Vec<String>,
+    /// The part of the timeline that is shown, `None` if there are no events.
+    pub window: Option<TimeRange>,
+    /// The range that filters the graph view, `None` to show all times.
+    pub brush: Option<TimeRange>,
+}
+
+impl Default for TimelineState {
+    fn default() -> Self {
+        Self {
+            cache: Cache::default(),
+            graph: VisualGraph::empty(),
+            events: Vec::new(),
+            lanes: Vec::new(),
+            window: None,
+            brush: None,
+        }
+    }
+}
+
+pub struct TimelineStateUpdate {
+    pub graph: VisualGraph,
+}
+
+impl ViewState for TimelineState {
+    type UpdateType = TimelineStateUpdate;
+
+    fn update_state(&mut self, new: TimelineStateUpdate) {
+        self.events = timeline::events(&new.graph);
+        self.graph = new.graph;
+        self.lanes = Vec::new();
+        for event in &self.events {
+            let lane = self.lane_name(event.subject);
+            if !self.lanes.contains(&lane) {
+                self.lanes.push(lane);
+            }
+        }
+        // the relationships are always at the bottom
+        self.lanes.sort_by_key(|lane| lane == RELATIONSHIP_LANE);
+        // keep the window the user zoomed to, unless it shows nothing
+        let is_empty = self
+            .window
+            .is_none_or(|window| !self.events.iter().any(|event| window.contains(event.time)));
+        if is_empty {
+            self.window = fit(&self.events);
+        }
+        self.cache.clear();
+    }
+}
+
+impl TimelineState {
+    /// The lane the events of a subject are drawn in.
+    fn lane_name(&self, subject: Subject) -> String {
+        match subject {
+            Subject::Entity(id) => self
+                .graph
+                .node(id)
+                .map(|node| entity::display_name(&node.node).to_string())
+                .unwrap_or_default(),
+            Subject::Edge(..) => RELATIONSHIP_LANE.to_string(),
+        }
+    }
+
+    /// The events in the brushed range, or all of them without a brush.
+    fn brushed_events(&self) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .filter(|event| self.brush.is_none_or(|brush| brush.contains(event.time)))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn height(&self) -> f32 {
+        self.lanes.len() as f32 * LANE_HEIGHT + AXIS_HEIGHT
+    }
+
+    /// Draw the labeled ticks of the time axis below the lanes.
+    fn draw_axis(&self, frame: &mut Frame, window: TimeRange, width: f32) {
+        let seconds = window.duration().num_seconds().max(1);
+        let (step, format) = TICK_STEPS
+            .iter()
+            .find(|(step, _)| seconds / step <= MAX_TICKS)
+            .copied()
+            .unwrap_or(TICK_STEPS[TICK_STEPS.len() - 1]);
+        let axis = self.height() - AXIS_HEIGHT;
+        frame.stroke(
+            &Path::line(
+                Point::new(LANE_LABEL_WIDTH, axis),
+                Point::new(LANE_LABEL_WIDTH + width, axis),
+            ),
+            Stroke::default(),
+        );
+        // the ticks are at whole steps since the epoch
+        let mut tick = window.start.timestamp().div_euclid(step) * step + step;
+        while let Some(time) = chrono::DateTime::from_timestamp(tick, 0) {
+            if time > window.end {
+                break;
+            }
+            let x = x_of(window, time, width);
+            frame.stroke(
+                &Path::line(Point::new(x, 0.0), Point::new(x, axis + 4.0)),
+                Stroke::default().with_color(Color {
+                    a: DIMMED_ALPHA,
+                    ..Color::BLACK
+                }),
+            );
+            frame.fill_text(Text {
+                content: time.format(format).to_string(),
+                position: Point::new(x, axis + 6.0),
+                horizontal_alignment: Horizontal::Center,
+                ..Text::default()
+            });
+            tick += step;
+        }
+    }
+}
+
+/// The window showing all events, with some space around them.
+fn fit(events: &[Event]) -> Option<TimeRange> {
+    let span = timeline::span(events)?;
+    let padding = (span.duration() / 20).max(Duration::hours(12));
+    Some(TimeRange::new(span.start - padding, span.end + padding))
+}
+
+/// The horizontal position of a point in time on the canvas.
+#[allow(clippy::cast_possible_truncation)]
+fn x_of(window: TimeRange, time: chrono::DateTime<chrono::Utc>, width: f32) -> f32 {
+    LANE_LABEL_WIDTH + window.fraction(time) as f32 * width
+}
+
+/// Where a position on the canvas is in the window, 0 being its start and 1
+/// its end.
+fn fraction_at(x: f32, width: f32) -> f64 {
+    f64::from(((x - LANE_LABEL_WIDTH) / width).clamp(0.0, 1.0))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimelineMessage {
+    Brushed(TimeRange),
+    ClearBrush,
+    /// Zoom the window around a fraction of it, shrinking it by the factor.
+    Zoom {
+        center: f64,
+        factor: f64,
+    },
+    /// Move the window by a fraction of its width.
+    Pan(f64),
+    /// Show all events.
+    Fit,
+    /// Move the brush by its width, forwards or backwards, to replay what
+    /// happened.
+    Step(bool),
+}
+
+pub fn update(state: &mut TimelineState, message: TimelineMessage) {
+    match message {
+        TimelineMessage::Brushed(range) => state.brush = Some(range),
+        TimelineMessage::ClearBrush => state.brush = None,
+        TimelineMessage::Zoom { center, factor } => {
+            if let (Some(window), Some(all)) = (state.window, fit(&state.events)) {
+                let center = window.at(center);
+                let scale = |duration: Duration| {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+                    let milliseconds = (duration.num_milliseconds() as f64 * factor) as i64;
+                    Duration::milliseconds(milliseconds)
+                };
+                let zoomed = center
+                    .checked_sub_signed(scale(center - window.start))
+                    .zip(center.checked_add_signed(scale(window.end - center)))
+                    .map(|(start, end)| TimeRange::new(start, end));
+                // zooming in further than a minute shows nothing useful, zooming
+                // out far beyond the events only squeezes them together
+                if let Some(zoomed) = zoomed.filter(|zoomed| {
+                    zoomed.duration() >= Duration::minutes(1)
+                        && zoomed.duration() <= all.duration() * MAX_ZOOM_OUT
+                }) {
+                    state.window = Some(zoomed);
+                }
+            }
+        }
+        TimelineMessage::Pan(fraction) => {
+            // the window stays where it is at the ends of time
+            if let Some(window) = state
+                .window
+                .and_then(|window| window.shifted(window.part(fraction)))
+            {
+                state.window = Some(window);
+            }
+        }
+        TimelineMessage::Fit => state.window = fit(&state.events),
+        TimelineMessage::Step(forward) => {
+            let shifted = state.brush.and_then(|brush| {
+                if forward {
+                    brush.shifted(brush.duration())
+                } else {
+                    brush.shifted(-brush.duration())
+                }
+            });
+            if let Some(brush) = shifted {
+                state.brush = Some(brush);
+            }
+        }
+    }
+    state.cache.clear();
+}
+
+impl Program<Message> for TimelineState {
+    /// Where brushing started.
+    type State = Option<f32>;
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<<iced::Renderer as canvas::Renderer>::Geometry> {
+        let timeline = self.cache.draw(renderer, bounds.size(), |frame| {
+            let Some(window) = self.window else {
+                return;
+            };
+            let width = (bounds.width - LANE_LABEL_WIDTH).max(1.0);
+
+            if let Some(brush) = self.brush {
+                let start = x_of(window, brush.start, width).max(LANE_LABEL_WIDTH);
+                let end = x_of(window, brush.end, width).min(bounds.width);
+                if end > start {
+                    frame.fill_rectangle(
+                        Point::new(start, 0.0),
+                        Size::new(end - start, self.height() - AXIS_HEIGHT),
+                        Color {
+                            a: DIMMED_ALPHA,
+                            ..HIGHLIGHT_COLOR
+                        },
+                    );
+                }
+            }
+
+            #[allow(clippy::cast_precision_loss)]
+            let lane_center = |i: usize| (i as f32 + 0.5) * LANE_HEIGHT;
+            for (i, lane) in self.lanes.iter().enumerate() {
+                frame.fill_text(Text {
+                    content: lane.clone(),
+                    position: Point::new(0.0, lane_center(i)),
+                    vertical_alignment: Vertical::Center,
+                    ..Text::default()
+                });
+            }
+            for event in self.events.iter().filter(|e| window.contains(e.time)) {
+                let lane = self.lane_name(event.subject);
+                let Some(i) = self.lanes.iter().position(|l| *l == lane) else {
+                    continue;
+                };
+                let mut color = GROUP_COLORS[i % GROUP_COLORS.len()];
+                if self.brush.is_some_and(|brush| !brush.contains(event.time)) {
+                    color.a = DIMMED_ALPHA;
+                }
+                frame.fill(
+                    &Path::circle(
+                        Point::new(x_of(window, event.time, width), lane_center(i)),
+                        EVENT_RADIUS,
+                    ),
+                    color,
+                );
+            }
+
+            self.draw_axis(frame, window, width);
+        });
+        vec![timeline]
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        let uncaptured = (canvas::event::Status::Ignored, None);
+        // brushing goes on when the mouse leaves the canvas
+        let (Some(window), Some(position)) = (self.window, cursor.position()) else {
+            return uncaptured;
+        };
+        let position = Point::new(position.x - bounds.x, position.y - bounds.y);
+        let width = (bounds.width - LANE_LABEL_WIDTH).max(1.0);
+        let message = match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if cursor.is_over(bounds) =>
+            {
+                *state = Some(position.x);
+                return (canvas::event::Status::Captured, None);
+            }
+            // the graph view follows the brush while it is dragged
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let Some(start) = *state else {
+                    return uncaptured;
+                };
+                if (position.x - start).abs() < MIN_BRUSH_WIDTH {
+                    return uncaptured;
+                }
+                TimelineMessage::Brushed(TimeRange::new(
+                    window.at(fraction_at(start, width)),
+                    window.at(fraction_at(position.x, width)),
+                ))
+            }
+            // a click without dragging clears the brush
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let Some(start) = state.take() else {
+                    return uncaptured;
+                };
+                if (position.x - start).abs() >= MIN_BRUSH_WIDTH {
+                    return (canvas::event::Status::Captured, None);
+                }
+                TimelineMessage::ClearBrush
+            }
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta })
+                if cursor.is_over(bounds) =>
+            {
+                let center = fraction_at(position.x, width);
+                match delta {
+                    ScrollDelta::Lines { y, .. } => TimelineMessage::Zoom {
+                        center,
+                        factor: (-f64::from(y) * LINE_ZOOM).exp(),
+                    },
+                    // two finger scroll sideways on a trackpad
+                    ScrollDelta::Pixels { x, y } if x.abs() > y.abs() => {
+                        TimelineMessage::Pan(-f64::from(x / width))
+                    }
+                    ScrollDelta::Pixels { y, .. } => TimelineMessage::Zoom {
+                        center,
+                        factor: (-f64::from(y) * PIXEL_ZOOM).exp(),
+                    },
+                }
+            }
+            _ => return uncaptured,
+        };
+        (
+            canvas::event::Status::Captured,
+            Some(Message::Timeline(message)),
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+pub fn view(state: &TimelineState) -> Element<'_, Message, Theme, iced::Renderer> {
+    if state.events.is_empty() {
+        return column!(text(
+            "Nothing in the investigation has a time yet. Entities with properties \
+             like dates and edges with times show up here."
+        ))
+        .padding(10)
+        .into();
+    }
+
+    let controls = row!(
+        button("Show all").on_press(Message::Timeline(TimelineMessage::Fit)),
+        button("Step back").on_press_maybe(
+            state
+                .brush
+                .map(|_| Message::Timeline(TimelineMessage::Step(false)))
+        ),
+        button("Step forward").on_press_maybe(
+            state
+                .brush
+                .map(|_| Message::Timeline(TimelineMessage::Step(true)))
+        ),
+        text("Drag to select a time range, scroll to zoom"),
+    )
+    .spacing(5);
+
+    let events: Vec<Element<'_, Message, Theme, iced::Renderer>> = state
+        .brushed_events()
+        .take(LISTED_EVENTS)
+        .map(|event| {
+            row!(
+                text(event.time.format("%Y-%m-%d %H:%M:%S").to_string()),
+                text(&event.label),
+                text(timeline::describe(&state.graph, event.subject)),
+            )
+            .spacing(10)
+            .into()
+        })
+        .collect();
+
+    column!(
+        controls,
+        Canvas::new(state)
+            .width(Length::Fill)
+            .height(state.height()),
+        scrollable(Column::from_vec(events).spacing(5)).height(Length::Fill),
+    )
+    .spacing(10)
+    .padding(10)
+    .into()
+}